@@ -0,0 +1,314 @@
+//! A compact native binary format for matrices, for reloading large matrices quickly.
+//!
+//! A file is a 64-byte header followed by the elements in storage order:
+//!
+//! | bytes  | contents                                                      |
+//! |--------|---------------------------------------------------------------|
+//! | 0..8   | magic, `\x89MATRIX\n`                                         |
+//! | 8..10  | format version, currently 1                                   |
+//! | 10     | byte order of the data, `<` for little-endian, `>` for big    |
+//! | 11..13 | element type tag: the kind and size of a `.npy` dtype, `f` 8  |
+//! | 13     | storage order, `R` for row-major, `C` for column-major        |
+//! | 16..32 | number of rows and of columns                                 |
+//! | 32..36 | CRC-32 of the data                                            |
+//! | 36..40 | CRC-32 of bytes 0..36                                         |
+//!
+//! Header integers are little-endian, and unused bytes are zero. Starting the data at byte 64
+//! keeps it aligned for every element type when the file is memory-mapped.
+
+#[cfg(unix)]
+mod mmap;
+
+#[cfg(unix)]
+pub use mmap::{MmapMatrix, Pod};
+
+use crate::crc32::{Crc32, Crc32Reader};
+use crate::npy::NpyElement;
+use crate::transpose::from_storage;
+use crate::{checked_len, is_col_major, ColMajor, Matrix, MatrixError, Order, RowMajor};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 8] = b"\x89MATRIX\n";
+const VERSION: u16 = 1;
+const HEADER_LEN: usize = 64;
+/// Elements are encoded and checked this many at a time.
+const BLOCK: usize = 8192;
+
+/// The fields of a header, as stored.
+#[derive(Debug, PartialEq)]
+struct Header {
+    little_endian: bool,
+    kind: u8,
+    size: u8,
+    col_major: bool,
+    num_rows: usize,
+    num_cols: usize,
+    crc: u32,
+}
+
+impl Header {
+    fn encode(&self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0; HEADER_LEN];
+        bytes[..8].copy_from_slice(MAGIC);
+        bytes[8..10].copy_from_slice(&VERSION.to_le_bytes());
+        bytes[10] = if self.little_endian { b'<' } else { b'>' };
+        bytes[11] = self.kind;
+        bytes[12] = self.size;
+        bytes[13] = if self.col_major { b'C' } else { b'R' };
+        bytes[16..24].copy_from_slice(&(self.num_rows as u64).to_le_bytes());
+        bytes[24..32].copy_from_slice(&(self.num_cols as u64).to_le_bytes());
+        bytes[32..36].copy_from_slice(&self.crc.to_le_bytes());
+        let mut crc = Crc32::new();
+        crc.update(&bytes[..36]);
+        bytes[36..40].copy_from_slice(&crc.finish().to_le_bytes());
+        bytes
+    }
+
+    fn decode(bytes: &[u8; HEADER_LEN]) -> Result<Self, MatrixError> {
+        if &bytes[..8] != MAGIC {
+            return Err(invalid("not a matrix file"));
+        }
+        let version = u16::from_le_bytes([bytes[8], bytes[9]]);
+        if version != VERSION {
+            return Err(MatrixError::Unsupported(format!(
+                "matrix file format version {}",
+                version
+            )));
+        }
+        let mut crc = Crc32::new();
+        crc.update(&bytes[..36]);
+        if crc.finish().to_le_bytes() != bytes[36..40] {
+            return Err(invalid("checksum mismatch in the matrix file header"));
+        }
+
+        // Dimensions too large for `usize` are reported by `checked_len`.
+        let dim = |i: usize| {
+            let dim = u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+            usize::try_from(dim).unwrap_or(usize::MAX)
+        };
+        Ok(Header {
+            little_endian: match bytes[10] {
+                b'<' => true,
+                b'>' => false,
+                _ => return Err(invalid("invalid byte order in the matrix file header")),
+            },
+            kind: bytes[11],
+            size: bytes[12],
+            col_major: match bytes[13] {
+                b'R' => false,
+                b'C' => true,
+                _ => return Err(invalid("invalid storage order in the matrix file header")),
+            },
+            num_rows: dim(16),
+            num_cols: dim(24),
+            crc: u32::from_le_bytes(bytes[32..36].try_into().unwrap()),
+        })
+    }
+
+    /// Checks that the data has type `T` and returns its length in elements.
+    fn check<T: NpyElement>(&self) -> Result<usize, MatrixError> {
+        if self.kind != T::KIND || self.size as usize != T::SIZE {
+            return Err(MatrixError::Unsupported(format!(
+                "reading '{}{}' data into a matrix of {}",
+                self.kind as char,
+                self.size,
+                std::any::type_name::<T>()
+            )));
+        }
+        checked_len::<T>(self.num_rows, self.num_cols)
+    }
+}
+
+fn invalid(message: &str) -> MatrixError {
+    MatrixError::Io(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+impl<T: NpyElement + Send + Sync, O: Order> Matrix<T, O> {
+    /// Writes the matrix in the native binary format, little-endian and in its own storage
+    /// order.
+    pub fn save_bin<W: Write>(&self, mut writer: W) -> Result<(), MatrixError> {
+        // The checksum goes in the header, so the data is encoded once to find it and again to
+        // write it.
+        let mut buf = Vec::with_capacity(BLOCK * T::SIZE);
+        let mut crc = Crc32::new();
+        for block in self.data.chunks(BLOCK) {
+            buf.clear();
+            block.iter().for_each(|x| x.extend_le(&mut buf));
+            crc.update(&buf);
+        }
+
+        let header = Header {
+            little_endian: true,
+            kind: T::KIND,
+            size: T::SIZE as u8,
+            col_major: is_col_major::<O>(),
+            num_rows: self.num_rows,
+            num_cols: self.num_cols,
+            crc: crc.finish(),
+        };
+        writer.write_all(&header.encode())?;
+        for block in self.data.chunks(BLOCK) {
+            buf.clear();
+            block.iter().for_each(|x| x.extend_le(&mut buf));
+            writer.write_all(&buf)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn save_bin_path<P: AsRef<Path>>(&self, path: P) -> Result<(), MatrixError> {
+        self.save_bin(File::create(path)?)
+    }
+
+    /// Reads a matrix written by [`Matrix::save_bin`]. Data of either byte order is accepted,
+    /// and data stored in the other order is transposed. Fails with an `InvalidData` I/O error
+    /// if either checksum does not match.
+    pub fn load_bin<R: Read>(reader: R) -> Result<Self, MatrixError> {
+        let mut reader = Crc32Reader::new(reader);
+        let mut bytes = [0; HEADER_LEN];
+        reader.inner.read_exact(&mut bytes)?;
+        let header = Header::decode(&bytes)?;
+        let len = header.check::<T>()?;
+
+        // Anyone can write a valid header CRC, so a short file may still claim huge
+        // dimensions. The buffer grows as data arrives rather than being reserved up front.
+        let mut data = Vec::with_capacity(len.min(BLOCK));
+        let mut buf = vec![0; BLOCK * T::SIZE];
+        while data.len() < len {
+            let count = (len - data.len()).min(BLOCK);
+            let bytes = &mut buf[..count * T::SIZE];
+            reader.read_exact(bytes)?;
+            data.extend(
+                bytes
+                    .chunks_exact(T::SIZE)
+                    .map(|b| T::from_bytes(b, header.little_endian)),
+            );
+        }
+        if reader.crc.finish() != header.crc {
+            return Err(invalid("checksum mismatch in the matrix file data"));
+        }
+
+        let dims = (header.num_rows, header.num_cols);
+        Ok(if header.col_major {
+            from_storage::<T, O, ColMajor>(dims, data)
+        } else {
+            from_storage::<T, O, RowMajor>(dims, data)
+        })
+    }
+
+    pub fn load_bin_path<P: AsRef<Path>>(path: P) -> Result<Self, MatrixError> {
+        Self::load_bin(File::open(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved<T: NpyElement + Send + Sync, O: Order>(m: &Matrix<T, O>) -> Vec<u8> {
+        let mut out = Vec::new();
+        m.save_bin(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn round_trip() {
+        let m: Matrix<f64, ColMajor> = Matrix::from_csv_str("1.5,2\n-3,4\n5,6e300\n").unwrap();
+        let bytes = saved(&m);
+        assert_eq!(&bytes[..14], b"\x89MATRIX\n\x01\x00<f\x08C");
+        assert_eq!(bytes.len(), 64 + 6 * 8);
+
+        let back: Matrix<f64, ColMajor> = Matrix::load_bin(&bytes[..]).unwrap();
+        assert_eq!(back.data, m.data);
+        let rows: Matrix<f64, RowMajor> = Matrix::load_bin(&bytes[..]).unwrap();
+        assert_eq!(rows.data, vec![1.5, 2.0, -3.0, 4.0, 5.0, 6e300]);
+
+        let flags: Matrix<bool, RowMajor> = Matrix {
+            data: vec![true, false, false, true],
+            ..Matrix::new(2, 2).unwrap()
+        };
+        let back: Matrix<bool, RowMajor> = Matrix::load_bin(&saved(&flags)[..]).unwrap();
+        assert_eq!(back.data, flags.data);
+    }
+
+    #[test]
+    fn big_endian_data() {
+        let values = [1i32, -2, 3, i32::MIN];
+        let mut bytes = Header {
+            little_endian: false,
+            kind: b'i',
+            size: 4,
+            col_major: false,
+            num_rows: 2,
+            num_cols: 2,
+            crc: 0,
+        }
+        .encode()
+        .to_vec();
+        let data: Vec<u8> = values.iter().flat_map(|x| x.to_be_bytes()).collect();
+        let mut crc = Crc32::new();
+        crc.update(&data);
+        bytes[32..36].copy_from_slice(&crc.finish().to_le_bytes());
+        let mut crc = Crc32::new();
+        crc.update(&bytes[..36]);
+        bytes[36..40].copy_from_slice(&crc.finish().to_le_bytes());
+        bytes.extend(data);
+
+        let m: Matrix<i32, RowMajor> = Matrix::load_bin(&bytes[..]).unwrap();
+        assert_eq!(m.data, values);
+    }
+
+    #[test]
+    fn errors() {
+        let m: Matrix<f32, RowMajor> = Matrix::from_csv_str("1,2\n3,4\n").unwrap();
+        let bytes = saved(&m);
+        let load = |bytes: &[u8]| Matrix::<f32, RowMajor>::load_bin(bytes).err().unwrap();
+        let invalid_data = |err: MatrixError| matches!(err, MatrixError::Io(ref e) if e.kind() == io::ErrorKind::InvalidData);
+
+        assert!(matches!(
+            Matrix::<f64, RowMajor>::load_bin(&bytes[..]),
+            Err(MatrixError::Unsupported(ref msg)) if msg.contains("'f4' data into a matrix of f64")
+        ));
+
+        let mut corrupt = bytes.clone();
+        corrupt[70] ^= 1;
+        assert!(invalid_data(load(&corrupt)));
+        let mut corrupt = bytes.clone();
+        corrupt[20] ^= 1;
+        assert!(invalid_data(load(&corrupt)));
+        let mut corrupt = bytes.clone();
+        corrupt[0] = b'P';
+        assert!(invalid_data(load(&corrupt)));
+
+        let mut newer = bytes.clone();
+        newer[8] = 2;
+        assert!(matches!(load(&newer), MatrixError::Unsupported(_)));
+
+        assert!(matches!(
+            load(&bytes[..bytes.len() - 1]),
+            MatrixError::Io(ref e) if e.kind() == io::ErrorKind::UnexpectedEof
+        ));
+
+        let mut huge = Header {
+            little_endian: true,
+            kind: b'f',
+            size: 4,
+            col_major: false,
+            num_rows: 1 << 20,
+            num_cols: 1 << 20,
+            crc: 0,
+        }
+        .encode()
+        .to_vec();
+        let mut crc = Crc32::new();
+        crc.update(&huge[..36]);
+        huge[36..40].copy_from_slice(&crc.finish().to_le_bytes());
+        huge.extend_from_slice(&[0; 8]);
+        assert!(matches!(
+            load(&huge),
+            MatrixError::Io(ref e) if e.kind() == io::ErrorKind::UnexpectedEof
+        ));
+    }
+}
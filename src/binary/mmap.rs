@@ -0,0 +1,202 @@
+use super::{invalid, Header, HEADER_LEN};
+use crate::crc32::Crc32;
+use crate::npy::NpyElement;
+use crate::{is_col_major, Dimensions, MatrixError, MatrixView, Order};
+use std::fs::File;
+use std::marker::PhantomData;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+/// Element types that can be used straight from the bytes of a file.
+///
+/// # Safety
+///
+/// Every pattern of `SIZE` bytes must be a valid value, and the type must have no padding.
+pub unsafe trait Pod: NpyElement {}
+
+macro_rules! pod {
+    ($($ty:ty),*) => {
+        $(unsafe impl Pod for $ty {})*
+    };
+}
+
+pod!(i8, i16, i32, i64, u8, u16, u32, u64, f32, f64);
+
+/// A matrix file written by [`crate::Matrix::save_bin`], memory-mapped read-only.
+///
+/// Opening the file reads only its header, and elements are paged in from disk as they are
+/// used. The data must be little-endian, on a little-endian machine, and in storage order `O`;
+/// use [`crate::Matrix::load_bin`] for anything else.
+pub struct MmapMatrix<T, O> {
+    ptr: *mut libc::c_void,
+    map_len: usize,
+    num_rows: usize,
+    num_cols: usize,
+    crc: u32,
+    _marker: PhantomData<(T, O)>,
+}
+
+unsafe impl<T: Sync, O> Send for MmapMatrix<T, O> {}
+unsafe impl<T: Sync, O> Sync for MmapMatrix<T, O> {}
+
+impl<T: Pod, O: Order> MmapMatrix<T, O> {
+    /// Maps the file at `path`. The header is checked, but not the data; see
+    /// [`MmapMatrix::verify`].
+    ///
+    /// # Safety
+    ///
+    /// The file must not be truncated or written to, by this process or any other, until the
+    /// returned value is dropped. Truncation makes reads fault, and writes change memory behind
+    /// the slices and views handed out by [`MmapMatrix::as_slice`] and [`MmapMatrix::view`].
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> Result<Self, MatrixError> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut bytes = [0; HEADER_LEN];
+        std::io::Read::read_exact(&mut &file, &mut bytes)?;
+        let header = Header::decode(&bytes)?;
+        let len = header.check::<T>()?;
+
+        if !header.little_endian || cfg!(target_endian = "big") {
+            return Err(MatrixError::Unsupported(
+                "memory-mapping data whose byte order differs from the machine's".to_string(),
+            ));
+        }
+        if header.col_major != is_col_major::<O>() {
+            return Err(MatrixError::Unsupported(format!(
+                "memory-mapping {} data into the other storage order",
+                if header.col_major {
+                    "column-major"
+                } else {
+                    "row-major"
+                }
+            )));
+        }
+        if file_len != (HEADER_LEN + len * T::SIZE) as u64 {
+            return Err(invalid("the matrix file length does not match its header"));
+        }
+
+        let map_len = file_len as usize;
+        // SAFETY: the mapping is private and read-only, and is unmapped on drop. The caller
+        // guarantees that the file stays unchanged while it is mapped.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                map_len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        Ok(MmapMatrix {
+            ptr,
+            map_len,
+            num_rows: header.num_rows,
+            num_cols: header.num_cols,
+            crc: header.crc,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn dims(&self) -> Dimensions {
+        Dimensions {
+            rows: self.num_rows,
+            cols: self.num_cols,
+        }
+    }
+
+    /// Returns the elements in storage order.
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: the mapping is page-aligned and the data starts 64 bytes in, so it is
+        // aligned for `T`. Its length was checked against the header in `open`, and `T: Pod`
+        // makes any bytes a valid `T`.
+        unsafe {
+            std::slice::from_raw_parts(
+                (self.ptr as *const u8).add(HEADER_LEN) as *const T,
+                self.num_rows * self.num_cols,
+            )
+        }
+    }
+
+    pub fn view(&self) -> MatrixView<'_, T, O> {
+        MatrixView {
+            num_rows: self.num_rows,
+            num_cols: self.num_cols,
+            data: self.as_slice(),
+            _order: PhantomData,
+        }
+    }
+
+    /// Reads all the data to check it against its checksum, failing with an `InvalidData` I/O
+    /// error if it does not match.
+    pub fn verify(&self) -> Result<(), MatrixError> {
+        // SAFETY: the whole mapping is readable, and initialised bytes have no invalid values.
+        let data = unsafe {
+            std::slice::from_raw_parts(
+                (self.ptr as *const u8).add(HEADER_LEN),
+                self.map_len - HEADER_LEN,
+            )
+        };
+        let mut crc = Crc32::new();
+        crc.update(data);
+        if crc.finish() != self.crc {
+            return Err(invalid("checksum mismatch in the matrix file data"));
+        }
+        Ok(())
+    }
+}
+
+impl<T, O> Drop for MmapMatrix<T, O> {
+    fn drop(&mut self) {
+        // SAFETY: `ptr` and `map_len` describe a mapping made in `open`, and no borrows of it
+        // outlive `self`.
+        unsafe {
+            libc::munmap(self.ptr, self.map_len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColMajor, Matrix, RowMajor};
+
+    #[test]
+    fn map_saved_file() {
+        let m: Matrix<f64, ColMajor> = Matrix::from_csv_str("1,2,3\n4,5,6\n").unwrap();
+        let file = std::env::temp_dir().join(format!("mmap_{}.bin", std::process::id()));
+        m.save_bin_path(&file).unwrap();
+
+        let mapped = unsafe { MmapMatrix::<f64, ColMajor>::open(&file) }.unwrap();
+        let dims = mapped.dims();
+        assert_eq!((dims.rows, dims.cols), (2, 3));
+        assert_eq!(mapped.as_slice(), &m.data[..]);
+        assert_eq!(mapped.view()[(0, 2)], 3.0);
+        assert!(mapped.verify().is_ok());
+
+        assert!(matches!(
+            unsafe { MmapMatrix::<f64, RowMajor>::open(&file) },
+            Err(MatrixError::Unsupported(_))
+        ));
+        assert!(matches!(
+            unsafe { MmapMatrix::<i64, ColMajor>::open(&file) },
+            Err(MatrixError::Unsupported(_))
+        ));
+        drop(mapped);
+
+        let mut bytes = std::fs::read(&file).unwrap();
+        bytes[HEADER_LEN] ^= 1;
+        std::fs::write(&file, &bytes).unwrap();
+        let mapped = unsafe { MmapMatrix::<f64, ColMajor>::open(&file) }.unwrap();
+        assert!(mapped.verify().is_err());
+        drop(mapped);
+
+        std::fs::write(&file, &bytes[..bytes.len() - 8]).unwrap();
+        assert!(unsafe { MmapMatrix::<f64, ColMajor>::open(&file) }.is_err());
+        std::fs::remove_file(&file).unwrap();
+    }
+}
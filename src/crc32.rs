@@ -0,0 +1,151 @@
+//! The CRC-32 used by zip and PNG, with the reflected polynomial `0xEDB88320`, computed eight
+//! bytes at a time.
+
+use std::io::{self, Read, Write};
+
+/// `TABLES[0]` is the usual byte-at-a-time table, and `TABLES[k][b]` is the CRC of byte `b`
+/// followed by `k` zero bytes.
+const TABLES: [[u32; 256]; 8] = {
+    let mut tables = [[0; 256]; 8];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        tables[0][i] = crc;
+        i += 1;
+    }
+
+    let mut k = 1;
+    while k < 8 {
+        let mut i = 0;
+        while i < 256 {
+            let prev = tables[k - 1][i];
+            tables[k][i] = (prev >> 8) ^ tables[0][(prev & 0xff) as usize];
+            i += 1;
+        }
+        k += 1;
+    }
+    tables
+};
+
+pub(crate) struct Crc32(u32);
+
+impl Crc32 {
+    pub(crate) fn new() -> Self {
+        Crc32(!0)
+    }
+
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        let mut crc = self.0;
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            let lo = crc ^ u32::from_le_bytes(chunk[..4].try_into().unwrap());
+            let hi = u32::from_le_bytes(chunk[4..].try_into().unwrap());
+            crc = TABLES[7][(lo & 0xff) as usize]
+                ^ TABLES[6][((lo >> 8) & 0xff) as usize]
+                ^ TABLES[5][((lo >> 16) & 0xff) as usize]
+                ^ TABLES[4][(lo >> 24) as usize]
+                ^ TABLES[3][(hi & 0xff) as usize]
+                ^ TABLES[2][((hi >> 8) & 0xff) as usize]
+                ^ TABLES[1][((hi >> 16) & 0xff) as usize]
+                ^ TABLES[0][(hi >> 24) as usize];
+        }
+        for &b in chunks.remainder() {
+            crc = TABLES[0][((crc ^ b as u32) & 0xff) as usize] ^ (crc >> 8);
+        }
+        self.0 = crc;
+    }
+
+    pub(crate) fn finish(&self) -> u32 {
+        !self.0
+    }
+}
+
+/// Passes writes through, keeping the CRC and count of the bytes written.
+pub(crate) struct Crc32Writer<W> {
+    pub(crate) inner: W,
+    pub(crate) crc: Crc32,
+    pub(crate) len: u64,
+}
+
+impl<W> Crc32Writer<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        Crc32Writer {
+            inner,
+            crc: Crc32::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<W: Write> Write for Crc32Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.crc.update(&buf[..n]);
+        self.len += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Passes reads through, keeping the CRC of the bytes read.
+pub(crate) struct Crc32Reader<R> {
+    pub(crate) inner: R,
+    pub(crate) crc: Crc32,
+}
+
+impl<R> Crc32Reader<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Crc32Reader {
+            inner,
+            crc: Crc32::new(),
+        }
+    }
+}
+
+impl<R: Read> Read for Crc32Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.crc.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_values() {
+        let mut crc = Crc32::new();
+        crc.update(b"123456789");
+        assert_eq!(crc.finish(), 0xCBF4_3926);
+
+        // Every split of the input gives the same CRC as the bitwise definition.
+        let bytes: Vec<u8> = (0..100u32).map(|i| (i * 37 % 251) as u8).collect();
+        let mut expected = !0u32;
+        for &b in &bytes {
+            expected ^= b as u32;
+            for _ in 0..8 {
+                expected = (expected >> 1) ^ (0xEDB8_8320 & (expected & 1).wrapping_neg());
+            }
+        }
+        for split in [0, 1, 7, 8, 9, 63, 100] {
+            let mut crc = Crc32::new();
+            crc.update(&bytes[..split]);
+            crc.update(&bytes[split..]);
+            assert_eq!(crc.finish(), !expected);
+        }
+    }
+}
@@ -0,0 +1,830 @@
+//! Reading and writing matrices as delimited text.
+//!
+//! Records come from the `csv` crate for single-byte delimiters and from a line splitter for
+//! whitespace, and every field is then parsed on its own, so that errors can point at the
+//! exact line and field. Whole matrices are loaded by [`chunked`], which parses plain input in
+//! parallel and leaves everything else to the streaming reader.
+
+mod chunked;
+mod float;
+mod stream;
+
+pub use stream::CsvRowStream;
+
+use crate::{Matrix, MatrixError, Order, Scalar};
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use std::error::Error;
+use std::fmt::{self, Write as _};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+
+/// How the fields of a record are separated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delimiter {
+    /// A single byte such as `b','`, `b'\t'` or `b';'`. Fields may be quoted.
+    Byte(u8),
+    /// Runs of spaces and tabs. Leading and trailing whitespace is ignored.
+    Whitespace,
+    /// Guessed from the first line that is not empty or a comment: the most frequent of comma,
+    /// tab and semicolon, or whitespace if none of them appears.
+    Auto,
+}
+
+impl From<u8> for Delimiter {
+    fn from(delimiter: u8) -> Self {
+        Delimiter::Byte(delimiter)
+    }
+}
+
+/// Options for reading delimited text, set with chained builder calls on
+/// [`CsvOptions::new`].
+///
+/// The defaults read the same files as `Matrix::from_file`: comma-separated, with no header,
+/// no comments, no trimming and no missing values.
+#[derive(Debug, Clone)]
+pub struct CsvOptions<T> {
+    delimiter: Delimiter,
+    has_header: bool,
+    comment: Option<u8>,
+    trim: bool,
+    missing: Vec<String>,
+    fill: Option<T>,
+}
+
+impl<T> Default for CsvOptions<T> {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: Delimiter::Byte(b','),
+            has_header: false,
+            comment: None,
+            trim: false,
+            missing: Vec::new(),
+            fill: None,
+        }
+    }
+}
+
+impl<T> CsvOptions<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn delimiter(mut self, delimiter: impl Into<Delimiter>) -> Self {
+        self.delimiter = delimiter.into();
+        self
+    }
+
+    /// Treats the first record as a header, which `Matrix::from_csv_with` skips and
+    /// `Matrix::from_csv_with_header` returns.
+    pub fn has_header(mut self, has_header: bool) -> Self {
+        self.has_header = has_header;
+        self
+    }
+
+    /// Skips lines that start with `comment`, typically `Some(b'#')`.
+    pub fn comment(mut self, comment: Option<u8>) -> Self {
+        self.comment = comment;
+        self
+    }
+
+    /// Strips leading and trailing whitespace from every field before parsing it.
+    pub fn trim(mut self, trim: bool) -> Self {
+        self.trim = trim;
+        self
+    }
+
+    /// Reads every field equal to one of `tokens` as `fill`. An empty token matches empty
+    /// fields. With trimming enabled, fields are compared after trimming.
+    pub fn missing_values<S: Into<String>>(
+        mut self,
+        tokens: impl IntoIterator<Item = S>,
+        fill: T,
+    ) -> Self {
+        self.missing = tokens.into_iter().map(Into::into).collect();
+        self.fill = Some(fill);
+        self
+    }
+}
+
+/// How numbers are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberFormat {
+    /// The `Display` output, which for floats is the shortest text that reads back to the
+    /// same value.
+    Shortest,
+    /// A fixed number of digits after the decimal point, as with `{:.N}`.
+    Fixed(usize),
+    /// Scientific notation as with `{:e}`, optionally with a fixed number of digits after the
+    /// decimal point.
+    Scientific(Option<usize>),
+}
+
+/// Options for writing delimited text, set with chained builder calls on
+/// [`CsvWriteOptions::new`].
+///
+/// The defaults write comma-separated values with no header, in the shortest form that reads
+/// back exactly.
+#[derive(Debug, Clone)]
+pub struct CsvWriteOptions {
+    delimiter: u8,
+    header: Option<Vec<String>>,
+    format: NumberFormat,
+}
+
+impl Default for CsvWriteOptions {
+    fn default() -> Self {
+        CsvWriteOptions {
+            delimiter: b',',
+            header: None,
+            format: NumberFormat::Shortest,
+        }
+    }
+}
+
+impl CsvWriteOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Writes `names` as a header row. There must be one name per column.
+    pub fn header<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.header = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn format(mut self, format: NumberFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+impl<T: Scalar + Default + Send + Sync + for<'a> Deserialize<'a>, O: Order> Matrix<T, O> {
+    /// Reads a comma-separated matrix from any reader, such as stdin, a socket or a
+    /// decompressor.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, MatrixError> {
+        Self::from_csv_with(reader, &CsvOptions::default())
+    }
+
+    pub fn from_csv_str(text: &str) -> Result<Self, MatrixError> {
+        Ok(chunked::read_matrix(text.as_bytes(), &CsvOptions::default(), false)?.0)
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, MatrixError> {
+        let bytes = std::fs::read(path)?;
+        Ok(chunked::read_matrix(&bytes, &CsvOptions::default(), false)?.0)
+    }
+
+    pub fn from_file(file: &mut File) -> Result<Self, MatrixError> {
+        Self::from_reader(file)
+    }
+
+    /// Reads a matrix from delimited text as configured by `opts`.
+    ///
+    /// The whole input is read into memory and then parsed in chunks, on several threads with
+    /// the `parallel` feature, straight into the matrix's storage order. Use
+    /// [`CsvRowStream`] to read a large input a block at a time instead.
+    pub fn from_csv_with<R: Read>(
+        mut reader: R,
+        opts: &CsvOptions<T>,
+    ) -> Result<Self, MatrixError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Ok(chunked::read_matrix(&bytes, opts, opts.has_header)?.0)
+    }
+
+    /// Like `from_csv_with`, but also returns the first record as column names, whether or
+    /// not `opts` has a header.
+    pub fn from_csv_with_header<R: Read>(
+        mut reader: R,
+        opts: &CsvOptions<T>,
+    ) -> Result<(Self, Vec<String>), MatrixError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let (m, header) = chunked::read_matrix(&bytes, opts, true)?;
+        Ok((m, header.unwrap_or_default()))
+    }
+}
+
+impl<T: fmt::Display + fmt::LowerExp, O: Order> Matrix<T, O> {
+    /// Writes the matrix as comma-separated values, one line per row whatever the storage
+    /// order.
+    pub fn to_writer<W: Write>(&self, writer: W) -> Result<(), MatrixError> {
+        self.to_writer_with(writer, &CsvWriteOptions::default())
+    }
+
+    pub fn to_writer_with<W: Write>(
+        &self,
+        writer: W,
+        opts: &CsvWriteOptions,
+    ) -> Result<(), MatrixError> {
+        if let Some(names) = &opts.header {
+            if names.len() != self.num_cols {
+                return Err(MatrixError::DimensionMismatch {
+                    expected: (1, self.num_cols),
+                    found: (1, names.len()),
+                });
+            }
+        }
+
+        let mut wtr = csv::WriterBuilder::new()
+            .delimiter(opts.delimiter)
+            .from_writer(writer);
+        if let Some(names) = &opts.header {
+            wtr.write_record(names)?;
+        }
+
+        let mut buf = String::new();
+        for i in 0..self.num_rows {
+            for j in 0..self.num_cols {
+                buf.clear();
+                write_number(&mut buf, &self[(i, j)], opts.format);
+                wtr.write_field(&buf)?;
+            }
+            wtr.write_record(None::<&[u8]>)?;
+        }
+
+        wtr.flush()?;
+        Ok(())
+    }
+
+    pub fn to_path<P: AsRef<Path>>(&self, path: P) -> Result<(), MatrixError> {
+        self.to_writer(File::create(path)?)
+    }
+
+    pub fn to_path_with<P: AsRef<Path>>(
+        &self,
+        path: P,
+        opts: &CsvWriteOptions,
+    ) -> Result<(), MatrixError> {
+        self.to_writer_with(File::create(path)?, opts)
+    }
+
+    /// Formats the matrix as delimited text in memory.
+    pub fn to_csv(&self, opts: &CsvWriteOptions) -> Result<String, MatrixError> {
+        let mut out = Vec::new();
+        self.to_writer_with(&mut out, opts)?;
+        Ok(String::from_utf8_lossy(&out).into_owned())
+    }
+}
+
+/// Appends `x` to `buf` in the given format.
+pub(crate) fn write_number<T: fmt::Display + fmt::LowerExp>(
+    buf: &mut String,
+    x: &T,
+    format: NumberFormat,
+) {
+    // Writing into a `String` cannot fail.
+    let _ = match format {
+        NumberFormat::Shortest => write!(buf, "{}", x),
+        NumberFormat::Fixed(digits) => write!(buf, "{:.*}", digits, x),
+        NumberFormat::Scientific(None) => write!(buf, "{:e}", x),
+        NumberFormat::Scientific(Some(digits)) => write!(buf, "{:.*e}", digits, x),
+    };
+}
+
+/// A parsed table in row-major order.
+pub(crate) struct CsvData<T> {
+    pub(crate) data: Vec<T>,
+    pub(crate) num_rows: usize,
+    pub(crate) num_cols: usize,
+    pub(crate) header: Option<Vec<String>>,
+}
+
+/// Reads delimited text into row-major storage, taking the first record as a header if
+/// `has_header` is set. Every record, the header included, must have the same number of
+/// fields, and there must be at least one record of data.
+pub(crate) fn read_csv_data<T: for<'a> Deserialize<'a> + Clone, R: Read>(
+    reader: R,
+    opts: &CsvOptions<T>,
+    has_header: bool,
+) -> Result<CsvData<T>, MatrixError> {
+    let mut records = RecordReader::new(reader, opts)?;
+
+    let header = if has_header {
+        records.read_header(opts.trim)?
+    } else {
+        None
+    };
+    let mut width = header.as_ref().map(Vec::len);
+
+    let mut num_rows = 0;
+    let mut data = Vec::new();
+
+    while let Some(line) = records.next_record()? {
+        let record = records.record();
+        let expected = *width.get_or_insert(record.len());
+        if record.len() != expected {
+            return Err(MatrixError::RaggedRow {
+                line,
+                expected,
+                found: record.len(),
+            });
+        }
+
+        parse_record(record, line, opts, &mut data)?;
+        num_rows += 1;
+    }
+
+    if num_rows == 0 {
+        return Err(MatrixError::ZeroDimension { rows: 0, cols: 0 });
+    }
+
+    Ok(CsvData {
+        data,
+        num_rows,
+        num_cols: width.unwrap_or(0),
+        header,
+    })
+}
+
+/// Parses every field of `record`, found at `line`, onto the end of `out`.
+pub(crate) fn parse_record<T: for<'a> Deserialize<'a> + Clone>(
+    record: &csv::ByteRecord,
+    line: usize,
+    opts: &CsvOptions<T>,
+    out: &mut Vec<T>,
+) -> Result<(), MatrixError> {
+    for j in 0..record.len() {
+        out.push(parse_field_at(record, j, line, opts)?);
+    }
+
+    Ok(())
+}
+
+/// Parses field `j` of `record`, found at `line`.
+pub(crate) fn parse_field_at<T: for<'a> Deserialize<'a> + Clone>(
+    record: &csv::ByteRecord,
+    j: usize,
+    line: usize,
+    opts: &CsvOptions<T>,
+) -> Result<T, MatrixError> {
+    let field = if opts.trim {
+        record[j].trim_ascii()
+    } else {
+        &record[j]
+    };
+
+    parse_field(field, opts).map_err(|source| MatrixError::Parse {
+        line,
+        column: j + 1,
+        text: String::from_utf8_lossy(field).into_owned(),
+        source,
+    })
+}
+
+fn parse_field<T: for<'a> Deserialize<'a> + Clone>(
+    field: &[u8],
+    opts: &CsvOptions<T>,
+) -> Result<T, Box<dyn Error + Send + Sync>> {
+    if let Some(fill) = &opts.fill {
+        if opts.missing.iter().any(|token| token.as_bytes() == field) {
+            return Ok(fill.clone());
+        }
+    }
+
+    let text = std::str::from_utf8(field)?;
+    Ok(T::deserialize(FieldDeserializer(text))?)
+}
+
+/// Splits delimited text into records, skipping empty lines and comments.
+pub(crate) struct RecordReader<R: Read> {
+    source: Source<R>,
+    record: csv::ByteRecord,
+}
+
+enum Source<R: Read> {
+    Csv(csv::Reader<BufReader<R>>),
+    Whitespace {
+        reader: BufReader<R>,
+        comment: Option<u8>,
+        line: Vec<u8>,
+        line_no: usize,
+    },
+}
+
+impl<R: Read> RecordReader<R> {
+    pub(crate) fn new<T>(reader: R, opts: &CsvOptions<T>) -> Result<Self, MatrixError> {
+        let mut reader = BufReader::new(reader);
+        let delimiter = match opts.delimiter {
+            Delimiter::Auto => sniff_delimiter(reader.fill_buf()?, opts.comment),
+            delimiter => delimiter,
+        };
+
+        let source = match delimiter {
+            Delimiter::Byte(delimiter) => Source::Csv(
+                csv::ReaderBuilder::new()
+                    .has_headers(false)
+                    .flexible(true)
+                    .delimiter(delimiter)
+                    .comment(opts.comment)
+                    .from_reader(reader),
+            ),
+            _ => Source::Whitespace {
+                reader,
+                comment: opts.comment,
+                line: Vec::new(),
+                line_no: 0,
+            },
+        };
+
+        Ok(RecordReader {
+            source,
+            record: csv::ByteRecord::new(),
+        })
+    }
+
+    /// Advances to the next record and returns the line it starts on, or `None` at the end of
+    /// the input.
+    pub(crate) fn next_record(&mut self) -> Result<Option<usize>, MatrixError> {
+        match &mut self.source {
+            Source::Csv(rdr) => {
+                if !rdr.read_byte_record(&mut self.record)? {
+                    return Ok(None);
+                }
+                Ok(Some(
+                    self.record.position().map_or(0, |pos| pos.line() as usize),
+                ))
+            }
+            Source::Whitespace {
+                reader,
+                comment,
+                line,
+                line_no,
+            } => loop {
+                line.clear();
+                if reader.read_until(b'\n', line)? == 0 {
+                    return Ok(None);
+                }
+                *line_no += 1;
+
+                let mut fields = line
+                    .split(|b| b.is_ascii_whitespace())
+                    .filter(|field| !field.is_empty())
+                    .peekable();
+                match fields.peek() {
+                    None => continue,
+                    Some(first) if Some(first[0]) == *comment => continue,
+                    Some(_) => {}
+                }
+
+                self.record.clear();
+                fields.for_each(|field| self.record.push_field(field));
+                return Ok(Some(*line_no));
+            },
+        }
+    }
+
+    pub(crate) fn record(&self) -> &csv::ByteRecord {
+        &self.record
+    }
+
+    /// Reads the next record as column names, or returns `None` at the end of the input.
+    pub(crate) fn read_header(&mut self, trim: bool) -> Result<Option<Vec<String>>, MatrixError> {
+        if self.next_record()?.is_none() {
+            return Ok(None);
+        }
+
+        let names = self
+            .record
+            .iter()
+            .map(|field| {
+                let field = if trim { field.trim_ascii() } else { field };
+                String::from_utf8_lossy(field).into_owned()
+            })
+            .collect();
+        Ok(Some(names))
+    }
+}
+
+/// Guesses the delimiter from the first line of `buf` that is not empty or a comment.
+fn sniff_delimiter(buf: &[u8], comment: Option<u8>) -> Delimiter {
+    let line = buf
+        .split(|&b| b == b'\n')
+        .map(<[u8]>::trim_ascii)
+        .find(|line| !line.is_empty() && Some(line[0]) != comment)
+        .unwrap_or_default();
+
+    let (count, delimiter) = [b',', b'\t', b';']
+        .into_iter()
+        .map(|d| (line.iter().filter(|&&b| b == d).count(), d))
+        .fold((0, b','), |best, candidate| {
+            if candidate.0 > best.0 {
+                candidate
+            } else {
+                best
+            }
+        });
+
+    if count > 0 {
+        Delimiter::Byte(delimiter)
+    } else if line.iter().any(u8::is_ascii_whitespace) {
+        Delimiter::Whitespace
+    } else {
+        Delimiter::Byte(b',')
+    }
+}
+
+/// Deserializes a scalar from the text of a single field.
+pub(crate) struct FieldDeserializer<'a>(pub(crate) &'a str);
+
+#[derive(Debug)]
+pub(crate) struct FieldError(String);
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for FieldError {}
+
+impl de::Error for FieldError {
+    fn custom<M: fmt::Display>(msg: M) -> Self {
+        FieldError(msg.to_string())
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident: $ty:ty $(= $parse:path)?),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FieldError> {
+                match deserialize_parsed!(@parse self.0, $ty $(, $parse)?) {
+                    Ok(x) => visitor.$visit(x),
+                    Err(err) => Err(FieldError(format!("{} (expected {})", err, stringify!($ty)))),
+                }
+            }
+        )*
+    };
+    (@parse $text:expr, $ty:ty) => {
+        $text.parse::<$ty>()
+    };
+    (@parse $text:expr, $ty:ty, $parse:path) => {
+        $parse($text)
+    };
+}
+
+impl<'de> Deserializer<'de> for FieldDeserializer<'_> {
+    type Error = FieldError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FieldError> {
+        visitor.visit_str(self.0)
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool: bool,
+        deserialize_i8 => visit_i8: i8,
+        deserialize_i16 => visit_i16: i16,
+        deserialize_i32 => visit_i32: i32,
+        deserialize_i64 => visit_i64: i64,
+        deserialize_i128 => visit_i128: i128,
+        deserialize_u8 => visit_u8: u8,
+        deserialize_u16 => visit_u16: u16,
+        deserialize_u32 => visit_u32: u32,
+        deserialize_u64 => visit_u64: u64,
+        deserialize_u128 => visit_u128: u128,
+        deserialize_f32 => visit_f32: f32 = float::parse_float,
+        deserialize_f64 => visit_f64: f64 = float::parse_float,
+        deserialize_char => visit_char: char,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FieldError> {
+        if self.0.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, FieldError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct enum
+        identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColMajor, RowMajor};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn read<O: Order>(text: &str, opts: &CsvOptions<f64>) -> Result<Matrix<f64, O>, MatrixError> {
+        Matrix::from_csv_with(text.as_bytes(), opts)
+    }
+
+    fn rows<O: Order>(m: &Matrix<f64, O>) -> Vec<Vec<f64>> {
+        (0..m.num_rows)
+            .map(|i| (0..m.num_cols).map(|j| m[(i, j)]).collect())
+            .collect()
+    }
+
+    #[test]
+    fn sources() {
+        let from_str: Matrix<f64, ColMajor> = Matrix::from_csv_str("1,2\n3,4\n").unwrap();
+        assert_eq!(rows(&from_str), vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+
+        let bytes: &[u8] = b"1,2\n3,4\n";
+        let from_reader: Matrix<f64, RowMajor> = Matrix::from_reader(bytes).unwrap();
+        assert_eq!(rows(&from_reader), rows(&from_str));
+
+        let from_path: Matrix<f64, ColMajor> = Matrix::from_path("data/input.txt").unwrap();
+        let mut file = File::open("data/input.txt").unwrap();
+        let from_file: Matrix<f64, RowMajor> = Matrix::from_file(&mut file).unwrap();
+        assert_eq!((from_path.num_rows, from_path.num_cols), (4, 5));
+        assert_eq!(rows(&from_path), rows(&from_file));
+
+        assert!(matches!(
+            Matrix::<f64, RowMajor>::from_path("data/missing.txt"),
+            Err(MatrixError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn write_and_read_back() {
+        let path = "data/input.txt";
+        let row_major: Matrix<f64, RowMajor> = Matrix::from_path(path).unwrap();
+        let col_major: Matrix<f64, ColMajor> = Matrix::from_path(path).unwrap();
+
+        let text = col_major.to_csv(&CsvWriteOptions::new()).unwrap();
+        assert!(text.starts_with("0,1,2,5,3\n3,8,9,1,4\n"));
+        assert_eq!(text, row_major.to_csv(&CsvWriteOptions::new()).unwrap());
+        let back: Matrix<f64, RowMajor> = Matrix::from_csv_str(&text).unwrap();
+        assert_eq!(back.data, row_major.data);
+
+        // Shortest formatting reproduces every bit, extremes included.
+        let mut rng = StdRng::seed_from_u64(18);
+        let mut m: Matrix<f64, ColMajor> = Matrix::new(20, 7).unwrap();
+        m.data
+            .iter_mut()
+            .for_each(|x| *x = rng.gen::<f64>() * 10f64.powi(rng.gen_range(-300..300)));
+        m.data[..6].copy_from_slice(&[
+            -0.0,
+            f64::MIN_POSITIVE / 3.0,
+            f64::MAX,
+            0.1,
+            f64::INFINITY,
+            f64::NAN,
+        ]);
+
+        let file = std::env::temp_dir().join(format!("csv_round_trip_{}.csv", std::process::id()));
+        m.to_path(&file).unwrap();
+        let back: Matrix<f64, ColMajor> = Matrix::from_path(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        let bits =
+            |m: &Matrix<f64, ColMajor>| m.data.iter().map(|x| x.to_bits()).collect::<Vec<_>>();
+        assert_eq!(bits(&back), bits(&m));
+    }
+
+    #[test]
+    fn write_formats() {
+        let m: Matrix<f64, ColMajor> = Matrix::from_csv_str("1.5,-2\n1234.5,0.001\n").unwrap();
+
+        let opts = CsvWriteOptions::new().format(NumberFormat::Fixed(2));
+        assert_eq!(m.to_csv(&opts).unwrap(), "1.50,-2.00\n1234.50,0.00\n");
+
+        let opts = CsvWriteOptions::new()
+            .delimiter(b';')
+            .header(["a;b", "c"])
+            .format(NumberFormat::Scientific(Some(1)));
+        assert_eq!(
+            m.to_csv(&opts).unwrap(),
+            "\"a;b\";c\n1.5e0;-2.0e0\n1.2e3;1.0e-3\n"
+        );
+
+        let opts = CsvWriteOptions::new().format(NumberFormat::Scientific(None));
+        assert_eq!(m.to_csv(&opts).unwrap(), "1.5e0,-2e0\n1.2345e3,1e-3\n");
+
+        let ints: Matrix<i32, RowMajor> = Matrix::from_csv_str("1,-2\n3,4\n").unwrap();
+        assert_eq!(ints.to_csv(&CsvWriteOptions::new()).unwrap(), "1,-2\n3,4\n");
+
+        assert!(matches!(
+            m.to_csv(&CsvWriteOptions::new().header(["only one"])),
+            Err(MatrixError::DimensionMismatch {
+                expected: (1, 2),
+                found: (1, 1)
+            })
+        ));
+    }
+
+    #[test]
+    fn delimiters() {
+        let expected = vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]];
+
+        let opts = CsvOptions::new().delimiter(b';');
+        assert_eq!(
+            rows(&read::<RowMajor>("1;2;3\n4;5;6\n", &opts).unwrap()),
+            expected
+        );
+
+        let opts = CsvOptions::new().delimiter(Delimiter::Whitespace);
+        let m = read::<ColMajor>("  1 2\t 3\n\n4   5 6  \r\n", &opts).unwrap();
+        assert_eq!(rows(&m), expected);
+
+        let auto = CsvOptions::new().delimiter(Delimiter::Auto);
+        for text in [
+            "1\t2\t3\n4\t5\t6",
+            "1;2;3\n4;5;6",
+            "1,2,3\n4,5,6",
+            " 1 2 3\n 4 5 6",
+        ] {
+            assert_eq!(rows(&read::<RowMajor>(text, &auto).unwrap()), expected);
+        }
+        let single = read::<RowMajor>("1\n2\n", &auto).unwrap();
+        assert_eq!(rows(&single), vec![vec![1.0], vec![2.0]]);
+    }
+
+    #[test]
+    fn header_comments_and_trimming() {
+        let text = "# exported values\nx ; y\n1.5; 2\n# more\n 3 ;4\n";
+        let opts = CsvOptions::new()
+            .delimiter(Delimiter::Auto)
+            .has_header(true)
+            .comment(Some(b'#'))
+            .trim(true);
+
+        let m: Matrix<f64, RowMajor> = Matrix::from_csv_with(text.as_bytes(), &opts).unwrap();
+        assert_eq!(rows(&m), vec![vec![1.5, 2.0], vec![3.0, 4.0]]);
+
+        let (m, header) =
+            Matrix::<f64, ColMajor>::from_csv_with_header(text.as_bytes(), &opts).unwrap();
+        assert_eq!(header, vec!["x", "y"]);
+        assert_eq!(rows(&m), vec![vec![1.5, 2.0], vec![3.0, 4.0]]);
+
+        // Without trimming, the padded fields are rejected.
+        let err = read::<RowMajor>(text, &opts.clone().trim(false))
+            .err()
+            .unwrap();
+        assert!(
+            matches!(err, MatrixError::Parse { line: 3, column: 2, ref text, .. } if text == " 2")
+        );
+    }
+
+    #[test]
+    fn missing_values() {
+        let text = "1,NA,3\n,5,n/a\n";
+        let opts = CsvOptions::new().missing_values(["NA", "n/a", ""], f64::NAN);
+        let m = read::<ColMajor>(text, &opts).unwrap();
+        let nan: Vec<bool> = m.data.iter().map(|x| x.is_nan()).collect();
+        assert_eq!(nan, vec![false, true, true, false, false, true]);
+        assert_eq!((m[(0, 0)], m[(1, 1)], m[(0, 2)]), (1.0, 5.0, 3.0));
+
+        let opts = CsvOptions::new().missing_values(["NA"], -1);
+        let m: Matrix<i32, RowMajor> =
+            Matrix::from_csv_with("1,NA\n3,4\n".as_bytes(), &opts).unwrap();
+        assert_eq!(m.data, vec![1, -1, 3, 4]);
+
+        let err = read::<RowMajor>(text, &CsvOptions::new()).err().unwrap();
+        assert!(
+            matches!(err, MatrixError::Parse { line: 1, column: 2, ref text, .. } if text == "NA")
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let opts = CsvOptions::new()
+            .delimiter(Delimiter::Whitespace)
+            .comment(Some(b'#'));
+        assert!(matches!(
+            read::<RowMajor>("# header\n1 2\n\n3 4 5\n", &opts),
+            Err(MatrixError::RaggedRow {
+                line: 4,
+                expected: 2,
+                found: 3
+            })
+        ));
+        assert!(matches!(
+            read::<RowMajor>("# only a comment\n\n", &opts),
+            Err(MatrixError::ZeroDimension { .. })
+        ));
+
+        // The header must be as wide as the data.
+        let opts = CsvOptions::new().has_header(true);
+        assert!(matches!(
+            read::<RowMajor>("a,b,c\n1,2\n", &opts),
+            Err(MatrixError::RaggedRow { line: 2, .. })
+        ));
+        assert!(matches!(
+            read::<RowMajor>("a,b\n", &opts),
+            Err(MatrixError::ZeroDimension { .. })
+        ));
+
+        let err = Matrix::<u8, RowMajor>::from_csv_with("1,300\n".as_bytes(), &CsvOptions::new())
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("expected u8"));
+    }
+}
@@ -0,0 +1,394 @@
+//! Loading a whole matrix from delimited text held in memory, in parallel.
+//!
+//! The data is cut at line boundaries into one chunk per thread. A first pass counts the
+//! records of every chunk, which fixes the row each chunk starts at, and a second pass parses
+//! the chunks concurrently and writes every value straight to its place in the matrix, in
+//! either storage order. Only plain records are handled this way. Anything else, such as quoted
+//! fields, and every error, sends the input through `read_csv_data` instead, so results and
+//! errors are exactly those of the streaming reader.
+
+use super::{parse_field, read_csv_data, sniff_delimiter, CsvOptions, Delimiter, RecordReader};
+use crate::parallel::{map_tasks, task_count};
+use crate::transpose::from_storage;
+use crate::{checked_len, Matrix, MatrixError, Order, RowMajor, Scalar};
+use serde::Deserialize;
+use std::marker::PhantomData;
+
+/// A matrix and the column names from its header, if it had one.
+type WithHeader<T, O> = (Matrix<T, O>, Option<Vec<String>>);
+
+/// Parses `bytes` into a matrix, along with the first record as column names if `has_header`
+/// is set.
+pub(crate) fn read_matrix<T, O>(
+    bytes: &[u8],
+    opts: &CsvOptions<T>,
+    has_header: bool,
+) -> Result<WithHeader<T, O>, MatrixError>
+where
+    T: Scalar + Default + Send + Sync + for<'a> Deserialize<'a>,
+    O: Order,
+{
+    read_matrix_in_chunks(bytes, opts, has_header, task_count(bytes.len()))
+}
+
+fn read_matrix_in_chunks<T, O>(
+    bytes: &[u8],
+    opts: &CsvOptions<T>,
+    has_header: bool,
+    chunks: usize,
+) -> Result<WithHeader<T, O>, MatrixError>
+where
+    T: Scalar + Default + Send + Sync + for<'a> Deserialize<'a>,
+    O: Order,
+{
+    // Both paths must split fields the same way, even if the first line is very long.
+    let mut opts = opts.clone();
+    if opts.delimiter == Delimiter::Auto {
+        opts.delimiter = sniff_delimiter(bytes, opts.comment);
+    }
+
+    if let Some(result) = read_plain(bytes, &opts, has_header, chunks) {
+        return Ok(result);
+    }
+
+    let csv = read_csv_data(bytes, &opts, has_header)?;
+    let m = from_storage::<T, O, RowMajor>((csv.num_rows, csv.num_cols), csv.data);
+    Ok((m, csv.header))
+}
+
+/// How plain records are split into fields.
+#[derive(Clone, Copy)]
+enum Split {
+    /// At every occurrence of the byte, after removing the line's `\r`.
+    Byte(u8),
+    /// At runs of whitespace, ignoring leading and trailing whitespace.
+    Whitespace,
+}
+
+impl Split {
+    fn lines(self, text: &[u8]) -> impl Iterator<Item = &[u8]> {
+        split_at_byte(text, b'\n').map(move |line| match self {
+            Split::Byte(_) => line.strip_suffix(b"\r").unwrap_or(line),
+            Split::Whitespace => line,
+        })
+    }
+
+    /// Returns whether `line` is a record, rather than empty or a comment.
+    fn is_record(self, line: &[u8], comment: Option<u8>) -> bool {
+        let first = match self {
+            Split::Byte(_) => line.first(),
+            Split::Whitespace => line.iter().find(|b| !b.is_ascii_whitespace()),
+        };
+        first.is_some_and(|&b| Some(b) != comment)
+    }
+
+    fn fields(self, line: &[u8]) -> impl Iterator<Item = &[u8]> {
+        // Exactly one of the two iterators is used; chaining them gives a single type.
+        let (delimiter, whitespace) = match self {
+            Split::Byte(delimiter) => (Some(delimiter), None),
+            Split::Whitespace => (None, Some(line.split(u8::is_ascii_whitespace))),
+        };
+        let by_byte = delimiter.map(|delimiter| split_at_byte(line, delimiter));
+        by_byte.into_iter().flatten().chain(
+            whitespace
+                .into_iter()
+                .flatten()
+                .filter(|field| !field.is_empty()),
+        )
+    }
+}
+
+/// Like `text.split(|&b| b == byte)`, but searches with `memchr`.
+fn split_at_byte(text: &[u8], byte: u8) -> impl Iterator<Item = &[u8]> {
+    let mut start = 0;
+    memchr::memchr_iter(byte, text)
+        .chain([text.len()])
+        .map(move |end| {
+            let piece = &text[start..end];
+            start = end + 1;
+            piece
+        })
+}
+
+/// The part of the matrix storage that holds a run of consecutive rows.
+enum Rows<'a, T> {
+    /// Row-major storage of the rows.
+    Contiguous(&'a mut [T]),
+    /// One slice per column.
+    Columns(Vec<&'a mut [T]>),
+}
+
+impl<T> Rows<'_, T> {
+    fn set(&mut self, i: usize, j: usize, width: usize, x: T) {
+        match self {
+            Rows::Contiguous(data) => data[i * width + j] = x,
+            Rows::Columns(columns) => columns[j][i] = x,
+        }
+    }
+}
+
+/// Reads `bytes` if every record is plain and parses, and returns `None` otherwise.
+fn read_plain<T, O>(
+    bytes: &[u8],
+    opts: &CsvOptions<T>,
+    has_header: bool,
+    chunks: usize,
+) -> Option<WithHeader<T, O>>
+where
+    T: Scalar + Default + Send + Sync + for<'a> Deserialize<'a>,
+    O: Order,
+{
+    let split = match opts.delimiter {
+        Delimiter::Byte(delimiter) => Split::Byte(delimiter),
+        _ => Split::Whitespace,
+    };
+
+    let mut data = bytes;
+    let mut header = None;
+    if has_header {
+        let mut start = 0;
+        let line = loop {
+            let end =
+                memchr::memchr(b'\n', &bytes[start..]).map_or(bytes.len(), |pos| start + pos + 1);
+            if end == start {
+                return None;
+            }
+            let line = &bytes[start..end];
+            start = end;
+            if split.is_record(split.lines(line).next().unwrap(), opts.comment) {
+                break line;
+            }
+        };
+        // The header may be quoted even when the data is not.
+        let mut records = RecordReader::new(line, opts).ok()?;
+        header = records.read_header(opts.trim).ok()?;
+        data = &bytes[start..];
+    }
+    if matches!(split, Split::Byte(_)) && data.contains(&b'"') {
+        return None;
+    }
+
+    let width = match &header {
+        Some(names) => names.len(),
+        None => {
+            let first = split
+                .lines(data)
+                .find(|line| split.is_record(line, opts.comment))?;
+            split.fields(first).count()
+        }
+    };
+
+    let chunks = split_at_lines(data, chunks);
+    let counts = map_tasks(chunks.clone(), |chunk| {
+        split
+            .lines(chunk)
+            .filter(|line| split.is_record(line, opts.comment))
+            .count()
+    });
+    let num_rows = counts.iter().sum();
+    let mut storage = vec![T::default(); checked_len::<T>(num_rows, width).ok()?];
+
+    let parts = if O::strides((num_rows, width)).1 == 1 {
+        let mut rest = &mut storage[..];
+        counts
+            .iter()
+            .map(|&count| {
+                let (part, tail) = std::mem::take(&mut rest).split_at_mut(count * width);
+                rest = tail;
+                Rows::Contiguous(part)
+            })
+            .collect::<Vec<_>>()
+    } else {
+        let mut parts: Vec<Vec<&mut [T]>> = counts.iter().map(|_| Vec::new()).collect();
+        for mut column in storage.chunks_mut(num_rows) {
+            for (part, &count) in parts.iter_mut().zip(&counts) {
+                let (rows, tail) = std::mem::take(&mut column).split_at_mut(count);
+                column = tail;
+                part.push(rows);
+            }
+        }
+        parts.into_iter().map(Rows::Columns).collect()
+    };
+
+    let tasks = chunks.into_iter().zip(parts).collect();
+    let parsed = map_tasks(tasks, |(chunk, rows)| {
+        parse_chunk(chunk, rows, split, width, opts)
+    });
+    if !parsed.into_iter().all(|ok| ok) {
+        return None;
+    }
+
+    let m = Matrix {
+        num_rows,
+        num_cols: width,
+        data: storage,
+        _order: PhantomData,
+    };
+    Some((m, header))
+}
+
+/// Cuts `text` into at most `chunks` pieces of similar length, each ending at a line break or
+/// at the end of `text`.
+fn split_at_lines(text: &[u8], chunks: usize) -> Vec<&[u8]> {
+    let mut pieces = Vec::with_capacity(chunks);
+    let mut rest = text;
+    for remaining in (1..=chunks.max(1)).rev() {
+        let target = rest.len() / remaining;
+        let end = memchr::memchr(b'\n', &rest[target..]).map_or(rest.len(), |pos| target + pos + 1);
+        let (piece, tail) = rest.split_at(end);
+        if !piece.is_empty() {
+            pieces.push(piece);
+        }
+        rest = tail;
+    }
+    pieces
+}
+
+/// Parses every record of `chunk` into `rows`, and returns whether they all had `width` fields
+/// that parsed.
+fn parse_chunk<T: for<'a> Deserialize<'a> + Clone>(
+    chunk: &[u8],
+    mut rows: Rows<T>,
+    split: Split,
+    width: usize,
+    opts: &CsvOptions<T>,
+) -> bool {
+    let records = split
+        .lines(chunk)
+        .filter(|line| split.is_record(line, opts.comment));
+
+    for (i, line) in records.enumerate() {
+        let mut j = 0;
+        for field in split.fields(line) {
+            let field = if opts.trim { field.trim_ascii() } else { field };
+            match parse_field(field, opts) {
+                Ok(x) if j < width => rows.set(i, j, width, x),
+                _ => return false,
+            }
+            j += 1;
+        }
+        if j != width {
+            return false;
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ColMajor;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Reads `text` in several chunk counts and both orders, checks that every read agrees
+    /// with `read_csv_data`, and returns its result.
+    fn read_all_ways(
+        text: &str,
+        opts: &CsvOptions<f64>,
+        has_header: bool,
+    ) -> Result<WithHeader<f64, RowMajor>, MatrixError> {
+        let expected = read_csv_data(text.as_bytes(), opts, has_header);
+
+        for chunks in [1, 2, 3, 7, 64] {
+            let row_major =
+                read_matrix_in_chunks::<f64, RowMajor>(text.as_bytes(), opts, has_header, chunks);
+            let col_major =
+                read_matrix_in_chunks::<f64, ColMajor>(text.as_bytes(), opts, has_header, chunks);
+
+            match (&expected, row_major, col_major) {
+                (Ok(csv), Ok((r, r_header)), Ok((c, c_header))) => {
+                    let bits = |data: &[f64]| data.iter().map(|x| x.to_bits()).collect::<Vec<_>>();
+                    assert_eq!(bits(&r.data), bits(&csv.data));
+                    assert_eq!(bits(&c.to_order::<RowMajor>().data), bits(&csv.data));
+                    assert_eq!((r.num_rows, r.num_cols), (csv.num_rows, csv.num_cols));
+                    assert_eq!(r_header, csv.header);
+                    assert_eq!(c_header, csv.header);
+                }
+                (Err(e), Err(r), Err(c)) => {
+                    assert_eq!(r.to_string(), e.to_string());
+                    assert_eq!(c.to_string(), e.to_string());
+                }
+                (e, r, c) => panic!(
+                    "{:?}: expected {:?}, got {:?} and {:?}",
+                    text,
+                    e.as_ref().err(),
+                    r.err(),
+                    c.err()
+                ),
+            }
+        }
+
+        read_matrix_in_chunks(text.as_bytes(), opts, has_header, 4)
+    }
+
+    #[test]
+    fn chunks_end_at_line_breaks() {
+        let text = b"1,2\n3,4\n5,6\n7";
+        for chunks in 1..10 {
+            let pieces = split_at_lines(text, chunks);
+            assert!(pieces.len() <= chunks);
+            assert_eq!(pieces.concat(), text);
+            assert!(pieces[..pieces.len() - 1]
+                .iter()
+                .all(|piece| piece.ends_with(b"\n")));
+        }
+        assert!(split_at_lines(b"", 4).is_empty());
+    }
+
+    #[test]
+    fn same_as_streaming_reader() {
+        let mut rng = StdRng::seed_from_u64(20);
+        let mut text = String::from("# generated\na,b,c\n");
+        for i in 0..500 {
+            let row: Vec<String> = (0..3)
+                .map(|_| (rng.gen::<f64>() * 10f64.powi(rng.gen_range(-30..30))).to_string())
+                .collect();
+            text.push_str(&row.join(","));
+            text.push_str(if i % 7 == 0 { "\r\n\n" } else { "\n" });
+        }
+
+        let opts = CsvOptions::new().comment(Some(b'#'));
+        let (m, header) = read_all_ways(&text, &opts, true).unwrap();
+        assert_eq!((m.num_rows, m.num_cols), (500, 3));
+        assert_eq!(header.unwrap(), ["a", "b", "c"]);
+
+        let whitespace = text.replace(',', " \t ");
+        let opts = opts.delimiter(Delimiter::Auto).trim(true);
+        let (ws, _) = read_all_ways(&whitespace, &opts, true).unwrap();
+        assert_eq!(ws.data, m.data);
+
+        let padded = text.replace(',', " , ");
+        assert!(read_all_ways(&padded, &opts, true).is_ok());
+        let opts = opts.missing_values(["NA", ""], f64::NAN);
+        assert!(read_all_ways("1,NA\n,4\n", &opts, false).is_ok());
+
+        // Quoted fields and bare carriage returns go through the streaming reader.
+        let opts = CsvOptions::new();
+        let (m, _) = read_all_ways("1,2\r3,4\n", &opts, false).unwrap();
+        assert_eq!(m.data, vec![1.0, 2.0, 3.0, 4.0]);
+        let (m, header) = read_all_ways("\"x,1\",y\n\"1\",2\n", &opts, true).unwrap();
+        assert_eq!(m.data, vec![1.0, 2.0]);
+        assert_eq!(header.unwrap(), ["x,1", "y"]);
+    }
+
+    #[test]
+    fn same_errors_as_streaming_reader() {
+        let opts = CsvOptions::new().comment(Some(b'#'));
+        for text in [
+            "",
+            "# nothing\n\n",
+            "1,2\n3,4\n5,x\n7,8\n",
+            "1,2\n3,4\n\n5,6,7\n",
+            "1,2\n\n3\n",
+            "1,2\n3,4\n5,6\n7,8,\n",
+            "1,2\n3,4\n5,6\n\"7\",8,9\n",
+        ] {
+            assert!(read_all_ways(text, &opts, false).is_err(), "{:?}", text);
+        }
+        for text in ["a,b\n", "a,b\n1,2,3\n", "a\n1\n2\n3,4\n"] {
+            assert!(read_all_ways(text, &opts, true).is_err());
+        }
+    }
+}
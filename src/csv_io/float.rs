@@ -0,0 +1,212 @@
+//! Float parsing for the common case of short decimal literals.
+//!
+//! A literal with at most 19 significant digits whose mantissa and power of ten are both exactly
+//! representable is converted with a single correctly rounded multiplication or division
+//! (Clinger's fast path). Everything else, including infinities, NaN and invalid input, goes to
+//! `str::parse`, so the results and error messages are always the same as the standard parser.
+
+use std::num::ParseFloatError;
+use std::ops::{Div, Mul, Neg};
+
+pub(crate) trait FastFloat:
+    'static + Copy + Mul<Output = Self> + Div<Output = Self> + Neg<Output = Self> + std::str::FromStr
+{
+    /// The largest mantissa that converts to `Self` exactly.
+    const MAX_MANTISSA: u64;
+    /// Exact powers of ten, from `10^0` up to the largest one that is exactly representable.
+    const POWERS: &'static [Self];
+
+    fn from_u64(x: u64) -> Self;
+}
+
+impl FastFloat for f32 {
+    const MAX_MANTISSA: u64 = 1 << f32::MANTISSA_DIGITS;
+    const POWERS: &'static [f32] = &[1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10];
+
+    fn from_u64(x: u64) -> Self {
+        x as f32
+    }
+}
+
+impl FastFloat for f64 {
+    const MAX_MANTISSA: u64 = 1 << f64::MANTISSA_DIGITS;
+    const POWERS: &'static [f64] = &[
+        1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10, 1e11, 1e12, 1e13, 1e14, 1e15, 1e16,
+        1e17, 1e18, 1e19, 1e20, 1e21, 1e22,
+    ];
+
+    fn from_u64(x: u64) -> Self {
+        x as f64
+    }
+}
+
+/// Parses `text` exactly as `text.parse::<F>()` would.
+pub(crate) fn parse_float<F: FastFloat<Err = ParseFloatError>>(
+    text: &str,
+) -> Result<F, ParseFloatError> {
+    match parse_simple(text.as_bytes()) {
+        Some(x) => Ok(x),
+        None => text.parse(),
+    }
+}
+
+/// Converts `[+-]digits[.digits][(e|E)[+-]digits]`, or returns `None` if the literal does not
+/// have that form or is outside the fast path.
+fn parse_simple<F: FastFloat>(text: &[u8]) -> Option<F> {
+    let negative = text.first() == Some(&b'-');
+    let sign_len = usize::from(negative || text.first() == Some(&b'+'));
+    let mut i = sign_len;
+
+    // Leading zeros are not significant.
+    while text.get(i) == Some(&b'0') {
+        i += 1;
+    }
+    let zeros_end = i;
+    let mut mantissa = 0u64;
+    while let Some(d) = digit(text, i) {
+        mantissa = mantissa.wrapping_mul(10).wrapping_add(d);
+        i += 1;
+    }
+    let int_end = i;
+    let mut frac_digits = 0;
+    if text.get(i) == Some(&b'.') {
+        i += 1;
+        let frac_start = i;
+        while let Some(d) = digit(text, i) {
+            mantissa = mantissa.wrapping_mul(10).wrapping_add(d);
+            i += 1;
+        }
+        frac_digits = i - frac_start;
+    }
+    // Up to 19 digits cannot overflow, and there must be a digit somewhere.
+    let digits = int_end - zeros_end + frac_digits;
+    if digits > 19 || (int_end == sign_len && frac_digits == 0) {
+        return None;
+    }
+
+    let mut exponent = 0i64;
+    if matches!(text.get(i), Some(b'e' | b'E')) {
+        i += 1;
+        let exp_negative = text.get(i) == Some(&b'-');
+        i += usize::from(exp_negative || text.get(i) == Some(&b'+'));
+        let exp_start = i;
+        while let Some(d) = digit(text, i) {
+            exponent = exponent * 10 + d as i64;
+            i += 1;
+            if i - exp_start > 4 {
+                return None;
+            }
+        }
+        if i == exp_start {
+            return None;
+        }
+        if exp_negative {
+            exponent = -exponent;
+        }
+    }
+    if i != text.len() || mantissa > F::MAX_MANTISSA {
+        return None;
+    }
+
+    let exponent = exponent - frac_digits as i64;
+    let power = *F::POWERS.get(exponent.unsigned_abs() as usize)?;
+    let x = if exponent >= 0 {
+        F::from_u64(mantissa) * power
+    } else {
+        F::from_u64(mantissa) / power
+    };
+    Some(if negative { -x } else { x })
+}
+
+fn digit(text: &[u8], i: usize) -> Option<u64> {
+    match text.get(i) {
+        Some(&b @ b'0'..=b'9') => Some(u64::from(b - b'0')),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn same<F: FastFloat<Err = ParseFloatError> + std::fmt::Debug>(
+        text: &str,
+        bits: impl Fn(F) -> u64,
+    ) {
+        let expected = text.parse::<F>();
+        let found = parse_float::<F>(text);
+        match (expected, found) {
+            (Ok(e), Ok(f)) => assert_eq!(bits(e), bits(f), "{}", text),
+            (Err(e), Err(f)) => assert_eq!(e, f, "{}", text),
+            (e, f) => panic!("{}: {:?} != {:?}", text, e, f),
+        }
+    }
+
+    fn check(text: &str) {
+        same::<f64>(text, f64::to_bits);
+        same::<f32>(text, |x| u64::from(x.to_bits()));
+    }
+
+    #[test]
+    fn matches_std() {
+        for text in [
+            "0",
+            "-0",
+            "+0.0",
+            "1",
+            "-1.5",
+            "3.",
+            ".25",
+            "-.5e1",
+            "007.50",
+            "1e22",
+            "1e23",
+            "123456789012345678",
+            "12345678901234567890",
+            "9007199254740993",
+            "0.1",
+            "1e-22",
+            "4.9e-324",
+            "1.7976931348623157e308",
+            "1e400",
+            "inf",
+            "-NaN",
+            "",
+            ".",
+            "-",
+            "e5",
+            "1e",
+            "1e+",
+            "1.2.3",
+            "1,5",
+            " 1",
+            "1 ",
+            "0x10",
+            "1_0",
+            "1e99999",
+            "0.000001e-3",
+        ] {
+            check(text);
+        }
+
+        let mut rng = StdRng::seed_from_u64(20);
+        for _ in 0..20_000 {
+            let bits = rng.gen_range(1..64);
+            let mantissa: u64 = rng.gen_range(0..1u64 << bits);
+            let mut text = mantissa.to_string();
+            if rng.gen_bool(0.7) {
+                let point = rng.gen_range(0..=text.len());
+                text.insert(point, '.');
+            }
+            if rng.gen_bool(0.5) {
+                text.push_str(&format!("e{}", rng.gen_range(-40..40)));
+            }
+            if rng.gen_bool(0.5) {
+                text.insert(0, '-');
+            }
+            check(&text);
+        }
+    }
+}
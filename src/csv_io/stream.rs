@@ -0,0 +1,232 @@
+use super::{parse_field_at, parse_record, CsvOptions, RecordReader};
+use crate::{Matrix, MatrixError, RowMajor};
+use serde::Deserialize;
+use std::io::Read;
+use std::marker::PhantomData;
+use std::ops::Range;
+
+/// Reads delimited text in blocks of rows, for inputs too large to hold in memory.
+///
+/// Each item is a `Matrix<T, RowMajor>` of at most `block_rows` rows, holding either every
+/// column or the ones chosen with [`CsvRowStream::columns`]. Only one record and the current
+/// block are held at a time. After an error the stream ends.
+pub struct CsvRowStream<T, R: Read> {
+    records: RecordReader<R>,
+    opts: CsvOptions<T>,
+    block_rows: usize,
+    header: Option<Vec<String>>,
+    columns: Option<Vec<usize>>,
+    rows: Range<usize>,
+    /// Index of the next data row, counting from the first row after the header.
+    next_row: usize,
+    width: Option<usize>,
+    done: bool,
+}
+
+impl<T: for<'a> Deserialize<'a> + Clone, R: Read> CsvRowStream<T, R> {
+    /// Streams `reader` in blocks of `block_rows` rows. The header, if `opts` has one, is read
+    /// straight away.
+    pub fn new(reader: R, opts: &CsvOptions<T>, block_rows: usize) -> Result<Self, MatrixError> {
+        if block_rows == 0 {
+            return Err(MatrixError::ZeroDimension { rows: 0, cols: 0 });
+        }
+
+        let mut records = RecordReader::new(reader, opts)?;
+        let header = if opts.has_header {
+            records.read_header(opts.trim)?
+        } else {
+            None
+        };
+
+        Ok(CsvRowStream {
+            records,
+            opts: opts.clone(),
+            width: header.as_ref().map(Vec::len),
+            block_rows,
+            header,
+            columns: None,
+            rows: 0..usize::MAX,
+            next_row: 0,
+            done: false,
+        })
+    }
+
+    /// Keeps only the given columns, in the given order. Indices are checked against the
+    /// width of the first record.
+    pub fn columns(mut self, columns: impl IntoIterator<Item = usize>) -> Self {
+        self.columns = Some(columns.into_iter().collect());
+        self
+    }
+
+    /// Keeps only the data rows with indices in `rows`, counting from 0 after the header.
+    /// Reading stops at the end of the range.
+    pub fn rows(mut self, rows: Range<usize>) -> Self {
+        self.rows = rows;
+        self
+    }
+
+    /// Returns the column names from the header, if `opts` had one, for all columns.
+    pub fn header(&self) -> Option<&[String]> {
+        self.header.as_deref()
+    }
+
+    fn read_block(&mut self) -> Result<Option<Matrix<T, RowMajor>>, MatrixError> {
+        let mut num_rows = 0;
+        let mut data = Vec::new();
+
+        while num_rows < self.block_rows && self.next_row < self.rows.end {
+            let Some(line) = self.records.next_record()? else {
+                break;
+            };
+            let record = self.records.record();
+            let expected = *self.width.get_or_insert(record.len());
+            if record.len() != expected {
+                return Err(MatrixError::RaggedRow {
+                    line,
+                    expected,
+                    found: record.len(),
+                });
+            }
+
+            self.next_row += 1;
+            if self.next_row <= self.rows.start {
+                continue;
+            }
+
+            match &self.columns {
+                None => parse_record(record, line, &self.opts, &mut data)?,
+                Some(columns) => {
+                    for &j in columns {
+                        if j >= expected {
+                            return Err(MatrixError::IndexOutOfBounds {
+                                index: j,
+                                len: expected,
+                            });
+                        }
+                        data.push(parse_field_at(record, j, line, &self.opts)?);
+                    }
+                }
+            }
+            num_rows += 1;
+        }
+
+        if num_rows == 0 {
+            return Ok(None);
+        }
+        if data.is_empty() {
+            return Err(MatrixError::ZeroDimension {
+                rows: num_rows,
+                cols: 0,
+            });
+        }
+
+        Ok(Some(Matrix {
+            num_rows,
+            num_cols: data.len() / num_rows,
+            data,
+            _order: PhantomData,
+        }))
+    }
+}
+
+impl<T: for<'a> Deserialize<'a> + Clone, R: Read> Iterator for CsvRowStream<T, R> {
+    type Item = Result<Matrix<T, RowMajor>, MatrixError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let block = self.read_block();
+        if !matches!(block, Ok(Some(_))) {
+            self.done = true;
+        }
+        block.transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rows `i` of the form `i, 10 i, 100 i`.
+    fn table(rows: usize) -> String {
+        (0..rows)
+            .map(|i| format!("{},{},{}\n", i, 10 * i, 100 * i))
+            .collect()
+    }
+
+    fn collect(stream: CsvRowStream<i64, &[u8]>) -> Vec<(usize, Vec<i64>)> {
+        stream
+            .map(|block| {
+                let block = block.unwrap();
+                (block.num_rows, block.data)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn blocks() {
+        let text = table(10);
+        let stream = CsvRowStream::new(text.as_bytes(), &CsvOptions::new(), 4).unwrap();
+        let blocks = collect(stream);
+        assert_eq!(
+            blocks.iter().map(|(rows, _)| *rows).collect::<Vec<_>>(),
+            vec![4, 4, 2]
+        );
+        assert_eq!(blocks[2].1, vec![8, 80, 800, 9, 90, 900]);
+    }
+
+    #[test]
+    fn column_and_row_subsets() {
+        let text = format!("a,b,c\n# note\n{}", table(10));
+        let opts = CsvOptions::new().has_header(true).comment(Some(b'#'));
+
+        let stream = CsvRowStream::new(text.as_bytes(), &opts, 2)
+            .unwrap()
+            .columns([2, 0])
+            .rows(3..8);
+        assert_eq!(stream.header().unwrap(), ["a", "b", "c"]);
+        assert_eq!(
+            collect(stream),
+            vec![
+                (2, vec![300, 3, 400, 4]),
+                (2, vec![500, 5, 600, 6]),
+                (1, vec![700, 7])
+            ]
+        );
+
+        let stream = CsvRowStream::new(text.as_bytes(), &opts, 2)
+            .unwrap()
+            .rows(20..30);
+        assert!(collect(stream).is_empty());
+    }
+
+    #[test]
+    fn errors_end_the_stream() {
+        let text = "1,2\n3,4\n5\n6,7\n";
+        let mut stream =
+            CsvRowStream::<i64, _>::new(text.as_bytes(), &CsvOptions::new(), 1).unwrap();
+        assert!(stream.next().unwrap().is_ok());
+        assert!(stream.next().unwrap().is_ok());
+        assert!(matches!(
+            stream.next(),
+            Some(Err(MatrixError::RaggedRow {
+                line: 3,
+                expected: 2,
+                found: 1
+            }))
+        ));
+        assert!(stream.next().is_none());
+
+        let mut stream = CsvRowStream::<i64, _>::new(text.as_bytes(), &CsvOptions::new(), 8)
+            .unwrap()
+            .columns([1, 2]);
+        assert!(matches!(
+            stream.next(),
+            Some(Err(MatrixError::IndexOutOfBounds { index: 2, len: 2 }))
+        ));
+
+        assert!(CsvRowStream::<i64, _>::new(text.as_bytes(), &CsvOptions::new(), 0).is_err());
+    }
+}
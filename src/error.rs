@@ -0,0 +1,197 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// The error type for every fallible operation in this crate.
+#[derive(Debug)]
+pub enum MatrixError {
+    /// An operand or result has the wrong shape.
+    DimensionMismatch {
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+    NotSquare {
+        rows: usize,
+        cols: usize,
+    },
+    /// A matrix was requested with no rows or no columns.
+    ZeroDimension {
+        rows: usize,
+        cols: usize,
+    },
+    /// The number of elements, or their size in bytes, does not fit in memory.
+    CapacityOverflow {
+        rows: usize,
+        cols: usize,
+    },
+    /// The matrix is singular to working precision; `pivot` is the first column without a
+    /// usable pivot.
+    Singular {
+        pivot: usize,
+    },
+    /// The leading principal minor of order `pivot + 1` is not positive.
+    NotPositiveDefinite {
+        pivot: usize,
+    },
+    /// An iterative algorithm did not converge within its iteration limit.
+    NoConvergence,
+    IndexOutOfBounds {
+        index: usize,
+        len: usize,
+    },
+    /// Input could not be parsed. `line` and `column` are 1-based, and `column` counts fields
+    /// rather than characters. `text` is the offending field, when there is one.
+    Parse {
+        line: usize,
+        column: usize,
+        text: String,
+        source: Box<dyn Error + Send + Sync>,
+    },
+    /// A row of the input has a different number of fields than the rows before it.
+    RaggedRow {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// The input or the requested output uses a feature of a file format that this crate does
+    /// not handle, such as complex values.
+    Unsupported(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for MatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatrixError::DimensionMismatch { expected, found } => write!(
+                f,
+                "Expected a {}x{} matrix, got a {}x{} matrix.",
+                expected.0, expected.1, found.0, found.1
+            ),
+            MatrixError::NotSquare { rows, cols } => write!(
+                f,
+                "Expected a square matrix, got a {}x{} matrix.",
+                rows, cols
+            ),
+            MatrixError::ZeroDimension { rows, cols } => write!(
+                f,
+                "Number of rows or number of columns cannot be 0, got {}x{}.",
+                rows, cols
+            ),
+            MatrixError::CapacityOverflow { rows, cols } => {
+                write!(f, "A {}x{} matrix does not fit in memory.", rows, cols)
+            }
+            MatrixError::Singular { pivot } => write!(
+                f,
+                "Matrix is singular: no usable pivot in column {}.",
+                pivot
+            ),
+            MatrixError::NotPositiveDefinite { pivot } => write!(
+                f,
+                "Matrix is not positive definite: pivot {} is not positive.",
+                pivot
+            ),
+            MatrixError::NoConvergence => write!(f, "Iteration did not converge."),
+            MatrixError::IndexOutOfBounds { index, len } => {
+                write!(f, "Index {} is out of bounds for length {}.", index, len)
+            }
+            MatrixError::Parse {
+                line,
+                column,
+                text,
+                source,
+            } => write!(
+                f,
+                "Parse error at line {}, field {} ({:?}): {}",
+                line, column, text, source
+            ),
+            MatrixError::RaggedRow {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Line {} has {} fields, expected {} like the rows before it.",
+                line, found, expected
+            ),
+            MatrixError::Unsupported(what) => write!(f, "Not supported: {}.", what),
+            MatrixError::Io(err) => write!(f, "I/O error: {}", err),
+        }
+    }
+}
+
+impl Error for MatrixError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MatrixError::Parse { source, .. } => Some(source.as_ref()),
+            MatrixError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MatrixError {
+    fn from(err: io::Error) -> Self {
+        MatrixError::Io(err)
+    }
+}
+
+impl From<csv::Error> for MatrixError {
+    fn from(err: csv::Error) -> Self {
+        if err.is_io_error() {
+            return MatrixError::Io(err.into());
+        }
+
+        let line = err.position().map_or(0, |pos| pos.line() as usize);
+        let column = match err.kind() {
+            csv::ErrorKind::UnequalLengths {
+                expected_len, len, ..
+            } => {
+                return MatrixError::RaggedRow {
+                    line,
+                    expected: *expected_len as usize,
+                    found: *len as usize,
+                }
+            }
+            csv::ErrorKind::Deserialize { err, .. } => err.field().map_or(0, |f| f as usize + 1),
+            csv::ErrorKind::Utf8 { err, .. } => err.field() + 1,
+            _ => 0,
+        };
+        MatrixError::Parse {
+            line,
+            column,
+            text: String::new(),
+            source: Box::new(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_error_location() {
+        let data = "1,2\n3,x\n";
+        let mut rdr = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(data.as_bytes());
+        let err: MatrixError = rdr
+            .deserialize::<Vec<f64>>()
+            .find_map(Result::err)
+            .unwrap()
+            .into();
+
+        assert!(matches!(
+            err,
+            MatrixError::Parse {
+                line: 2,
+                column: 2,
+                ..
+            }
+        ));
+        assert!(err.source().is_some());
+        assert!(err
+            .to_string()
+            .starts_with("Parse error at line 2, field 2"));
+    }
+}
@@ -0,0 +1,481 @@
+//! General matrix multiply, `C = alpha * A * B + beta * C`.
+//!
+//! Follows the BLIS/GotoBLAS structure: `B` is packed into `KC x NC` panels and `A` into
+//! `MC x KC` blocks, both split into micro-panels that a register-tiled `MR x NR` micro-kernel
+//! streams through. Operands are described by their row and column strides, so any mix of
+//! storage orders goes through the same kernel; only the packing loops change to follow
+//! whichever dimension is contiguous.
+
+use crate::parallel;
+use crate::simd::{self, Isa};
+use crate::{Field, Matrix, MatrixError, Order};
+use num_traits::Num;
+
+/// Rows of `C` computed by one micro-kernel call.
+pub(crate) const MR: usize = 8;
+/// Columns of `C` computed by one micro-kernel call.
+pub(crate) const NR: usize = 4;
+
+const MC: usize = 128;
+const KC: usize = 256;
+const NC: usize = 4096;
+
+/// A read-only operand where element `(i, j)` lives at `data[i * rs + j * cs]`.
+#[derive(Clone, Copy)]
+pub(crate) struct Operand<'a, T> {
+    pub(crate) data: &'a [T],
+    pub(crate) rs: usize,
+    pub(crate) cs: usize,
+}
+
+impl<'a, T: Copy> Operand<'a, T> {
+    fn get(&self, i: usize, j: usize) -> T {
+        self.data[i * self.rs + j * self.cs]
+    }
+
+    fn transposed(self) -> Self {
+        Self {
+            data: self.data,
+            rs: self.cs,
+            cs: self.rs,
+        }
+    }
+}
+
+impl<T: Copy, O: Order> Matrix<T, O> {
+    pub(crate) fn operand(&self) -> Operand<'_, T> {
+        let (rs, cs) = O::strides((self.num_rows, self.num_cols));
+        Operand {
+            data: &self.data,
+            rs,
+            cs,
+        }
+    }
+}
+
+impl<T: Field, O: Order> Matrix<T, O> {
+    /// Computes `c = alpha * a * b + beta * c`. When `beta` is zero, `c` is overwritten
+    /// without being read.
+    pub fn gemm<OA: Order, OB: Order>(
+        alpha: T,
+        a: &Matrix<T, OA>,
+        b: &Matrix<T, OB>,
+        beta: T,
+        c: &mut Self,
+    ) -> Result<(), MatrixError> {
+        if a.num_cols != b.num_rows {
+            return Err(MatrixError::DimensionMismatch {
+                expected: (a.num_cols, b.num_cols),
+                found: (b.num_rows, b.num_cols),
+            });
+        }
+        if (c.num_rows, c.num_cols) != (a.num_rows, b.num_cols) {
+            return Err(MatrixError::DimensionMismatch {
+                expected: (a.num_rows, b.num_cols),
+                found: (c.num_rows, c.num_cols),
+            });
+        }
+
+        let (c_rs, c_cs) = O::strides((c.num_rows, c.num_cols));
+        gemm(
+            (a.num_rows, b.num_cols, a.num_cols),
+            alpha,
+            a.operand(),
+            b.operand(),
+            beta,
+            &mut c.data,
+            c_rs,
+            c_cs,
+        );
+
+        Ok(())
+    }
+}
+
+/// Computes `C = alpha * A * B + beta * C` where `A` is `m x k`, `B` is `k x n` and `C` is an
+/// `m x n` buffer with row stride `c_rs` and column stride `c_cs`.
+///
+/// With the `parallel` feature, column panels of `C` are split between threads. Each thread
+/// packs its own blocks and every element of `C` is accumulated in the same order as on a
+/// single thread.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn gemm<T: Field>(
+    dims: (usize, usize, usize),
+    alpha: T,
+    a: Operand<T>,
+    b: Operand<T>,
+    beta: T,
+    c: &mut [T],
+    c_rs: usize,
+    c_cs: usize,
+) {
+    gemm_with(simd::detected(), dims, alpha, a, b, beta, c, c_rs, c_cs);
+}
+
+/// Same as [`gemm`], with the instruction set used by the micro-kernel chosen by the caller.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn gemm_with<T: Field>(
+    isa: Isa,
+    (m, n, k): (usize, usize, usize),
+    alpha: T,
+    a: Operand<T>,
+    b: Operand<T>,
+    beta: T,
+    c: &mut [T],
+    c_rs: usize,
+    c_cs: usize,
+) {
+    if c_rs != 1 && c_cs == 1 {
+        // The micro-kernel writes columns of `C`, so for row-major output compute
+        // `C^T = B^T * A^T` instead, which turns the rows of `C` into columns.
+        return gemm_with(
+            isa,
+            (n, m, k),
+            alpha,
+            b.transposed(),
+            a.transposed(),
+            beta,
+            c,
+            c_cs,
+            c_rs,
+        );
+    }
+
+    if c.is_empty() {
+        return;
+    }
+
+    // A single column can have any column stride, so use one that keeps chunks aligned to it.
+    let c_cs = if n == 1 { c.len() } else { c_cs };
+    parallel::for_each_chunk(c, NR * c_cs, NR * m * k, |offset, c| {
+        if beta.is_zero() {
+            // Overwrite rather than scale so that NaNs or infinities already in `C` don't
+            // leak in.
+            c.iter_mut().for_each(|x| *x = T::zero());
+        } else if !beta.is_one() {
+            c.iter_mut().for_each(|x| *x = *x * beta);
+        }
+
+        if k == 0 || alpha.is_zero() {
+            return;
+        }
+
+        let j0 = offset / c_cs;
+        let cols = n.min(j0 + c.len() / c_cs) - j0;
+        let b = Operand {
+            data: &b.data[j0 * b.cs..],
+            ..b
+        };
+
+        // Only a single-column product takes the fast path, never a one-column chunk of a
+        // wider one, so that each column is computed the same way whatever the thread count.
+        if n == 1 && c_rs == 1 && gemv(alpha, a, b, c, (m, k)) {
+            return;
+        }
+
+        blocked(isa, (m, cols, k), alpha, a, b, c, c_rs, c_cs);
+    });
+}
+
+/// Matrix-vector fast path for a single contiguous column of `C`. Uses dot products when the
+/// rows of `A` are contiguous and `axpy` updates when its columns are. Returns `false` if
+/// neither applies.
+fn gemv<T: Num + Copy + 'static>(
+    alpha: T,
+    a: Operand<T>,
+    b: Operand<T>,
+    c: &mut [T],
+    (m, k): (usize, usize),
+) -> bool {
+    if a.cs == 1 && b.rs == 1 {
+        let x = &b.data[..k];
+        for (i, c) in c[..m].iter_mut().enumerate() {
+            let row = &a.data[i * a.rs..i * a.rs + k];
+            *c = *c + alpha * simd::dot(row, x);
+        }
+        true
+    } else if a.rs == 1 {
+        for p in 0..k {
+            let col = &a.data[p * a.cs..p * a.cs + m];
+            simd::axpy(alpha * b.get(p, 0), col, &mut c[..m]);
+        }
+        true
+    } else {
+        false
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn blocked<T: Num + Copy + 'static>(
+    isa: Isa,
+    (m, n, k): (usize, usize, usize),
+    alpha: T,
+    a: Operand<T>,
+    b: Operand<T>,
+    c: &mut [T],
+    c_rs: usize,
+    c_cs: usize,
+) {
+    let mut packed_a = vec![T::zero(); MC.min(m.next_multiple_of(MR)) * KC.min(k)];
+    let mut packed_b = vec![T::zero(); KC.min(k) * NC.min(n.next_multiple_of(NR))];
+
+    for jc in (0..n).step_by(NC) {
+        let nc = NC.min(n - jc);
+
+        for pc in (0..k).step_by(KC) {
+            let kc = KC.min(k - pc);
+            pack_b(b, (pc, jc), (kc, nc), &mut packed_b);
+
+            for ic in (0..m).step_by(MC) {
+                let mc = MC.min(m - ic);
+                pack_a(a, (ic, pc), (mc, kc), &mut packed_a);
+
+                for jr in (0..nc).step_by(NR) {
+                    let b_panel = &packed_b[jr * kc..(jr + NR) * kc];
+
+                    for ir in (0..mc).step_by(MR) {
+                        let a_panel = &packed_a[ir * kc..(ir + MR) * kc];
+                        let offset = (ic + ir) * c_rs + (jc + jr) * c_cs;
+
+                        micro_kernel(
+                            isa,
+                            kc,
+                            alpha,
+                            a_panel,
+                            b_panel,
+                            &mut c[offset..],
+                            (c_rs, c_cs),
+                            (MR.min(mc - ir), NR.min(nc - jr)),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Packs the `mc x kc` block of `A` at `(i0, p0)` into micro-panels of `MR` rows. Within a
+/// micro-panel, element `(ii, p)` is stored at `p * MR + ii`; rows past `mc` are zero.
+fn pack_a<T: Num + Copy>(
+    a: Operand<T>,
+    (i0, p0): (usize, usize),
+    (mc, kc): (usize, usize),
+    packed: &mut [T],
+) {
+    for (panel, ir) in (0..mc).step_by(MR).enumerate() {
+        let rows = MR.min(mc - ir);
+        let dst = &mut packed[panel * MR * kc..(panel + 1) * MR * kc];
+
+        if a.rs <= a.cs {
+            // Columns of `A` are contiguous: walk down each column.
+            for (p, chunk) in dst.chunks_exact_mut(MR).enumerate() {
+                for (ii, x) in chunk.iter_mut().enumerate() {
+                    *x = if ii < rows {
+                        a.get(i0 + ir + ii, p0 + p)
+                    } else {
+                        T::zero()
+                    };
+                }
+            }
+        } else {
+            // Rows of `A` are contiguous: walk along each row.
+            for ii in 0..MR {
+                for p in 0..kc {
+                    dst[p * MR + ii] = if ii < rows {
+                        a.get(i0 + ir + ii, p0 + p)
+                    } else {
+                        T::zero()
+                    };
+                }
+            }
+        }
+    }
+}
+
+/// Packs the `kc x nc` panel of `B` at `(p0, j0)` into micro-panels of `NR` columns. Within a
+/// micro-panel, element `(p, jj)` is stored at `p * NR + jj`; columns past `nc` are zero.
+fn pack_b<T: Num + Copy>(
+    b: Operand<T>,
+    (p0, j0): (usize, usize),
+    (kc, nc): (usize, usize),
+    packed: &mut [T],
+) {
+    for (panel, jr) in (0..nc).step_by(NR).enumerate() {
+        let cols = NR.min(nc - jr);
+        let dst = &mut packed[panel * NR * kc..(panel + 1) * NR * kc];
+
+        if b.cs <= b.rs {
+            // Rows of `B` are contiguous: walk along each row.
+            for (p, chunk) in dst.chunks_exact_mut(NR).enumerate() {
+                for (jj, x) in chunk.iter_mut().enumerate() {
+                    *x = if jj < cols {
+                        b.get(p0 + p, j0 + jr + jj)
+                    } else {
+                        T::zero()
+                    };
+                }
+            }
+        } else {
+            // Columns of `B` are contiguous: walk down each column.
+            for jj in 0..NR {
+                for p in 0..kc {
+                    dst[p * NR + jj] = if jj < cols {
+                        b.get(p0 + p, j0 + jr + jj)
+                    } else {
+                        T::zero()
+                    };
+                }
+            }
+        }
+    }
+}
+
+/// Accumulates an `MR x NR` tile of `A * B` in registers and adds `alpha` times the valid
+/// `rows x cols` corner of it to `C`. Uses a vectorized kernel for `f32` and `f64` when the
+/// CPU supports one.
+#[allow(clippy::too_many_arguments)]
+fn micro_kernel<T: Num + Copy + 'static>(
+    isa: Isa,
+    kc: usize,
+    alpha: T,
+    a: &[T],
+    b: &[T],
+    c: &mut [T],
+    (c_rs, c_cs): (usize, usize),
+    (rows, cols): (usize, usize),
+) {
+    if simd::micro_kernel(isa, kc, alpha, a, b, c, (c_rs, c_cs), (rows, cols)) {
+        return;
+    }
+
+    let mut acc = [[T::zero(); MR]; NR];
+
+    for (a, b) in a.chunks_exact(MR).zip(b.chunks_exact(NR)).take(kc) {
+        for (acc, &b) in acc.iter_mut().zip(b) {
+            for (acc, &a) in acc.iter_mut().zip(a) {
+                *acc = *acc + a * b;
+            }
+        }
+    }
+
+    for (jj, acc) in acc.iter().enumerate().take(cols) {
+        for (ii, &value) in acc.iter().enumerate().take(rows) {
+            let x = &mut c[ii * c_rs + jj * c_cs];
+            *x = *x + alpha * value;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive(m: usize, n: usize, k: usize, a: &[i64], b: &[i64]) -> Vec<i64> {
+        let mut c = vec![0; m * n];
+        for i in 0..m {
+            for j in 0..n {
+                for p in 0..k {
+                    c[i * n + j] += a[i * k + p] * b[p * n + j];
+                }
+            }
+        }
+        c
+    }
+
+    #[test]
+    fn matches_naive_for_all_stride_combinations() {
+        for &(m, n, k) in &[
+            (1, 1, 1),
+            (3, 5, 7),
+            (13, 17, 19),
+            (130, 9, 300),
+            (37, 1, 41),
+            (1, 29, 6),
+        ] {
+            let a: Vec<i64> = (0..m * k).map(|x| (x % 7) as i64 - 3).collect();
+            let b: Vec<i64> = (0..k * n).map(|x| (x % 5) as i64 - 2).collect();
+            let expected = naive(m, n, k, &a, &b);
+
+            // Column-major copies of the row-major inputs.
+            let a_col: Vec<i64> = (0..m * k).map(|x| a[(x % m) * k + x / m]).collect();
+            let b_col: Vec<i64> = (0..k * n).map(|x| b[(x % k) * n + x / k]).collect();
+
+            let a_ops = [
+                Operand {
+                    data: &a[..],
+                    rs: k,
+                    cs: 1,
+                },
+                Operand {
+                    data: &a_col[..],
+                    rs: 1,
+                    cs: m,
+                },
+            ];
+            let b_ops = [
+                Operand {
+                    data: &b[..],
+                    rs: n,
+                    cs: 1,
+                },
+                Operand {
+                    data: &b_col[..],
+                    rs: 1,
+                    cs: k,
+                },
+            ];
+
+            for a_op in a_ops {
+                for b_op in b_ops {
+                    let mut c = vec![1; m * n];
+                    gemm((m, n, k), 2, a_op, b_op, 3, &mut c, n, 1);
+                    let want: Vec<i64> = expected.iter().map(|x| 2 * x + 3).collect();
+                    assert_eq!(c, want);
+
+                    let mut c = vec![0; m * n];
+                    gemm((m, n, k), 1, a_op, b_op, 0, &mut c, 1, m);
+                    for i in 0..m {
+                        for j in 0..n {
+                            assert_eq!(c[j * m + i], expected[i * n + j]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn single_column_chunks_match_one_thread() {
+        // With 9 columns, splitting between threads leaves a last chunk of one column, which
+        // must still go through the same kernel as on one thread.
+        let (m, n, k) = (200, 9, 600);
+        let a: Vec<f64> = (0..m * k)
+            .map(|x| ((x * 37) % 101) as f64 / 13.0 - 3.0)
+            .collect();
+        let b: Vec<f64> = (0..k * n)
+            .map(|x| ((x * 53) % 97) as f64 / 11.0 - 4.0)
+            .collect();
+        let a_op = Operand {
+            data: &a[..],
+            rs: 1,
+            cs: m,
+        };
+        let b_op = Operand {
+            data: &b[..],
+            rs: 1,
+            cs: k,
+        };
+
+        let mut results = Vec::new();
+        for threads in [1, 3] {
+            crate::set_num_threads(threads);
+            let mut c: Vec<f64> = (0..m * n).map(|x| (x % 11) as f64 - 5.0).collect();
+            gemm((m, n, k), 0.3, a_op, b_op, 0.7, &mut c, 1, m);
+            results.push(c);
+        }
+        crate::set_num_threads(0);
+
+        assert!(results[0] == results[1]);
+    }
+}
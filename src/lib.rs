@@ -1,51 +1,146 @@
-use serde::Deserialize;
-use std::fs::File;
-use std::io::BufReader;
 use std::marker::PhantomData;
 
+mod binary;
+mod crc32;
+mod csv_io;
+mod error;
+mod gemm;
+mod linalg;
+mod mtx;
+mod npy;
+mod ops;
+mod parallel;
+mod scalar;
+#[cfg(feature = "serde")]
+mod serde_impls;
+mod simd;
+mod transpose;
+mod view;
+
+#[cfg(unix)]
+pub use binary::{MmapMatrix, Pod};
+pub use csv_io::{CsvOptions, CsvRowStream, CsvWriteOptions, Delimiter, NumberFormat};
+pub use error::MatrixError;
+pub use linalg::{Cholesky, Complex, Eigen, Ldlt, Lu, Qr, Schur, Svd, SymmetricEigen};
+pub use mtx::{MtxField, MtxLayout, MtxSymmetry, MtxWriteOptions};
+pub use npy::{NpyElement, NpzReader, NpzWriter};
+#[cfg(feature = "parallel")]
+pub use parallel::{num_threads, set_num_threads};
+pub use scalar::{Field, RealField, Scalar};
+pub use view::MatrixView;
+
 pub trait Order {
+    /// The order whose layout of a `(c, r)` matrix is identical to this order's layout of
+    /// an `(r, c)` matrix.
+    type Transposed: Order;
+
     fn calc_index(pos: (usize, usize), dims: (usize, usize)) -> usize;
+
+    /// Splits `dims` into the number of contiguous runs in storage and the length of each run.
+    fn storage_dims(dims: (usize, usize)) -> (usize, usize);
+
+    /// Returns the distance in storage between neighbouring rows and between neighbouring
+    /// columns.
+    fn strides(dims: (usize, usize)) -> (usize, usize);
 }
 
-enum RowMajor {}
+pub enum RowMajor {}
 
 impl Order for RowMajor {
+    type Transposed = ColMajor;
+
     fn calc_index(pos: (usize, usize), dims: (usize, usize)) -> usize {
         let (i, j) = pos;
         let (_, num_cols) = dims;
         i * num_cols + j
     }
+
+    fn storage_dims(dims: (usize, usize)) -> (usize, usize) {
+        dims
+    }
+
+    fn strides(dims: (usize, usize)) -> (usize, usize) {
+        let (_, num_cols) = dims;
+        (num_cols, 1)
+    }
 }
 
-enum ColMajor {}
+pub enum ColMajor {}
 
 impl Order for ColMajor {
+    type Transposed = RowMajor;
+
     fn calc_index(pos: (usize, usize), dims: (usize, usize)) -> usize {
         let (i, j) = pos;
         let (num_rows, _) = dims;
         j * num_rows + i
     }
+
+    fn storage_dims(dims: (usize, usize)) -> (usize, usize) {
+        let (num_rows, num_cols) = dims;
+        (num_cols, num_rows)
+    }
+
+    fn strides(dims: (usize, usize)) -> (usize, usize) {
+        let (num_rows, _) = dims;
+        (1, num_rows)
+    }
 }
 
-struct Dimensions {
-    rows: usize,
-    cols: usize,
+pub struct Dimensions {
+    pub rows: usize,
+    pub cols: usize,
 }
 
-struct Matrix<T, Order> {
+pub struct Matrix<T, Order> {
     num_rows: usize,
     num_cols: usize,
     data: Vec<T>,
     _order: PhantomData<Order>,
 }
 
-impl<T: Default + Copy + for<'a> Deserialize<'a>, O: Order> Matrix<T, O> {
-    pub fn new(num_rows: usize, num_cols: usize) -> Result<Self, String> {
-        if num_rows * num_cols == 0 {
-            return Err("Number of rows or number of columns cannot be 0.".to_string());
+impl<T: Clone, O> Clone for Matrix<T, O> {
+    fn clone(&self) -> Self {
+        Self {
+            num_rows: self.num_rows,
+            num_cols: self.num_cols,
+            data: self.data.clone(),
+            _order: PhantomData,
         }
+    }
+}
 
-        let data = vec![T::default(); num_rows * num_cols];
+/// Returns the number of elements of a `num_rows x num_cols` matrix of `T`, checking that the
+/// matrix is not empty and that its storage can be allocated.
+fn checked_len<T>(num_rows: usize, num_cols: usize) -> Result<usize, MatrixError> {
+    if num_rows == 0 || num_cols == 0 {
+        return Err(MatrixError::ZeroDimension {
+            rows: num_rows,
+            cols: num_cols,
+        });
+    }
+
+    num_rows
+        .checked_mul(num_cols)
+        .filter(|len| {
+            len.checked_mul(std::mem::size_of::<T>())
+                .is_some_and(|bytes| bytes <= isize::MAX as usize)
+        })
+        .ok_or(MatrixError::CapacityOverflow {
+            rows: num_rows,
+            cols: num_cols,
+        })
+}
+
+/// Returns whether `O` stores columns contiguously, as file formats record it.
+fn is_col_major<O: Order>() -> bool {
+    O::strides((2, 2)) == ColMajor::strides((2, 2))
+}
+
+impl<T: Scalar + Default, O: Order> Matrix<T, O> {
+    pub fn new(num_rows: usize, num_cols: usize) -> Result<Self, MatrixError> {
+        let len = checked_len::<T>(num_rows, num_cols)?;
+        let data = vec![T::default(); len];
 
         Ok(Self {
             num_rows,
@@ -55,12 +150,15 @@ impl<T: Default + Copy + for<'a> Deserialize<'a>, O: Order> Matrix<T, O> {
         })
     }
 
-    pub fn set_identity(&mut self) -> Result<(), String>
+    pub fn set_identity(&mut self) -> Result<(), MatrixError>
     where
         T: num_traits::One,
     {
         if !self.is_square() {
-            return Err("Can't make non-square matrix an identity matrix.".to_string());
+            return Err(MatrixError::NotSquare {
+                rows: self.num_rows,
+                cols: self.num_cols,
+            });
         }
 
         for i in 0..self.num_rows {
@@ -70,12 +168,59 @@ impl<T: Default + Copy + for<'a> Deserialize<'a>, O: Order> Matrix<T, O> {
         Ok(())
     }
 
-    pub fn is_square(&self) -> bool {
-        self.num_rows == self.num_cols
+    pub fn transpose(&self) -> Result<Self, MatrixError>
+    where
+        T: Send + Sync,
+    {
+        let (outer, inner) = O::storage_dims((self.num_rows, self.num_cols));
+
+        let mut data = vec![T::default(); self.data.len()];
+        transpose::transpose_into(&self.data, outer, inner, &mut data);
+
+        Ok(Self {
+            num_rows: self.num_cols,
+            num_cols: self.num_rows,
+            data,
+            _order: PhantomData,
+        })
     }
 
-    pub fn transpose(&self) -> Result<Self, String> {
-        std::unimplemented!()
+    /// Transposes the matrix without allocating a second buffer for the data.
+    pub fn transpose_in_place(&mut self) {
+        let (outer, inner) = O::storage_dims((self.num_rows, self.num_cols));
+        transpose::transpose_in_place(&mut self.data, outer, inner);
+        std::mem::swap(&mut self.num_rows, &mut self.num_cols);
+    }
+
+    /// Copies the matrix into storage order `O2`, keeping the logical contents unchanged.
+    pub fn to_order<O2: Order>(&self) -> Matrix<T, O2>
+    where
+        T: Send + Sync,
+    {
+        let dims = (self.num_rows, self.num_cols);
+        let (outer, inner) = O::storage_dims(dims);
+
+        let data = if O2::strides(dims) == O::strides(dims) {
+            self.data.clone()
+        } else {
+            // Switching between row and column runs is a transpose of the storage.
+            let mut data = vec![T::default(); self.data.len()];
+            transpose::transpose_into(&self.data, outer, inner, &mut data);
+            data
+        };
+
+        Matrix {
+            num_rows: self.num_rows,
+            num_cols: self.num_cols,
+            data,
+            _order: PhantomData,
+        }
+    }
+}
+
+impl<T, O: Order> Matrix<T, O> {
+    pub fn is_square(&self) -> bool {
+        self.num_rows == self.num_cols
     }
 
     pub fn dims(&self) -> Dimensions {
@@ -84,67 +229,52 @@ impl<T: Default + Copy + for<'a> Deserialize<'a>, O: Order> Matrix<T, O> {
             cols: self.num_cols,
         }
     }
-}
-
-fn read_csv_data<T: for<'a> Deserialize<'a> + Clone>(file: &mut File) -> Result<(Vec<T>, usize, usize), String> {
-    let reader = BufReader::new(file);
-
-    let mut rdr = csv::ReaderBuilder::new()
-        .has_headers(false)
-        .delimiter(b',')
-        .from_reader(reader);
 
-    let mut num_rows = 0;
-    let mut num_cols = 0;
-
-    let mut data = Vec::new();
-
-    for (i, result) in rdr.deserialize().enumerate() {
-        let record: Vec<T> = result.unwrap();
-
-        num_rows += 1;
-
-        if i == 0 {
-            num_cols = record.len();
+    /// Transposes in O(1) by reinterpreting the storage in the opposite order. No data is
+    /// moved.
+    pub fn into_transposed_layout(self) -> Matrix<T, O::Transposed> {
+        Matrix {
+            num_rows: self.num_cols,
+            num_cols: self.num_rows,
+            data: self.data,
+            _order: PhantomData,
         }
-
-        data.extend_from_slice(&record);
     }
 
-    Ok((data, num_rows, num_cols))
-}
-
-
-impl<T: Default + Copy + for<'a> Deserialize<'a>> Matrix<T, RowMajor> {
-    pub fn from_file(file: &mut File) -> Result<Self, String> {
-        let (data, num_rows, num_cols) = read_csv_data(file)?;
-
-        Ok(Self {
-            num_rows,
-            num_cols,
-            data,
+    /// Borrows the matrix as its transpose, reinterpreting the storage in the opposite order.
+    pub fn as_transposed(&self) -> MatrixView<'_, T, O::Transposed> {
+        MatrixView {
+            num_rows: self.num_cols,
+            num_cols: self.num_rows,
+            data: &self.data,
             _order: PhantomData,
-        })
+        }
     }
 }
 
-impl<T: Default + Copy + for<'a> Deserialize<'a>> Matrix<T, ColMajor> {
-    pub fn from_file(file: &mut File) -> Result<Self, String> {
-        let (mut data, num_rows, num_cols) = read_csv_data(file)?;
-
-        let mut transposed_data = vec![T::default(); data.len()];
-        for i in 0..num_rows {
-            for j in 0..num_cols {
-                transposed_data[j * num_rows + i] = data[i * num_cols + j];
-            }
+impl<T: Scalar> Matrix<T, RowMajor> {
+    /// Switches to column-major storage, transposing the data in its own buffer.
+    pub fn into_col_major(mut self) -> Matrix<T, ColMajor> {
+        transpose::transpose_in_place(&mut self.data, self.num_rows, self.num_cols);
+        Matrix {
+            num_rows: self.num_rows,
+            num_cols: self.num_cols,
+            data: self.data,
+            _order: PhantomData,
         }
+    }
+}
 
-        Ok(Self {
-            num_rows,
-            num_cols,
-            data: transposed_data,
+impl<T: Scalar> Matrix<T, ColMajor> {
+    /// Switches to row-major storage, transposing the data in its own buffer.
+    pub fn into_row_major(mut self) -> Matrix<T, RowMajor> {
+        transpose::transpose_in_place(&mut self.data, self.num_cols, self.num_rows);
+        Matrix {
+            num_rows: self.num_rows,
+            num_cols: self.num_cols,
+            data: self.data,
             _order: PhantomData,
-        })
+        }
     }
 }
 
@@ -159,7 +289,6 @@ impl<T, O: Order> std::ops::Index<(usize, usize)> for Matrix<T, O> {
 
 impl<T, O: Order> std::ops::IndexMut<(usize, usize)> for Matrix<T, O> {
     fn index_mut(&mut self, index: (usize, usize)) -> &mut T {
-        let (row, col) = index;
         let idx = O::calc_index(index, (self.num_rows, self.num_cols));
         &mut self.data[idx]
     }
@@ -168,6 +297,7 @@ impl<T, O: Order> std::ops::IndexMut<(usize, usize)> for Matrix<T, O> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs::File;
     use std::path::PathBuf;
 
     #[test]
@@ -180,7 +310,14 @@ mod tests {
         assert_eq!(m1.data.len(), 6);
         assert!(!m1.is_square());
 
-        assert!(Matrix::<usize, ColMajor>::new(0, 0).is_err());
+        assert!(matches!(
+            Matrix::<usize, ColMajor>::new(0, 3),
+            Err(MatrixError::ZeroDimension { rows: 0, cols: 3 })
+        ));
+        assert!(matches!(
+            Matrix::<u64, RowMajor>::new(usize::MAX / 4, 3),
+            Err(MatrixError::CapacityOverflow { .. })
+        ));
     }
 
     #[test]
@@ -195,7 +332,10 @@ mod tests {
         assert_eq!(m1.dims().cols, 2);
 
         let mut m1: Matrix<usize, ColMajor> = Matrix::new(3, 2).unwrap();
-        assert!(m1.set_identity().is_err());
+        assert!(matches!(
+            m1.set_identity(),
+            Err(MatrixError::NotSquare { rows: 3, cols: 2 })
+        ));
 
         let mut m1: Matrix<usize, ColMajor> = Matrix::new(2, 2).unwrap();
         assert!(m1.set_identity().is_ok())
@@ -242,4 +382,173 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn from_file_errors() {
+        let mut file = File::open("data/invalid_input.txt").unwrap();
+        assert!(matches!(
+            Matrix::<f64, RowMajor>::from_file(&mut file),
+            Err(MatrixError::RaggedRow {
+                line: 2,
+                expected: 1,
+                found: 5
+            })
+        ));
+
+        let mut file = File::open("data/malformed_input.txt").unwrap();
+        let err = Matrix::<f64, ColMajor>::from_file(&mut file).err().unwrap();
+        match &err {
+            MatrixError::Parse {
+                line, column, text, ..
+            } => assert_eq!((*line, *column, text.as_str()), (3, 2, "3.x")),
+            _ => panic!("unexpected error: {}", err),
+        }
+        assert!(err.to_string().contains("line 3, field 2 (\"3.x\")"));
+
+        let mut file = File::open("data/empty_input.txt").unwrap();
+        assert!(matches!(
+            Matrix::<f64, RowMajor>::from_file(&mut file),
+            Err(MatrixError::ZeroDimension { rows: 0, cols: 0 })
+        ));
+    }
+
+    #[test]
+    fn transpose() {
+        let mut m1: Matrix<usize, RowMajor> = Matrix::new(2, 3).unwrap();
+        m1.data = vec![1, 2, 3, 4, 5, 6];
+
+        let t = m1.transpose().unwrap();
+        assert_eq!(t.dims().rows, 3);
+        assert_eq!(t.dims().cols, 2);
+        assert_eq!(t.data, vec![1, 4, 2, 5, 3, 6]);
+
+        let mut m2: Matrix<usize, ColMajor> = Matrix::new(2, 3).unwrap();
+        m2.data = vec![1, 4, 2, 5, 3, 6];
+
+        let t = m2.transpose().unwrap();
+        assert_eq!(t.dims().rows, 3);
+        assert_eq!(t.dims().cols, 2);
+        for i in 0..2 {
+            for j in 0..3 {
+                assert_eq!(t[(j, i)], m2[(i, j)]);
+            }
+        }
+    }
+
+    #[test]
+    fn transpose_in_place() {
+        let mut m1: Matrix<usize, RowMajor> = Matrix::new(3, 5).unwrap();
+        m1.data = (0..15).collect();
+        let expected = m1.transpose().unwrap();
+
+        m1.transpose_in_place();
+        assert_eq!(m1.dims().rows, 5);
+        assert_eq!(m1.dims().cols, 3);
+        assert_eq!(m1.data, expected.data);
+
+        let mut m2: Matrix<usize, ColMajor> = Matrix::new(4, 4).unwrap();
+        m2.data = (0..16).collect();
+        let expected = m2.transpose().unwrap();
+
+        m2.transpose_in_place();
+        assert_eq!(m2.data, expected.data);
+    }
+
+    #[test]
+    fn order_conversion() {
+        let mut m1: Matrix<usize, RowMajor> = Matrix::new(3, 4).unwrap();
+        m1.data = (0..12).collect();
+
+        let m2 = m1.to_order::<ColMajor>();
+        assert_eq!(m2.dims().rows, 3);
+        assert_eq!(m2.dims().cols, 4);
+        for i in 0..3 {
+            for j in 0..4 {
+                assert_eq!(m2[(i, j)], m1[(i, j)]);
+            }
+        }
+
+        assert_eq!(m1.to_order::<RowMajor>().data, m1.data);
+
+        // The consuming conversions reuse the buffer.
+        let ptr = m1.data.as_ptr();
+        let m3 = m1.into_col_major();
+        assert_eq!(m3.data, m2.data);
+        assert_eq!(m3.data.as_ptr(), ptr);
+        let m4 = m3.into_row_major();
+        assert_eq!(m4.data, (0..12).collect::<Vec<_>>());
+        assert_eq!(m4.data.as_ptr(), ptr);
+
+        // Square matrices have the same storage dimensions in both orders.
+        let mut sq: Matrix<usize, RowMajor> = Matrix::new(2, 2).unwrap();
+        sq.data = vec![1, 2, 3, 4];
+        assert_eq!(sq.to_order::<ColMajor>().data, vec![1, 3, 2, 4]);
+    }
+
+    #[test]
+    fn transposed_layout() {
+        let mut m1: Matrix<usize, RowMajor> = Matrix::new(2, 3).unwrap();
+        m1.data = vec![1, 2, 3, 4, 5, 6];
+
+        let view = m1.as_transposed();
+        assert_eq!(view.dims().rows, 3);
+        assert_eq!(view.dims().cols, 2);
+        for i in 0..2 {
+            for j in 0..3 {
+                assert_eq!(view[(j, i)], m1[(i, j)]);
+            }
+        }
+        assert_eq!(view.to_owned().data, m1.data);
+
+        let t: Matrix<usize, ColMajor> = m1.into_transposed_layout();
+        assert_eq!(t.dims().rows, 3);
+        assert_eq!(t.dims().cols, 2);
+        assert_eq!(t.data, vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(t[(2, 0)], 3);
+        assert_eq!(t[(0, 1)], 4);
+    }
+
+    /// An element type without `Deserialize`.
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    struct Meters(f64);
+
+    impl std::ops::Mul for Meters {
+        type Output = Self;
+
+        fn mul(self, rhs: Self) -> Self {
+            Meters(self.0 * rhs.0)
+        }
+    }
+
+    impl num_traits::One for Meters {
+        fn one() -> Self {
+            Meters(1.0)
+        }
+    }
+
+    #[test]
+    fn elements_need_not_be_deserializable() {
+        let mut m: Matrix<Meters, RowMajor> = Matrix::new(2, 2).unwrap();
+        assert!(m.is_square());
+        m.set_identity().unwrap();
+        m[(0, 1)] = Meters(2.5);
+
+        let t = m.transpose().unwrap().into_col_major();
+        assert_eq!(t[(1, 0)], Meters(2.5));
+        assert_eq!(t[(1, 1)], Meters(1.0));
+        assert_eq!(t[(0, 1)], Meters::default());
+    }
+
+    /// An element type that is neither `Send` nor `Sync`.
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    struct Local(i32, PhantomData<*const ()>);
+
+    #[test]
+    fn serial_operations_do_not_need_thread_safe_elements() {
+        let mut m: Matrix<Local, RowMajor> = Matrix::new(2, 3).unwrap();
+        m[(0, 2)] = Local(7, PhantomData);
+        m.transpose_in_place();
+        assert_eq!(m[(2, 0)].0, 7);
+        assert_eq!(m.as_transposed()[(0, 2)].0, 7);
+    }
 }
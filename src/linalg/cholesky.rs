@@ -0,0 +1,240 @@
+use super::{solve_columns, split_cols, MatrixError};
+use crate::transpose::{from_storage, storage_in};
+use crate::{simd, ColMajor, Matrix, Order, RealField};
+use std::marker::PhantomData;
+
+/// Cholesky factorization of a symmetric positive-definite matrix, `A = L * L^T`.
+///
+/// `L` is kept in the lower triangle of a column-major buffer; the upper triangle is unused.
+pub struct Cholesky<T, O> {
+    n: usize,
+    factor: Vec<T>,
+    _order: PhantomData<O>,
+}
+
+impl<T: RealField, O: Order> Matrix<T, O> {
+    /// Factorizes a symmetric positive-definite matrix. Only the lower triangle is read.
+    ///
+    /// Fails with `MatrixError::NotPositiveDefinite` naming the first pivot that is not
+    /// positive.
+    pub fn cholesky(&self) -> Result<Cholesky<T, O>, MatrixError> {
+        if !self.is_square() {
+            return Err(MatrixError::NotSquare {
+                rows: self.num_rows,
+                cols: self.num_cols,
+            });
+        }
+
+        let n = self.num_rows;
+        let mut a = storage_in::<T, ColMajor, O>(self).into_owned();
+
+        for k in 0..n {
+            let d = a[k * n + k];
+            if d.is_nan() || d <= T::zero() {
+                return Err(MatrixError::NotPositiveDefinite { pivot: k });
+            }
+
+            let l_kk = d.sqrt();
+            a[k * n + k] = l_kk;
+            simd::scale(&mut a[k * n + k + 1..(k + 1) * n], T::one() / l_kk);
+
+            for j in k + 1..n {
+                let (col_k, col_j) = split_cols(&mut a, n, k, j);
+                let l_jk = col_k[j];
+                if l_jk != T::zero() {
+                    simd::axpy(-l_jk, &col_k[j..], &mut col_j[j..]);
+                }
+            }
+        }
+
+        Ok(Cholesky {
+            n,
+            factor: a,
+            _order: PhantomData,
+        })
+    }
+}
+
+impl<T: RealField, O: Order> Cholesky<T, O> {
+    /// Solves `A * X = B`.
+    pub fn solve<O2: Order>(&self, b: &Matrix<T, O2>) -> Result<Matrix<T, O2>, MatrixError> {
+        let mut x = b.clone();
+        self.solve_in_place(&mut x)?;
+        Ok(x)
+    }
+
+    /// Solves `A * X = B`, overwriting `B` with `X`.
+    pub fn solve_in_place<O2: Order>(&self, b: &mut Matrix<T, O2>) -> Result<(), MatrixError> {
+        solve_columns(self.n, b, |col| self.solve_vec(col))
+    }
+
+    pub fn det(&self) -> T {
+        let n = self.n;
+        (0..n).fold(T::one(), |det, k| {
+            let l_kk = self.factor[k * n + k];
+            det * l_kk * l_kk
+        })
+    }
+
+    /// Returns the natural logarithm of the determinant, which does not overflow for large
+    /// matrices the way `det` can.
+    pub fn log_det(&self) -> T {
+        let n = self.n;
+        let sum = (0..n).fold(T::zero(), |sum, k| sum + self.factor[k * n + k].ln());
+        sum + sum
+    }
+
+    /// Returns the lower triangular factor.
+    pub fn l(&self) -> Matrix<T, O> {
+        let n = self.n;
+        let data = (0..n * n)
+            .map(|idx| {
+                if idx % n >= idx / n {
+                    self.factor[idx]
+                } else {
+                    T::zero()
+                }
+            })
+            .collect();
+
+        from_storage::<T, O, ColMajor>((n, n), data)
+    }
+
+    /// Updates the factorization to that of `A + x * x^T` in `O(n^2)` operations.
+    pub fn update(&mut self, x: &[T]) -> Result<(), MatrixError> {
+        self.rank_one(x, false)
+    }
+
+    /// Updates the factorization to that of `A - x * x^T` in `O(n^2)` operations. If the
+    /// result would not be positive definite, the factorization is left unchanged.
+    pub fn downdate(&mut self, x: &[T]) -> Result<(), MatrixError> {
+        self.rank_one(x, true)
+    }
+
+    /// Applies a sequence of plane rotations (hyperbolic ones when downdating) that folds
+    /// `x` into `L` column by column.
+    fn rank_one(&mut self, x: &[T], downdate: bool) -> Result<(), MatrixError> {
+        let n = self.n;
+        if x.len() != n {
+            return Err(MatrixError::DimensionMismatch {
+                expected: (n, 1),
+                found: (x.len(), 1),
+            });
+        }
+
+        let mut l = self.factor.clone();
+        let mut w = x.to_vec();
+        for k in 0..n {
+            let l_kk = l[k * n + k];
+            let r = if downdate {
+                let r2 = (l_kk - w[k]) * (l_kk + w[k]);
+                if r2.is_nan() || r2 <= T::zero() {
+                    return Err(MatrixError::NotPositiveDefinite { pivot: k });
+                }
+                r2.sqrt()
+            } else {
+                l_kk.hypot(w[k])
+            };
+
+            let (c, s) = (r / l_kk, w[k] / l_kk);
+            let sign = if downdate { -T::one() } else { T::one() };
+            l[k * n + k] = r;
+
+            let col = &mut l[k * n + k + 1..(k + 1) * n];
+            let w = &mut w[k + 1..];
+            simd::axpy(sign * s, w, col);
+            simd::scale(col, T::one() / c);
+            simd::scale(w, c);
+            simd::axpy(-s, col, w);
+        }
+
+        self.factor = l;
+        Ok(())
+    }
+
+    fn solve_vec(&self, b: &mut [T]) {
+        let (n, l) = (self.n, &self.factor);
+
+        for k in 0..n {
+            b[k] = b[k] / l[k * n + k];
+            let bk = b[k];
+            if bk != T::zero() {
+                simd::axpy(-bk, &l[k * n + k + 1..(k + 1) * n], &mut b[k + 1..]);
+            }
+        }
+
+        for k in (0..n).rev() {
+            b[k] = (b[k] - simd::dot(&l[k * n + k + 1..(k + 1) * n], &b[k + 1..])) / l[k * n + k];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linalg::test_util::{assert_close, from_rows};
+    use crate::RowMajor;
+
+    fn spd() -> Matrix<f64, RowMajor> {
+        from_rows(&[
+            &[4.0, 12.0, -16.0],
+            &[12.0, 37.0, -43.0],
+            &[-16.0, -43.0, 98.0],
+        ])
+    }
+
+    #[test]
+    fn factor_solve_det() {
+        let a = spd();
+        let chol = a.cholesky().unwrap();
+
+        let l = chol.l();
+        let expected =
+            from_rows::<RowMajor>(&[&[2.0, 0.0, 0.0], &[6.0, 1.0, 0.0], &[-8.0, 5.0, 3.0]]);
+        assert_close(&l, &expected, 1e-12);
+        assert_close(&(&l * &l.transpose().unwrap()), &a, 1e-12);
+
+        let b = from_rows::<ColMajor>(&[&[1.0, 0.0], &[2.0, 1.0], &[3.0, 0.0]]);
+        assert_close(&(&a * &chol.solve(&b).unwrap()), &b, 1e-10);
+
+        assert!((chol.det() - 36.0).abs() < 1e-10);
+        assert!((chol.log_det() - 36f64.ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn reports_failed_pivot() {
+        let a = from_rows::<RowMajor>(&[&[1.0, 2.0, 0.0], &[2.0, 1.0, 0.0], &[0.0, 0.0, 1.0]]);
+        assert!(matches!(
+            a.cholesky().err(),
+            Some(MatrixError::NotPositiveDefinite { pivot: 1 })
+        ));
+        assert!(a.cholesky().err().unwrap().to_string().contains("pivot 1"));
+    }
+
+    #[test]
+    fn rank_one_update_and_downdate() {
+        let a = spd();
+        let x = [1.0, -2.0, 0.5];
+        let mut xxt: Matrix<f64, RowMajor> = Matrix::new(3, 3).unwrap();
+        for i in 0..3 {
+            for j in 0..3 {
+                xxt[(i, j)] = x[i] * x[j];
+            }
+        }
+
+        let mut chol = a.cholesky().unwrap();
+        chol.update(&x).unwrap();
+        let expected = (&a + &xxt).cholesky().unwrap();
+        assert_close(&chol.l(), &expected.l(), 1e-10);
+
+        chol.downdate(&x).unwrap();
+        assert_close(&chol.l(), &a.cholesky().unwrap().l(), 1e-10);
+
+        let before = chol.l();
+        assert!(matches!(
+            chol.downdate(&[10.0, 0.0, 0.0]).err(),
+            Some(MatrixError::NotPositiveDefinite { pivot: 0 })
+        ));
+        assert_close(&chol.l(), &before, 0.0);
+    }
+}
@@ -0,0 +1,130 @@
+use num_traits::{Float, Num};
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// A complex number `re + im * i`, used for the eigenvalues and eigenvectors of real
+/// nonsymmetric matrices.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Complex<T> {
+    pub re: T,
+    pub im: T,
+}
+
+impl<T> Complex<T> {
+    pub fn new(re: T, im: T) -> Self {
+        Complex { re, im }
+    }
+}
+
+impl<T: Copy + Neg<Output = T>> Complex<T> {
+    pub fn conj(self) -> Self {
+        Complex::new(self.re, -self.im)
+    }
+}
+
+impl<T: Float> Complex<T> {
+    /// Returns the modulus `|z|`.
+    pub fn abs(self) -> T {
+        self.re.hypot(self.im)
+    }
+
+    pub fn is_real(self) -> bool {
+        self.im == T::zero()
+    }
+}
+
+impl<T: Num + Copy> From<T> for Complex<T> {
+    fn from(re: T) -> Self {
+        Complex::new(re, T::zero())
+    }
+}
+
+impl<T: Num + Copy> Add for Complex<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Complex::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl<T: Num + Copy> Sub for Complex<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Complex::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl<T: Num + Copy> Mul for Complex<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Complex::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl<T: Num + Copy> Mul<T> for Complex<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: T) -> Self {
+        Complex::new(self.re * rhs, self.im * rhs)
+    }
+}
+
+impl<T: Float> Div for Complex<T> {
+    type Output = Self;
+
+    /// Smith's algorithm, which avoids overflow in the intermediate products.
+    fn div(self, rhs: Self) -> Self {
+        if rhs.re.abs() >= rhs.im.abs() {
+            let r = rhs.im / rhs.re;
+            let d = rhs.re + rhs.im * r;
+            Complex::new((self.re + self.im * r) / d, (self.im - self.re * r) / d)
+        } else {
+            let r = rhs.re / rhs.im;
+            let d = rhs.re * r + rhs.im;
+            Complex::new((self.re * r + self.im) / d, (self.im * r - self.re) / d)
+        }
+    }
+}
+
+impl<T: Copy + Neg<Output = T>> Neg for Complex<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Complex::new(-self.re, -self.im)
+    }
+}
+
+impl<T: Float + fmt::Display> fmt::Display for Complex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.im.is_sign_negative() {
+            write!(f, "{}-{}i", self.re, -self.im)
+        } else {
+            write!(f, "{}+{}i", self.re, self.im)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let a = Complex::new(1.0, 2.0);
+        let b = Complex::new(3.0, -4.0);
+
+        assert_eq!(a + b, Complex::new(4.0, -2.0));
+        assert_eq!(a - b, Complex::new(-2.0, 6.0));
+        assert_eq!(a * b, Complex::new(11.0, 2.0));
+        assert_eq!((a * b) / b, a);
+        assert_eq!(a * 2.0, Complex::new(2.0, 4.0));
+        assert_eq!(-a.conj(), Complex::new(-1.0, 2.0));
+        assert_eq!(b.abs(), 5.0);
+        assert_eq!(b.to_string(), "3-4i");
+    }
+}
@@ -0,0 +1,799 @@
+use super::{householder, rotate_cols, subdiagonal_reflector_product, Complex, MatrixError};
+use crate::transpose::{from_storage, storage_in};
+use crate::{simd, ColMajor, Matrix, Order, RealField};
+use num_traits::Float;
+use std::ops::Range;
+
+/// Real Schur decomposition, `A = Z * T * Z^T`.
+///
+/// `Z` is orthogonal and `T` is upper quasi-triangular: real eigenvalues sit on its diagonal,
+/// and each complex conjugate pair `a ± b i` forms a 2x2 block `[[a, x], [y, a]]` with
+/// `x * y < 0`.
+pub struct Schur<T, O> {
+    t: Matrix<T, O>,
+    z: Matrix<T, O>,
+}
+
+/// Eigenvalues and eigenvectors of a real nonsymmetric matrix.
+///
+/// The eigenvalues are in the order they appear on the diagonal of the Schur form, with the
+/// members of a complex conjugate pair next to each other, positive imaginary part first.
+/// Column `j` of the eigenvector matrices belongs to eigenvalue `j`; each eigenvector has
+/// unit Euclidean norm and a real largest component.
+pub struct Eigen<T, O> {
+    values: Vec<Complex<T>>,
+    vectors: Matrix<Complex<T>, O>,
+    left_vectors: Option<Matrix<Complex<T>, O>>,
+}
+
+impl<T: RealField, O: Order> Matrix<T, O> {
+    /// Computes the real Schur form by Householder reduction to Hessenberg form followed by
+    /// Francis double-shift QR iterations.
+    pub fn schur(&self) -> Result<Schur<T, O>, MatrixError> {
+        let n = self.check_square()?;
+        let (t, z) = real_schur(storage_in::<T, ColMajor, O>(self).into_owned(), n, true)?;
+
+        Ok(Schur {
+            t: from_storage::<T, O, ColMajor>((n, n), t),
+            z: from_storage::<T, O, ColMajor>((n, n), z),
+        })
+    }
+
+    /// Computes only the eigenvalues, which skips accumulating the Schur vectors.
+    pub fn eigenvalues(&self) -> Result<Vec<Complex<T>>, MatrixError> {
+        let n = self.check_square()?;
+        let (t, _) = real_schur(storage_in::<T, ColMajor, O>(self).into_owned(), n, false)?;
+        Ok(schur_eigenvalues(&t, n))
+    }
+
+    /// Computes the eigenvalues and right eigenvectors, `A * v = λ * v`.
+    pub fn eigen(&self) -> Result<Eigen<T, O>, MatrixError> {
+        Eigen::compute(self, false)
+    }
+
+    /// Computes the eigenvalues with both right eigenvectors and left eigenvectors,
+    /// `u^H * A = λ * u^H`.
+    pub fn eigen_with_left(&self) -> Result<Eigen<T, O>, MatrixError> {
+        Eigen::compute(self, true)
+    }
+}
+
+impl<T: RealField, O: Order> Schur<T, O> {
+    /// Returns the quasi-triangular factor.
+    pub fn t(&self) -> &Matrix<T, O> {
+        &self.t
+    }
+
+    /// Returns the orthogonal factor, whose columns are the Schur vectors.
+    pub fn z(&self) -> &Matrix<T, O> {
+        &self.z
+    }
+
+    pub fn eigenvalues(&self) -> Vec<Complex<T>> {
+        let n = self.t.num_rows;
+        schur_eigenvalues(&storage_in::<T, ColMajor, O>(&self.t), n)
+    }
+}
+
+impl<T: RealField, O: Order> Eigen<T, O> {
+    fn compute(a: &Matrix<T, O>, left: bool) -> Result<Self, MatrixError> {
+        let n = a.check_square()?;
+        let (t, z) = real_schur(storage_in::<T, ColMajor, O>(a).into_owned(), n, true)?;
+        let values = schur_eigenvalues(&t, n);
+
+        let vectors = eigenvectors(&t, &z, n, &values, false);
+        let left_vectors = if left {
+            let vectors = eigenvectors(&t, &z, n, &values, true);
+            Some(from_storage::<Complex<T>, O, ColMajor>((n, n), vectors))
+        } else {
+            None
+        };
+
+        Ok(Eigen {
+            values,
+            vectors: from_storage::<Complex<T>, O, ColMajor>((n, n), vectors),
+            left_vectors,
+        })
+    }
+
+    pub fn values(&self) -> &[Complex<T>] {
+        &self.values
+    }
+
+    /// Returns the right eigenvectors as the columns of a matrix.
+    pub fn vectors(&self) -> &Matrix<Complex<T>, O> {
+        &self.vectors
+    }
+
+    /// Returns the left eigenvectors as the columns of a matrix, if they were computed.
+    pub fn left_vectors(&self) -> Option<&Matrix<Complex<T>, O>> {
+        self.left_vectors.as_ref()
+    }
+}
+
+/// Reduces the column-major `n x n` matrix `h` to real Schur form, returning `T` and, if
+/// `want_z` is set, the Schur vectors `Z`.
+fn real_schur<T: Float + 'static>(
+    mut h: Vec<T>,
+    n: usize,
+    want_z: bool,
+) -> Result<(Vec<T>, Vec<T>), MatrixError> {
+    let tau = hessenberg(&mut h, n);
+    let mut z = if want_z {
+        subdiagonal_reflector_product(&h, &tau, n)
+    } else {
+        Vec::new()
+    };
+
+    for j in 0..n {
+        for i in j + 2..n {
+            h[j * n + i] = T::zero();
+        }
+    }
+
+    hessenberg_qr(&mut h, &mut z, n)?;
+    Ok((h, z))
+}
+
+/// Reduces the column-major matrix `a` to upper Hessenberg form `Q^T * A * Q` with
+/// Householder reflectors, stored as described in `subdiagonal_reflector_product`.
+fn hessenberg<T: Float + 'static>(a: &mut [T], n: usize) -> Vec<T> {
+    let mut tau = Vec::with_capacity(n.saturating_sub(2));
+    let mut w = vec![T::zero(); n];
+
+    for k in 0..n.saturating_sub(2) {
+        let t = householder(&mut a[k * n + k + 1..(k + 1) * n]);
+        tau.push(t);
+        if t == T::zero() {
+            continue;
+        }
+
+        let (head, tail) = a.split_at_mut((k + 1) * n);
+        let v = &head[k * n + k + 2..(k + 1) * n];
+        let vj = |idx: usize| if idx == 0 { T::one() } else { v[idx - 1] };
+
+        for col in tail.chunks_exact_mut(n) {
+            super::apply_householder(v, t, &mut col[k + 1..]);
+        }
+
+        // Every row changes when the reflector is applied from the right: `A - t * (A v) v^T`.
+        w.iter_mut().for_each(|x| *x = T::zero());
+        for (idx, col) in tail.chunks_exact(n).enumerate() {
+            simd::axpy(vj(idx), col, &mut w);
+        }
+        for (idx, col) in tail.chunks_exact_mut(n).enumerate() {
+            simd::axpy(-t * vj(idx), &w, col);
+        }
+    }
+
+    tau
+}
+
+/// Reduces the column-major upper Hessenberg matrix `h` to real Schur form with Francis
+/// double-shift QR sweeps, deflating from the bottom (Golub and Van Loan, algorithm 7.5.2).
+/// The transformations are applied to the whole matrix so that `h` ends up as `T`, and are
+/// accumulated into the columns of `z` unless it is empty.
+fn hessenberg_qr<T: Float + 'static>(
+    h: &mut [T],
+    z: &mut [T],
+    n: usize,
+) -> Result<(), MatrixError> {
+    let idx = |i: usize, j: usize| j * n + i;
+    let eps = T::epsilon();
+    let norm = h.iter().fold(T::zero(), |max, x| max.max(x.abs()));
+
+    let max_sweeps = 30 * n;
+    let mut sweeps = 0;
+    let mut since_deflation = 0;
+    let mut end = n;
+    while end > 0 {
+        let hi = end - 1;
+
+        // Find the start of the trailing unreduced block.
+        let mut lo = hi;
+        while lo > 0 {
+            let mut scale = h[idx(lo - 1, lo - 1)].abs() + h[idx(lo, lo)].abs();
+            if scale == T::zero() {
+                scale = norm;
+            }
+            if h[idx(lo, lo - 1)].abs() <= eps * scale {
+                h[idx(lo, lo - 1)] = T::zero();
+                break;
+            }
+            lo -= 1;
+        }
+
+        if lo == hi {
+            end -= 1;
+            since_deflation = 0;
+            continue;
+        }
+        if lo + 1 == hi {
+            standardize_block(h, z, n, lo);
+            end -= 2;
+            since_deflation = 0;
+            continue;
+        }
+
+        sweeps += 1;
+        since_deflation += 1;
+        if sweeps > max_sweeps {
+            return Err(MatrixError::NoConvergence);
+        }
+
+        // The shifts are the eigenvalues of the trailing 2x2 block, passed as their sum and
+        // product. An ad hoc pair every ten sweeps breaks cycles.
+        let (sum, product) = if since_deflation % 10 == 0 {
+            let x = h[idx(hi, hi - 1)].abs() + h[idx(hi - 1, hi - 2)].abs();
+            (T::from(1.5).unwrap() * x, x * x)
+        } else {
+            let (a, b) = (h[idx(hi - 1, hi - 1)], h[idx(hi - 1, hi)]);
+            let (c, d) = (h[idx(hi, hi - 1)], h[idx(hi, hi)]);
+            (a + d, a * d - b * c)
+        };
+
+        francis_step(h, z, n, lo..hi + 1, sum, product);
+    }
+
+    Ok(())
+}
+
+/// Performs one implicit double-shift sweep on the active block `window`, chasing a 3x3
+/// bulge from its top left to its bottom right corner.
+fn francis_step<T: Float + 'static>(
+    h: &mut [T],
+    z: &mut [T],
+    n: usize,
+    window: Range<usize>,
+    sum: T,
+    product: T,
+) {
+    let idx = |i: usize, j: usize| j * n + i;
+    let (lo, hi) = (window.start, window.end - 1);
+
+    // First column of `(H - s1 I) (H - s2 I)`.
+    let (h00, h01, h10) = (h[idx(lo, lo)], h[idx(lo, lo + 1)], h[idx(lo + 1, lo)]);
+    let (h11, h21) = (h[idx(lo + 1, lo + 1)], h[idx(lo + 2, lo + 1)]);
+    let mut x = h00 * h00 + h01 * h10 - sum * h00 + product;
+    let mut y = h10 * (h00 + h11 - sum);
+    let mut w = h10 * h21;
+
+    for k in lo..hi - 1 {
+        let mut v = [x, y, w];
+        let tau = householder(&mut v);
+        if tau != T::zero() {
+            let tail = [v[1], v[2]];
+            let first_col = if k > lo { k - 1 } else { lo };
+            reflect_rows(h, n, k, &tail, tau, first_col..n);
+            reflect_cols(h, n, k, &tail, tau, 0..(k + 4).min(hi + 1));
+            reflect_cols(z, n, k, &tail, tau, 0..n);
+        }
+        if k > lo {
+            h[idx(k + 1, k - 1)] = T::zero();
+            h[idx(k + 2, k - 1)] = T::zero();
+        }
+
+        x = h[idx(k + 1, k)];
+        y = h[idx(k + 2, k)];
+        if k + 3 <= hi {
+            w = h[idx(k + 3, k)];
+        }
+    }
+
+    let k = hi - 1;
+    let mut v = [x, y];
+    let tau = householder(&mut v);
+    if tau != T::zero() {
+        let tail = [v[1]];
+        reflect_rows(h, n, k, &tail, tau, k - 1..n);
+        reflect_cols(h, n, k, &tail, tau, 0..hi + 1);
+        reflect_cols(z, n, k, &tail, tau, 0..n);
+    }
+    h[idx(hi, hi - 2)] = T::zero();
+}
+
+/// Applies the reflector `I - tau * v * v^T` with `v = [1, tail..]` to rows `k..` of the given
+/// columns of the column-major matrix `h`.
+fn reflect_rows<T: Float>(h: &mut [T], n: usize, k: usize, tail: &[T], tau: T, cols: Range<usize>) {
+    for j in cols {
+        let col = &mut h[j * n + k..j * n + k + tail.len() + 1];
+        let w = tail
+            .iter()
+            .zip(&col[1..])
+            .fold(col[0], |w, (&v, &x)| w + v * x);
+        let w = tau * w;
+        col[0] = col[0] - w;
+        for (x, &v) in col[1..].iter_mut().zip(tail) {
+            *x = *x - w * v;
+        }
+    }
+}
+
+/// Applies the reflector of [`reflect_rows`] from the right to columns `k..` of the given rows.
+/// Does nothing if `h` is empty.
+fn reflect_cols<T: Float>(h: &mut [T], n: usize, k: usize, tail: &[T], tau: T, rows: Range<usize>) {
+    if h.is_empty() {
+        return;
+    }
+
+    for i in rows {
+        let w = tail
+            .iter()
+            .enumerate()
+            .fold(h[k * n + i], |w, (l, &v)| w + v * h[(k + 1 + l) * n + i]);
+        let w = tau * w;
+        h[k * n + i] = h[k * n + i] - w;
+        for (l, &v) in tail.iter().enumerate() {
+            h[(k + 1 + l) * n + i] = h[(k + 1 + l) * n + i] - w * v;
+        }
+    }
+}
+
+/// Rotates the deflated 2x2 block at `k` into standard form: upper triangular if its
+/// eigenvalues are real, and with equal diagonal entries otherwise. The rotation is applied
+/// to the rest of `h` and accumulated into `z` so that `h` stays similar to the input.
+fn standardize_block<T: Float>(h: &mut [T], z: &mut [T], n: usize, k: usize) {
+    let idx = |i: usize, j: usize| j * n + i;
+    let (a, b, c, d) = (
+        h[idx(k, k)],
+        h[idx(k, k + 1)],
+        h[idx(k + 1, k)],
+        h[idx(k + 1, k + 1)],
+    );
+    let ([a, b, c, d], cs, sn) = standardize_2x2(a, b, c, d);
+    h[idx(k, k)] = a;
+    h[idx(k, k + 1)] = b;
+    h[idx(k + 1, k)] = c;
+    h[idx(k + 1, k + 1)] = d;
+
+    for j in k + 2..n {
+        let (x, y) = (h[idx(k, j)], h[idx(k + 1, j)]);
+        h[idx(k, j)] = cs * x + sn * y;
+        h[idx(k + 1, j)] = cs * y - sn * x;
+    }
+    for i in 0..k {
+        let (x, y) = (h[idx(i, k)], h[idx(i, k + 1)]);
+        h[idx(i, k)] = cs * x + sn * y;
+        h[idx(i, k + 1)] = cs * y - sn * x;
+    }
+    rotate_cols(z, n, k, k + 1, cs, sn);
+}
+
+/// Computes the standardized form of `[[a, b], [c, d]]` and the rotation `(cs, sn)` with
+/// `[[a, b], [c, d]] = [[cs, -sn], [sn, cs]] * standardized * [[cs, sn], [-sn, cs]]`,
+/// following LAPACK's `xLANV2`.
+fn standardize_2x2<T: Float>(mut a: T, mut b: T, mut c: T, mut d: T) -> ([T; 4], T, T) {
+    let (zero, one) = (T::zero(), T::one());
+    let half = one / (one + one);
+    let eps = T::epsilon();
+
+    let (mut cs, mut sn);
+    if c == zero {
+        cs = one;
+        sn = zero;
+    } else if b == zero {
+        // Swap the rows and columns.
+        cs = zero;
+        sn = one;
+        std::mem::swap(&mut a, &mut d);
+        b = -c;
+        c = zero;
+    } else if a == d && b.signum() != c.signum() {
+        cs = one;
+        sn = zero;
+    } else {
+        let temp = a - d;
+        let p = half * temp;
+        let bcmax = b.abs().max(c.abs());
+        let bcmis = b.abs().min(c.abs()) * b.signum() * c.signum();
+        let scale = p.abs().max(bcmax);
+        let z = p / scale * p + bcmax / scale * bcmis;
+
+        if z >= T::from(4.0).unwrap() * eps {
+            // Real eigenvalues: make the block upper triangular.
+            let z = p + (scale.sqrt() * z.sqrt()).copysign(p);
+            a = d + z;
+            d = d - bcmax / z * bcmis;
+            let tau = c.hypot(z);
+            cs = z / tau;
+            sn = c / tau;
+            b = b - c;
+            c = zero;
+        } else {
+            // Complex or nearly equal real eigenvalues: make the diagonal entries equal.
+            let sigma = b + c;
+            let tau = sigma.hypot(temp);
+            cs = (half * (one + sigma.abs() / tau)).sqrt();
+            sn = -(p / (tau * cs)) * one.copysign(sigma);
+
+            let (aa, bb) = (a * cs + b * sn, -a * sn + b * cs);
+            let (cc, dd) = (c * cs + d * sn, -c * sn + d * cs);
+            a = aa * cs + cc * sn;
+            b = bb * cs + dd * sn;
+            c = -aa * sn + cc * cs;
+            d = -bb * sn + dd * cs;
+
+            let temp = half * (a + d);
+            a = temp;
+            d = temp;
+
+            if c != zero {
+                if b == zero {
+                    b = -c;
+                    c = zero;
+                    let temp = cs;
+                    cs = -sn;
+                    sn = temp;
+                } else if b.signum() == c.signum() {
+                    // Real eigenvalues after all.
+                    let (sab, sac) = (b.abs().sqrt(), c.abs().sqrt());
+                    let p = (sab * sac).copysign(c);
+                    let tau = one / (b + c).abs().sqrt();
+                    a = temp + p;
+                    d = temp - p;
+                    b = b - c;
+                    c = zero;
+                    let (cs1, sn1) = (sab * tau, sac * tau);
+                    let temp = cs * cs1 - sn * sn1;
+                    sn = cs * sn1 + sn * cs1;
+                    cs = temp;
+                }
+            }
+        }
+    }
+
+    ([a, b, c, d], cs, sn)
+}
+
+/// Returns the diagonal blocks of the quasi-triangular column-major `t` as
+/// `(first index, size)`.
+fn schur_blocks<T: Float>(t: &[T], n: usize) -> Vec<(usize, usize)> {
+    let mut blocks = Vec::new();
+    let mut k = 0;
+    while k < n {
+        let size = if k + 1 < n && t[k * n + k + 1] != T::zero() {
+            2
+        } else {
+            1
+        };
+        blocks.push((k, size));
+        k += size;
+    }
+    blocks
+}
+
+fn schur_eigenvalues<T: Float>(t: &[T], n: usize) -> Vec<Complex<T>> {
+    let mut values = Vec::with_capacity(n);
+    for (k, size) in schur_blocks(t, n) {
+        let a = t[k * n + k];
+        if size == 1 {
+            values.push(Complex::new(a, T::zero()));
+        } else {
+            // Standardized blocks have equal diagonal entries.
+            let (b, c) = (t[(k + 1) * n + k], t[k * n + k + 1]);
+            let im = b.abs().sqrt() * c.abs().sqrt();
+            values.push(Complex::new(a, im));
+            values.push(Complex::new(a, -im));
+        }
+    }
+    values
+}
+
+/// Computes eigenvectors from the real Schur form `A = Z * T * Z^T` as the columns of a
+/// column-major `n x n` complex matrix, right eigenvectors by back substitution with `T` or
+/// left eigenvectors by forward substitution with `T^T`.
+///
+/// Pivots that are tiny relative to the eigenvalue are perturbed as in LAPACK's `xTREVC`, so
+/// that defective matrices still get finite vectors.
+fn eigenvectors<T: Float>(
+    t: &[T],
+    z: &[T],
+    n: usize,
+    values: &[Complex<T>],
+    left: bool,
+) -> Vec<Complex<T>> {
+    // Entry `(i, j)` of `T` or of `T^T`.
+    let at = |i: usize, j: usize| {
+        if left {
+            t[i * n + j]
+        } else {
+            t[j * n + i]
+        }
+    };
+    let zero = Complex::new(T::zero(), T::zero());
+    let one = Complex::new(T::one(), T::zero());
+    let big = T::max_value() * T::epsilon();
+
+    let blocks = schur_blocks(t, n);
+    let mut out = vec![zero; n * n];
+    let mut x = vec![zero; n];
+
+    for (b, &(k, size)) in blocks.iter().enumerate() {
+        let lambda = values[k];
+        let small = (T::epsilon() * lambda.abs()).max(T::min_positive_value());
+        x.iter_mut().for_each(|x| *x = zero);
+
+        // The eigenvector of the diagonal block itself.
+        if size == 1 {
+            x[k] = one;
+        } else {
+            let (b12, b21) = (at(k, k + 1), at(k + 1, k));
+            if b12.abs() >= b21.abs() {
+                x[k] = one;
+                x[k + 1] = (lambda - at(k, k).into()) / b12.into();
+            } else {
+                x[k + 1] = one;
+                x[k] = (lambda - at(k + 1, k + 1).into()) / b21.into();
+            }
+        }
+
+        // Substitute through the other blocks: upwards for `T`, downwards for `T^T`.
+        let others: Vec<(usize, usize)> = if left {
+            blocks[b + 1..].to_vec()
+        } else {
+            blocks[..b].iter().rev().copied().collect()
+        };
+        let (mut from, mut to) = (k, k + size);
+        for (i, s) in others {
+            let rhs =
+                |r: usize, x: &[Complex<T>]| (from..to).fold(zero, |sum, q| sum - x[q] * at(r, q));
+            let shifted = |r: usize| Complex::from(at(r, r)) - lambda;
+
+            if s == 1 {
+                let mut d = shifted(i);
+                if d.abs() < small {
+                    d = small.into();
+                }
+                x[i] = rhs(i, &x) / d;
+            } else {
+                let m = [
+                    [shifted(i), at(i, i + 1).into()],
+                    [at(i + 1, i).into(), shifted(i + 1)],
+                ];
+                let [x0, x1] = solve_2x2(m, [rhs(i, &x), rhs(i + 1, &x)], small);
+                x[i] = x0;
+                x[i + 1] = x1;
+            }
+
+            if left {
+                to = i + s;
+            } else {
+                from = i;
+            }
+
+            // Rescale before the entries can overflow.
+            let max = x[from..to]
+                .iter()
+                .fold(T::zero(), |max, x| max.max(x.abs()));
+            if max > big {
+                x[from..to]
+                    .iter_mut()
+                    .for_each(|x| *x = *x * (T::one() / max));
+            }
+        }
+
+        // The eigenvector of `A` is `Z * x`; a left eigenvector is `Z * conj(x)`.
+        let col = &mut out[k * n..(k + 1) * n];
+        for q in from..to {
+            let xq = if left { x[q].conj() } else { x[q] };
+            if xq != zero {
+                for (c, &zq) in col.iter_mut().zip(&z[q * n..(q + 1) * n]) {
+                    *c = *c + xq * zq;
+                }
+            }
+        }
+        normalize(col, !lambda.is_real());
+
+        if size == 2 {
+            let (first, second) = out[k * n..(k + 2) * n].split_at_mut(n);
+            for (c, &v) in second.iter_mut().zip(first.iter()) {
+                *c = v.conj();
+            }
+        }
+    }
+
+    out
+}
+
+/// Solves a 2x2 complex system by Gaussian elimination with complete pivoting, raising
+/// pivots smaller than `small` to `small` (LAPACK's `xLALN2`).
+fn solve_2x2<T: Float>(m: [[Complex<T>; 2]; 2], rhs: [Complex<T>; 2], small: T) -> [Complex<T>; 2] {
+    let (mut pi, mut pj) = (0, 0);
+    for i in 0..2 {
+        for j in 0..2 {
+            if m[i][j].abs() > m[pi][pj].abs() {
+                (pi, pj) = (i, j);
+            }
+        }
+    }
+    let (qi, qj) = (1 - pi, 1 - pj);
+
+    let mut pivot = m[pi][pj];
+    if pivot.abs() < small {
+        pivot = small.into();
+    }
+    let l = m[qi][pj] / pivot;
+    let mut u = m[qi][qj] - l * m[pi][qj];
+    if u.abs() < small {
+        u = small.into();
+    }
+
+    let mut x = [Complex::from(T::zero()); 2];
+    x[qj] = (rhs[qi] - l * rhs[pi]) / u;
+    x[pj] = (rhs[pi] - m[pi][qj] * x[qj]) / pivot;
+    x
+}
+
+/// Scales `v` to unit Euclidean norm and, if `rotate` is set, rotates it so that its largest
+/// component is real and positive.
+fn normalize<T: Float>(v: &mut [Complex<T>], rotate: bool) {
+    let norm = v.iter().fold(T::zero(), |norm, c| norm.hypot(c.abs()));
+    if norm == T::zero() {
+        return;
+    }
+
+    let mut scale = Complex::new(T::one() / norm, T::zero());
+    if rotate {
+        let largest = v
+            .iter()
+            .fold(v[0], |max, &c| if c.abs() > max.abs() { c } else { max });
+        scale = scale * (largest.conj() * (T::one() / largest.abs()));
+    }
+    v.iter_mut().for_each(|c| *c = *c * scale);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linalg::test_util::{from_rows, random};
+    use crate::RowMajor;
+
+    fn close(a: Complex<f64>, b: Complex<f64>, tol: f64) -> bool {
+        (a - b).abs() <= tol
+    }
+
+    /// Checks `A * v = λ * v` for every right eigenvector and `u^H * A = λ * u^H` for every
+    /// left one.
+    fn assert_eigenpairs<O: Order>(a: &Matrix<f64, O>, eig: &Eigen<f64, O>, tol: f64) {
+        let n = a.num_rows;
+        for (j, &lambda) in eig.values().iter().enumerate() {
+            let v = eig.vectors();
+            for i in 0..n {
+                let av = (0..n).fold(Complex::default(), |s, q| s + v[(q, j)] * a[(i, q)]);
+                assert!(close(av, lambda * v[(i, j)], tol), "right pair {}", j);
+            }
+
+            if let Some(u) = eig.left_vectors() {
+                for i in 0..n {
+                    let ua =
+                        (0..n).fold(Complex::default(), |s, q| s + u[(q, j)].conj() * a[(q, i)]);
+                    assert!(close(ua, lambda * u[(i, j)].conj(), tol), "left pair {}", j);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn complex_pairs() {
+        let rotation = from_rows::<RowMajor>(&[&[0.0, -1.0], &[1.0, 0.0]]);
+        let values = rotation.eigenvalues().unwrap();
+        assert!(close(values[0], Complex::new(0.0, 1.0), 1e-15));
+        assert!(close(values[1], Complex::new(0.0, -1.0), 1e-15));
+
+        let a = from_rows::<RowMajor>(&[&[2.0, 0.0, 0.0], &[0.0, 3.0, 4.0], &[0.0, -4.0, 3.0]]);
+        let eig = a.eigen_with_left().unwrap();
+        let mut values = eig.values().to_vec();
+        values.sort_by(|a, b| a.re.partial_cmp(&b.re).unwrap());
+        assert!(close(values[0], Complex::new(2.0, 0.0), 1e-14));
+        assert!(close(values[1].conj(), values[2], 0.0));
+        assert!(
+            close(values[1], Complex::new(3.0, 4.0), 1e-14)
+                || close(values[1], Complex::new(3.0, -4.0), 1e-14)
+        );
+        assert_eigenpairs(&a, &eig, 1e-14);
+    }
+
+    #[test]
+    fn schur_form() {
+        for n in [1, 2, 3, 10, 40] {
+            let a = random::<ColMajor>(n, n, n as u64);
+            let schur = a.schur().unwrap();
+            let (t, z) = (schur.t(), schur.z());
+
+            let ztz = &z.transpose().unwrap() * z;
+            let zt = &(z * t) * &z.transpose().unwrap();
+            for i in 0..n {
+                for j in 0..n {
+                    let identity = if i == j { 1.0 } else { 0.0 };
+                    assert!((ztz[(i, j)] - identity).abs() < 1e-12);
+                    assert!((zt[(i, j)] - a[(i, j)]).abs() < 1e-12);
+                    if i > j + 1 || (i == j + 1 && j + 2 < n && t[(j + 2, j + 1)] != 0.0) {
+                        assert_eq!(t[(i, j)], 0.0);
+                    }
+                }
+            }
+
+            // Trace and determinant are preserved.
+            let values = schur.eigenvalues();
+            let trace = values.iter().fold(Complex::default(), |s, &v| s + v);
+            assert!((trace.re - (0..n).map(|i| a[(i, i)]).sum::<f64>()).abs() < 1e-12);
+            assert!(trace.im.abs() < 1e-12);
+            let det = values.iter().fold(Complex::from(1.0), |p, &v| p * v);
+            assert!((det.re - a.lu().unwrap().det()).abs() < 1e-10 * det.abs().max(1.0));
+        }
+    }
+
+    #[test]
+    fn random_eigenpairs() {
+        for n in [5, 30] {
+            let a = random::<ColMajor>(n, n, 100 + n as u64);
+            let eig = a.eigen_with_left().unwrap();
+            assert!(eig.values().iter().any(|v| !v.is_real()));
+            assert_eigenpairs(&a, &eig, 1e-11);
+
+            let values = a.eigenvalues().unwrap();
+            assert!(values
+                .iter()
+                .zip(eig.values())
+                .all(|(&a, &b)| close(a, b, 1e-12)));
+        }
+    }
+
+    #[test]
+    fn graded_eigenpairs() {
+        // Entries spanning many orders of magnitude give eigenvalue pairs far below the norm.
+        let mut a = random::<ColMajor>(30, 30, 7);
+        for i in 0..30 {
+            for j in 0..30 {
+                a[(i, j)] *= 10f64.powi(-((i + j) as i32) / 4);
+            }
+        }
+        let eig = a.eigen_with_left().unwrap();
+        assert!(eig.values().iter().any(|v| !v.is_real() && v.abs() < 1e-8));
+        assert_eigenpairs(&a, &eig, 1e-14);
+    }
+
+    #[test]
+    fn markov_stationary_distribution() {
+        let p =
+            from_rows::<RowMajor>(&[&[0.9, 0.075, 0.025], &[0.15, 0.8, 0.05], &[0.25, 0.25, 0.5]]);
+        let eig = p.eigen_with_left().unwrap();
+        let j = eig
+            .values()
+            .iter()
+            .position(|v| close(*v, Complex::from(1.0), 1e-12))
+            .unwrap();
+
+        let left = eig.left_vectors().unwrap();
+        let total: f64 = (0..3).map(|i| left[(i, j)].re).sum();
+        let pi: Vec<f64> = (0..3).map(|i| left[(i, j)].re / total).collect();
+        for (x, y) in pi.iter().zip([0.625, 0.3125, 0.0625]) {
+            assert!((x - y).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn defective_and_triangular() {
+        let jordan = from_rows::<RowMajor>(&[&[1.0, 1.0], &[0.0, 1.0]]);
+        let eig = jordan.eigen().unwrap();
+        assert!(eig
+            .values()
+            .iter()
+            .all(|v| close(*v, Complex::from(1.0), 0.0)));
+        assert!(eig
+            .vectors()
+            .data
+            .iter()
+            .all(|c| c.re.is_finite() && c.im.is_finite()));
+        assert_eigenpairs(&jordan, &eig, 1e-12);
+
+        let zero: Matrix<f64, RowMajor> = Matrix::new(3, 3).unwrap();
+        assert_eigenpairs(&zero, &zero.eigen_with_left().unwrap(), 0.0);
+
+        let wide: Matrix<f64, RowMajor> = Matrix::new(2, 3).unwrap();
+        assert!(matches!(
+            wide.eigen().err(),
+            Some(MatrixError::NotSquare { rows: 2, cols: 3 })
+        ));
+    }
+}
@@ -0,0 +1,431 @@
+use super::{solve_columns, split_cols, MatrixError};
+use crate::transpose::{from_storage, storage_in};
+use crate::{simd, ColMajor, Matrix, Order, RealField};
+use std::marker::PhantomData;
+
+/// Bunch–Kaufman factorization of a symmetric matrix, `P * A * P^T = L * D * L^T`.
+///
+/// `L` is unit lower triangular and `D` is block diagonal with 1x1 and 2x2 blocks. The
+/// column-major buffer holds `L` below the diagonal and the diagonal of `D` on it;
+/// `offdiag[k]` is the subdiagonal entry of a 2x2 block starting at `k`, and zero otherwise.
+pub struct Ldlt<T, O> {
+    n: usize,
+    factors: Vec<T>,
+    offdiag: Vec<T>,
+    perm: Vec<usize>,
+    _order: PhantomData<O>,
+}
+
+impl<T: RealField, O: Order> Matrix<T, O> {
+    /// Factorizes a symmetric, possibly indefinite matrix with Bunch–Kaufman diagonal
+    /// pivoting. Only the lower triangle is read.
+    ///
+    /// Fails with `MatrixError::Singular` if no usable pivot block is left at some step.
+    pub fn ldlt(&self) -> Result<Ldlt<T, O>, MatrixError> {
+        if !self.is_square() {
+            return Err(MatrixError::NotSquare {
+                rows: self.num_rows,
+                cols: self.num_cols,
+            });
+        }
+
+        let n = self.num_rows;
+        let mut a = storage_in::<T, ColMajor, O>(self).into_owned();
+        let mut offdiag = vec![T::zero(); n];
+        let mut perm: Vec<usize> = (0..n).collect();
+
+        // The growth bound of the Bunch–Kaufman pivoting strategy is smallest for this alpha.
+        let alpha = (T::one() + T::from(17.0).unwrap().sqrt()) / T::from(8.0).unwrap();
+        let max_abs = (0..n)
+            .flat_map(|j| &a[j * n + j..(j + 1) * n])
+            .fold(T::zero(), |max, x| max.max(x.abs()));
+        let tol = max_abs * T::epsilon() * T::from(n).unwrap();
+
+        let mut k = 0;
+        while k < n {
+            let abs_kk = a[k * n + k].abs();
+            let (imax, colmax) = (k + 1..n).fold((k, T::zero()), |(imax, colmax), i| {
+                let v = a[k * n + i].abs();
+                if v > colmax {
+                    (i, v)
+                } else {
+                    (imax, colmax)
+                }
+            });
+
+            if abs_kk.is_nan() || abs_kk.max(colmax) <= tol {
+                return Err(MatrixError::Singular { pivot: k });
+            }
+
+            let (kp, step) = if abs_kk >= alpha * colmax {
+                (k, 1)
+            } else {
+                let rowmax = (k..imax)
+                    .map(|j| a[j * n + imax].abs())
+                    .chain((imax + 1..n).map(|i| a[imax * n + i].abs()))
+                    .fold(T::zero(), T::max);
+
+                if abs_kk * rowmax >= alpha * colmax * colmax {
+                    (k, 1)
+                } else if a[imax * n + imax].abs() >= alpha * rowmax {
+                    (imax, 1)
+                } else {
+                    (imax, 2)
+                }
+            };
+
+            let kk = k + step - 1;
+            if kp != kk {
+                interchange(&mut a, n, kk, kp);
+                perm.swap(kk, kp);
+            }
+
+            if step == 1 {
+                let d = a[k * n + k];
+                for j in k + 1..n {
+                    let (col_k, col_j) = split_cols(&mut a, n, k, j);
+                    let factor = col_k[j] / d;
+                    if factor != T::zero() {
+                        simd::axpy(-factor, &col_k[j..], &mut col_j[j..]);
+                    }
+                }
+                simd::scale(&mut a[k * n + k + 1..(k + 1) * n], T::one() / d);
+            } else {
+                let (d11, d21, d22) = (a[k * n + k], a[k * n + k + 1], a[(k + 1) * n + k + 1]);
+                let det = d11 * d22 - d21 * d21;
+                if det.is_nan() || det == T::zero() {
+                    return Err(MatrixError::Singular { pivot: k });
+                }
+
+                // Columns `k` and `k + 1` below the block become `[c0 c1] * D^-1`.
+                let c0 = a[k * n + k + 2..(k + 1) * n].to_vec();
+                let c1 = a[(k + 1) * n + k + 2..(k + 2) * n].to_vec();
+                for (i, (&c0, &c1)) in c0.iter().zip(&c1).enumerate() {
+                    a[k * n + k + 2 + i] = (d22 * c0 - d21 * c1) / det;
+                    a[(k + 1) * n + k + 2 + i] = (d11 * c1 - d21 * c0) / det;
+                }
+
+                for j in k + 2..n {
+                    let (left, right) = a.split_at_mut(j * n);
+                    let col_j = &mut right[j..n];
+                    let (f0, f1) = (c0[j - k - 2], c1[j - k - 2]);
+                    simd::axpy(-f0, &left[k * n + j..(k + 1) * n], col_j);
+                    simd::axpy(-f1, &left[(k + 1) * n + j..(k + 2) * n], col_j);
+                }
+
+                offdiag[k] = d21;
+                a[k * n + k + 1] = T::zero();
+            }
+
+            k += step;
+        }
+
+        Ok(Ldlt {
+            n,
+            factors: a,
+            offdiag,
+            perm,
+            _order: PhantomData,
+        })
+    }
+}
+
+/// Swaps rows and columns `kk < kp` of the symmetric matrix whose lower triangle is stored in
+/// `a`, along with rows `kk` and `kp` of the columns of `L` already computed.
+fn interchange<T>(a: &mut [T], n: usize, kk: usize, kp: usize) {
+    for j in 0..kk {
+        a.swap(j * n + kk, j * n + kp);
+    }
+    for j in kk + 1..kp {
+        a.swap(kk * n + j, j * n + kp);
+    }
+    a.swap(kk * n + kk, kp * n + kp);
+    for i in kp + 1..n {
+        a.swap(kk * n + i, kp * n + i);
+    }
+}
+
+impl<T: RealField, O: Order> Ldlt<T, O> {
+    /// Solves `A * X = B`.
+    pub fn solve<O2: Order>(&self, b: &Matrix<T, O2>) -> Result<Matrix<T, O2>, MatrixError> {
+        let mut x = b.clone();
+        self.solve_in_place(&mut x)?;
+        Ok(x)
+    }
+
+    /// Solves `A * X = B`, overwriting `B` with `X`.
+    pub fn solve_in_place<O2: Order>(&self, b: &mut Matrix<T, O2>) -> Result<(), MatrixError> {
+        solve_columns(self.n, b, |col| self.solve_vec(col))
+    }
+
+    pub fn det(&self) -> T {
+        self.blocks()
+            .fold(T::one(), |det, (k, size)| det * self.block_det(k, size))
+    }
+
+    /// Returns the sign of the determinant and the natural logarithm of its absolute value.
+    /// Unlike `det`, the logarithm does not overflow for large matrices.
+    pub fn log_det(&self) -> (T, T) {
+        self.blocks()
+            .fold((T::one(), T::zero()), |(sign, log), (k, size)| {
+                let det = self.block_det(k, size);
+                (sign * det.signum(), log + det.abs().ln())
+            })
+    }
+
+    /// Returns the unit lower triangular factor.
+    pub fn l(&self) -> Matrix<T, O> {
+        let n = self.n;
+        let data = (0..n * n)
+            .map(|idx| {
+                let (i, j) = (idx % n, idx / n);
+                if i > j {
+                    self.factors[idx]
+                } else if i == j {
+                    T::one()
+                } else {
+                    T::zero()
+                }
+            })
+            .collect();
+
+        from_storage::<T, O, ColMajor>((n, n), data)
+    }
+
+    /// Returns the block diagonal factor.
+    pub fn d(&self) -> Matrix<T, O> {
+        let n = self.n;
+        let mut data = vec![T::zero(); n * n];
+        for k in 0..n {
+            data[k * n + k] = self.factors[k * n + k];
+            if self.offdiag[k] != T::zero() {
+                data[k * n + k + 1] = self.offdiag[k];
+                data[(k + 1) * n + k] = self.offdiag[k];
+            }
+        }
+
+        from_storage::<T, O, ColMajor>((n, n), data)
+    }
+
+    /// Returns the symmetric permutation: row and column `i` of `P * A * P^T` are row and
+    /// column `permutation()[i]` of `A`.
+    pub fn permutation(&self) -> Vec<usize> {
+        self.perm.clone()
+    }
+
+    /// Updates the factorization to that of `A + x * x^T` in `O(n^2)` operations, keeping
+    /// the pivot order and block structure. Fails with `MatrixError::Singular`, leaving the
+    /// factorization unchanged, if a block of `D` would become singular.
+    pub fn update(&mut self, x: &[T]) -> Result<(), MatrixError> {
+        self.rank_one(x, T::one())
+    }
+
+    /// Updates the factorization to that of `A - x * x^T`; see [`Ldlt::update`].
+    pub fn downdate(&mut self, x: &[T]) -> Result<(), MatrixError> {
+        self.rank_one(x, -T::one())
+    }
+
+    /// Computes `L * D * L^T + sigma * w * w^T` with `w = P * x` one block of `D` at a time.
+    /// Each block absorbs its part of `w`, and the remainder is a rank-one update of the
+    /// trailing blocks with a new `sigma`.
+    fn rank_one(&mut self, x: &[T], mut sigma: T) -> Result<(), MatrixError> {
+        let n = self.n;
+        if x.len() != n {
+            return Err(MatrixError::DimensionMismatch {
+                expected: (n, 1),
+                found: (x.len(), 1),
+            });
+        }
+
+        let mut f = self.factors.clone();
+        let mut offdiag = self.offdiag.clone();
+        let mut w: Vec<T> = self.perm.iter().map(|&p| x[p]).collect();
+        let eps = T::epsilon();
+
+        for (k, size) in self.blocks() {
+            if size == 1 {
+                let (d, wk) = (f[k * n + k], w[k]);
+                let shift = sigma * wk * wk;
+                let d_new = d + shift;
+                if d_new.is_nan() || d_new.abs() <= eps * (d.abs() + shift.abs()) {
+                    return Err(MatrixError::Singular { pivot: k });
+                }
+
+                let beta = sigma * wk / d_new;
+                sigma = sigma * d / d_new;
+                f[k * n + k] = d_new;
+
+                let col = &mut f[k * n + k + 1..(k + 1) * n];
+                let tail = &mut w[k + 1..];
+                simd::axpy(-wk, col, tail);
+                simd::axpy(beta, tail, col);
+            } else {
+                let (w0, w1) = (w[k], w[k + 1]);
+                let d11 = f[k * n + k] + sigma * w0 * w0;
+                let d21 = offdiag[k] + sigma * w0 * w1;
+                let d22 = f[(k + 1) * n + k + 1] + sigma * w1 * w1;
+                let det = d11 * d22 - d21 * d21;
+                if det.is_nan() || det.abs() <= eps * ((d11 * d22).abs() + d21 * d21) {
+                    return Err(MatrixError::Singular { pivot: k });
+                }
+
+                let beta0 = sigma * (d22 * w0 - d21 * w1) / det;
+                let beta1 = sigma * (d11 * w1 - d21 * w0) / det;
+                sigma = sigma - sigma * (w0 * beta0 + w1 * beta1);
+                f[k * n + k] = d11;
+                f[(k + 1) * n + k + 1] = d22;
+                offdiag[k] = d21;
+
+                let (left, right) = f.split_at_mut((k + 1) * n);
+                let col0 = &mut left[k * n + k + 2..];
+                let col1 = &mut right[k + 2..n];
+                let tail = &mut w[k + 2..];
+                simd::axpy(-w0, col0, tail);
+                simd::axpy(-w1, col1, tail);
+                simd::axpy(beta0, tail, col0);
+                simd::axpy(beta1, tail, col1);
+            }
+        }
+
+        self.factors = f;
+        self.offdiag = offdiag;
+        Ok(())
+    }
+
+    /// Iterates over the blocks of `D` as `(first index, size)`.
+    fn blocks(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let mut k = 0;
+        std::iter::from_fn(move || {
+            if k >= self.n {
+                return None;
+            }
+            let size = if self.offdiag[k] != T::zero() { 2 } else { 1 };
+            k += size;
+            Some((k - size, size))
+        })
+    }
+
+    fn block_det(&self, k: usize, size: usize) -> T {
+        let n = self.n;
+        let d11 = self.factors[k * n + k];
+        if size == 1 {
+            d11
+        } else {
+            d11 * self.factors[(k + 1) * n + k + 1] - self.offdiag[k] * self.offdiag[k]
+        }
+    }
+
+    fn solve_vec(&self, b: &mut [T]) {
+        let (n, l) = (self.n, &self.factors);
+        let mut y: Vec<T> = self.perm.iter().map(|&p| b[p]).collect();
+
+        for k in 0..n {
+            let yk = y[k];
+            if yk != T::zero() {
+                simd::axpy(-yk, &l[k * n + k + 1..(k + 1) * n], &mut y[k + 1..]);
+            }
+        }
+
+        for (k, size) in self.blocks() {
+            if size == 1 {
+                y[k] = y[k] / l[k * n + k];
+            } else {
+                let (d11, d21, d22) = (l[k * n + k], self.offdiag[k], l[(k + 1) * n + k + 1]);
+                let det = d11 * d22 - d21 * d21;
+                let (y0, y1) = (y[k], y[k + 1]);
+                y[k] = (d22 * y0 - d21 * y1) / det;
+                y[k + 1] = (d11 * y1 - d21 * y0) / det;
+            }
+        }
+
+        for k in (0..n).rev() {
+            y[k] = y[k] - simd::dot(&l[k * n + k + 1..(k + 1) * n], &y[k + 1..]);
+        }
+
+        for (&p, &y) in self.perm.iter().zip(&y) {
+            b[p] = y;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linalg::test_util::{assert_close, from_rows};
+    use crate::RowMajor;
+
+    /// Checks `P * A * P^T = L * D * L^T`.
+    fn assert_reconstructs(ldlt: &Ldlt<f64, RowMajor>, a: &Matrix<f64, RowMajor>) {
+        let perm = ldlt.permutation();
+        let mut pap = a.clone();
+        for i in 0..a.num_rows {
+            for j in 0..a.num_cols {
+                pap[(i, j)] = a[(perm[i], perm[j])];
+            }
+        }
+
+        let l = ldlt.l();
+        assert_close(&(&(&l * &ldlt.d()) * &l.transpose().unwrap()), &pap, 1e-10);
+    }
+
+    fn indefinite() -> Matrix<f64, RowMajor> {
+        from_rows(&[
+            &[0.0, 1.0, 2.0, 3.0],
+            &[1.0, 0.0, 1.0, -1.0],
+            &[2.0, 1.0, 0.0, 4.0],
+            &[3.0, -1.0, 4.0, 0.0],
+        ])
+    }
+
+    #[test]
+    fn factor_indefinite() {
+        let a = indefinite();
+        let ldlt = a.ldlt().unwrap();
+        assert!(ldlt.blocks().any(|(_, size)| size == 2));
+        assert_reconstructs(&ldlt, &a);
+
+        let b = from_rows::<ColMajor>(&[&[1.0, 0.0], &[2.0, 1.0], &[3.0, 0.0], &[4.0, -1.0]]);
+        assert_close(&(&a * &ldlt.solve(&b).unwrap()), &b, 1e-10);
+
+        let det = a.lu().unwrap().det();
+        assert!((ldlt.det() - det).abs() < 1e-10);
+        let (sign, log) = ldlt.log_det();
+        assert!(sign == det.signum() && (log - det.abs().ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn two_by_two_pivot() {
+        let a = from_rows::<RowMajor>(&[&[0.0, 1.0], &[1.0, 0.0]]);
+        let ldlt = a.ldlt().unwrap();
+        assert_eq!(ldlt.blocks().collect::<Vec<_>>(), vec![(0, 2)]);
+        assert!((ldlt.det() - -1.0).abs() < 1e-15);
+    }
+
+    #[test]
+    fn singular() {
+        let a = from_rows::<RowMajor>(&[&[1.0, 1.0], &[1.0, 1.0]]);
+        assert!(matches!(
+            a.ldlt().err(),
+            Some(MatrixError::Singular { pivot: 1 })
+        ));
+    }
+
+    #[test]
+    fn rank_one_update_and_downdate() {
+        let a = indefinite();
+        let x = [1.0, -2.0, 0.5, 3.0];
+        let mut xxt: Matrix<f64, RowMajor> = Matrix::new(4, 4).unwrap();
+        for i in 0..4 {
+            for j in 0..4 {
+                xxt[(i, j)] = x[i] * x[j];
+            }
+        }
+
+        let mut ldlt = a.ldlt().unwrap();
+        ldlt.update(&x).unwrap();
+        assert_reconstructs(&ldlt, &(&a + &xxt));
+
+        ldlt.downdate(&x).unwrap();
+        assert_reconstructs(&ldlt, &a);
+    }
+}
@@ -0,0 +1,324 @@
+use super::{solve_columns, split_cols, MatrixError};
+use crate::transpose::{from_storage, storage_in};
+use crate::{simd, ColMajor, Matrix, Order, RealField};
+use std::marker::PhantomData;
+
+/// LU factorization with partial pivoting, `P * A = L * U`.
+///
+/// The unit lower triangular `L` and upper triangular `U` are packed into one column-major
+/// buffer. Row interchanges are kept LAPACK style: at step `k`, row `k` was swapped with row
+/// `pivots[k]`.
+pub struct Lu<T, O> {
+    n: usize,
+    factors: Vec<T>,
+    pivots: Vec<usize>,
+    norm1: T,
+    _order: PhantomData<O>,
+}
+
+impl<T: RealField, O: Order> Matrix<T, O> {
+    /// Factorizes a square matrix. Fails with `MatrixError::Singular` if a pivot is zero or
+    /// negligible relative to the largest entry of the matrix.
+    pub fn lu(&self) -> Result<Lu<T, O>, MatrixError> {
+        if !self.is_square() {
+            return Err(MatrixError::NotSquare {
+                rows: self.num_rows,
+                cols: self.num_cols,
+            });
+        }
+
+        let n = self.num_rows;
+        let mut a = storage_in::<T, ColMajor, O>(self).into_owned();
+
+        let norm1 = a
+            .chunks_exact(n)
+            .map(|col| col.iter().fold(T::zero(), |sum, x| sum + x.abs()))
+            .fold(T::zero(), T::max);
+        let max_abs = a.iter().fold(T::zero(), |max, x| max.max(x.abs()));
+        let tol = max_abs * T::epsilon() * T::from(n).unwrap();
+
+        let mut pivots = Vec::with_capacity(n);
+        for k in 0..n {
+            let col = &a[k * n..(k + 1) * n];
+            let p = (k..n).fold(k, |p, i| if col[i].abs() > col[p].abs() { i } else { p });
+
+            if col[p].is_nan() || col[p].abs() <= tol {
+                return Err(MatrixError::Singular { pivot: k });
+            }
+
+            pivots.push(p);
+            if p != k {
+                for j in 0..n {
+                    a.swap(j * n + k, j * n + p);
+                }
+            }
+
+            let recip = T::one() / a[k * n + k];
+            simd::scale(&mut a[k * n + k + 1..(k + 1) * n], recip);
+
+            for j in k + 1..n {
+                let (col_k, col_j) = split_cols(&mut a, n, k, j);
+                let factor = col_j[k];
+                if factor != T::zero() {
+                    simd::axpy(-factor, &col_k[k + 1..], &mut col_j[k + 1..]);
+                }
+            }
+        }
+
+        Ok(Lu {
+            n,
+            factors: a,
+            pivots,
+            norm1,
+            _order: PhantomData,
+        })
+    }
+}
+
+impl<T: RealField, O: Order> Lu<T, O> {
+    /// Solves `A * X = B`.
+    pub fn solve<O2: Order>(&self, b: &Matrix<T, O2>) -> Result<Matrix<T, O2>, MatrixError> {
+        let mut x = b.clone();
+        self.solve_in_place(&mut x)?;
+        Ok(x)
+    }
+
+    /// Solves `A * X = B`, overwriting `B` with `X`.
+    pub fn solve_in_place<O2: Order>(&self, b: &mut Matrix<T, O2>) -> Result<(), MatrixError> {
+        solve_columns(self.n, b, |col| self.solve_vec(col))
+    }
+
+    pub fn det(&self) -> T {
+        let n = self.n;
+        let swaps = self
+            .pivots
+            .iter()
+            .enumerate()
+            .filter(|&(k, &p)| p != k)
+            .count();
+        let det = (0..n).fold(T::one(), |det, k| det * self.factors[k * n + k]);
+
+        if swaps % 2 == 1 {
+            -det
+        } else {
+            det
+        }
+    }
+
+    pub fn inverse(&self) -> Matrix<T, O> {
+        let n = self.n;
+        let mut data = vec![T::zero(); n * n];
+        for (j, col) in data.chunks_exact_mut(n).enumerate() {
+            col[j] = T::one();
+            self.solve_vec(col);
+        }
+
+        from_storage::<T, O, ColMajor>((n, n), data)
+    }
+
+    /// Estimates the reciprocal of the 1-norm condition number with Hager's method, as
+    /// refined by Higham (LAPACK's `xLACON`). The estimate costs a handful of triangular
+    /// solves and is usually within a factor of 3 of the true value.
+    pub fn rcond(&self) -> T {
+        let n = self.n;
+        let nf = T::from(n).unwrap();
+        let norm1 = |v: &[T]| v.iter().fold(T::zero(), |sum, x| sum + x.abs());
+
+        let mut x = vec![T::one() / nf; n];
+        let mut estimate = T::zero();
+        let mut last = None;
+        for _ in 0..5 {
+            let mut y = x.clone();
+            self.solve_vec(&mut y);
+            estimate = norm1(&y);
+
+            let mut z: Vec<T> = y
+                .iter()
+                .map(|&v| if v >= T::zero() { T::one() } else { -T::one() })
+                .collect();
+            self.solve_transpose_vec(&mut z);
+
+            let j = (0..n).fold(0, |j, i| if z[i].abs() > z[j].abs() { i } else { j });
+            if z[j].abs() <= simd::dot(&z, &x) || last == Some(j) {
+                break;
+            }
+
+            x.iter_mut().for_each(|x| *x = T::zero());
+            x[j] = T::one();
+            last = Some(j);
+        }
+
+        // Higham's extra test vector catches matrices that fool the iteration above.
+        let mut alt: Vec<T> = (0..n)
+            .map(|i| {
+                let magnitude = if n > 1 {
+                    T::one() + T::from(i).unwrap() / T::from(n - 1).unwrap()
+                } else {
+                    T::one()
+                };
+                if i % 2 == 0 {
+                    magnitude
+                } else {
+                    -magnitude
+                }
+            })
+            .collect();
+        self.solve_vec(&mut alt);
+        let alt_estimate = T::from(2.0).unwrap() * norm1(&alt) / (T::from(3.0).unwrap() * nf);
+
+        T::one() / (self.norm1 * estimate.max(alt_estimate))
+    }
+
+    /// Returns the unit lower triangular factor.
+    pub fn l(&self) -> Matrix<T, O> {
+        self.triangle(|i, j| i > j, T::one())
+    }
+
+    /// Returns the upper triangular factor.
+    pub fn u(&self) -> Matrix<T, O> {
+        self.triangle(|i, j| i <= j, T::zero())
+    }
+
+    /// Returns the row permutation: row `i` of `P * A` is row `permutation()[i]` of `A`.
+    pub fn permutation(&self) -> Vec<usize> {
+        let mut perm: Vec<usize> = (0..self.n).collect();
+        for (k, &p) in self.pivots.iter().enumerate() {
+            perm.swap(k, p);
+        }
+        perm
+    }
+
+    fn triangle(&self, keep: impl Fn(usize, usize) -> bool, diagonal: T) -> Matrix<T, O> {
+        let n = self.n;
+        let data = (0..n * n)
+            .map(|idx| {
+                let (i, j) = (idx % n, idx / n);
+                if keep(i, j) {
+                    self.factors[idx]
+                } else if i == j {
+                    diagonal
+                } else {
+                    T::zero()
+                }
+            })
+            .collect();
+
+        from_storage::<T, O, ColMajor>((n, n), data)
+    }
+
+    /// Solves `A * x = b` for a single right-hand side.
+    fn solve_vec(&self, b: &mut [T]) {
+        let (n, lu) = (self.n, &self.factors);
+
+        for (k, &p) in self.pivots.iter().enumerate() {
+            b.swap(k, p);
+        }
+
+        for k in 0..n {
+            let bk = b[k];
+            if bk != T::zero() {
+                simd::axpy(-bk, &lu[k * n + k + 1..(k + 1) * n], &mut b[k + 1..]);
+            }
+        }
+
+        for k in (0..n).rev() {
+            b[k] = b[k] / lu[k * n + k];
+            let bk = b[k];
+            if bk != T::zero() {
+                simd::axpy(-bk, &lu[k * n..k * n + k], &mut b[..k]);
+            }
+        }
+    }
+
+    /// Solves `A^T * x = b` for a single right-hand side.
+    fn solve_transpose_vec(&self, b: &mut [T]) {
+        let (n, lu) = (self.n, &self.factors);
+
+        for k in 0..n {
+            b[k] = (b[k] - simd::dot(&lu[k * n..k * n + k], &b[..k])) / lu[k * n + k];
+        }
+
+        for k in (0..n).rev() {
+            b[k] = b[k] - simd::dot(&lu[k * n + k + 1..(k + 1) * n], &b[k + 1..]);
+        }
+
+        for (k, &p) in self.pivots.iter().enumerate().rev() {
+            b.swap(k, p);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linalg::test_util::{assert_close, from_rows};
+    use crate::RowMajor;
+
+    #[test]
+    fn factors_reconstruct_input() {
+        let a = from_rows::<RowMajor>(&[&[2.0, 1.0, 1.0], &[4.0, -6.0, 0.0], &[-2.0, 7.0, 2.0]]);
+        let lu = a.lu().unwrap();
+
+        let perm = lu.permutation();
+        let mut pa = a.clone();
+        for (i, &p) in perm.iter().enumerate() {
+            for j in 0..3 {
+                pa[(i, j)] = a[(p, j)];
+            }
+        }
+
+        assert_close(&(&lu.l() * &lu.u()), &pa, 1e-12);
+        assert!((lu.det() - -16.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn solve_and_inverse() {
+        let a = from_rows::<ColMajor>(&[&[4.0, -2.0, 1.0], &[-2.0, 4.0, -2.0], &[1.0, -2.0, 4.0]]);
+        let b = from_rows::<RowMajor>(&[&[11.0, 1.0], &[-16.0, 0.0], &[17.0, 0.0]]);
+        let lu = a.lu().unwrap();
+
+        let x = lu.solve(&b).unwrap();
+        assert_close(&(&a * &x), &b, 1e-12);
+
+        let mut x = b.to_order::<ColMajor>();
+        lu.solve_in_place(&mut x).unwrap();
+        assert_close(&(&a * &x), &b, 1e-12);
+
+        let mut identity: Matrix<f64, ColMajor> = Matrix::new(3, 3).unwrap();
+        identity.set_identity().unwrap();
+        assert_close(&(&a * &lu.inverse()), &identity, 1e-12);
+
+        let wrong = from_rows::<RowMajor>(&[&[1.0], &[2.0]]);
+        assert!(matches!(
+            lu.solve(&wrong),
+            Err(MatrixError::DimensionMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn singular_and_non_square() {
+        let a = from_rows::<RowMajor>(&[&[1.0, 2.0], &[2.0, 4.0]]);
+        assert!(matches!(
+            a.lu().err(),
+            Some(MatrixError::Singular { pivot: 1 })
+        ));
+
+        let a = from_rows::<RowMajor>(&[&[1.0, 2.0, 3.0]]);
+        assert!(matches!(
+            a.lu().err(),
+            Some(MatrixError::NotSquare { rows: 1, cols: 3 })
+        ));
+    }
+
+    #[test]
+    fn condition_estimate() {
+        let mut identity: Matrix<f64, RowMajor> = Matrix::new(4, 4).unwrap();
+        identity.set_identity().unwrap();
+        assert!((identity.lu().unwrap().rcond() - 1.0).abs() < 1e-12);
+
+        // The 1-norm condition number of this matrix is exactly 4e8.
+        let a = from_rows::<RowMajor>(&[&[1.0, 1.0], &[1.0, 1.0 + 1e-8]]);
+        let rcond = a.lu().unwrap().rcond();
+        assert!(rcond > 0.3 / 4e8 && rcond < 3.0 / 4e8, "rcond = {}", rcond);
+    }
+}
@@ -0,0 +1,210 @@
+//! Matrix factorizations.
+//!
+//! The factorizations copy their input into column-major scratch storage, so every column
+//! operation runs on a contiguous slice and can use the vectorized kernels in `crate::simd`.
+//! Results are handed back in the storage order of the input.
+
+mod cholesky;
+mod complex;
+mod eigen;
+mod ldlt;
+mod lu;
+mod qr;
+mod svd;
+mod symmetric_eigen;
+
+pub use cholesky::Cholesky;
+pub use complex::Complex;
+pub use eigen::{Eigen, Schur};
+pub use ldlt::Ldlt;
+pub use lu::Lu;
+pub use qr::Qr;
+pub use svd::Svd;
+pub use symmetric_eigen::SymmetricEigen;
+
+use crate::{simd, Matrix, MatrixError, Order};
+use num_traits::Float;
+
+/// Splits column-major storage with columns of length `n` into column `k`, read-only, and
+/// column `j`, mutable. Requires `k < j`.
+fn split_cols<T>(data: &mut [T], n: usize, k: usize, j: usize) -> (&[T], &mut [T]) {
+    let (left, right) = data.split_at_mut(j * n);
+    (&left[k * n..(k + 1) * n], &mut right[..n])
+}
+
+/// Checks that `b` has `n` rows and calls `solve` on each of its columns, in place when the
+/// columns are contiguous.
+fn solve_columns<T: Float, O: Order>(
+    n: usize,
+    b: &mut Matrix<T, O>,
+    solve: impl Fn(&mut [T]),
+) -> Result<(), MatrixError> {
+    if b.num_rows != n {
+        return Err(MatrixError::DimensionMismatch {
+            expected: (n, b.num_cols),
+            found: (b.num_rows, b.num_cols),
+        });
+    }
+
+    let (rs, _) = O::strides((b.num_rows, b.num_cols));
+    if rs == 1 {
+        b.data.chunks_exact_mut(n).for_each(solve);
+    } else {
+        let mut col = vec![T::zero(); n];
+        for j in 0..b.num_cols {
+            col.iter_mut().enumerate().for_each(|(i, x)| *x = b[(i, j)]);
+            solve(&mut col);
+            col.iter().enumerate().for_each(|(i, &x)| b[(i, j)] = x);
+        }
+    }
+
+    Ok(())
+}
+
+/// Euclidean norm of `x`, scaled so that squaring the entries cannot overflow or underflow.
+fn norm2<T: Float + 'static>(x: &[T]) -> T {
+    let scale = x.iter().fold(T::zero(), |max, v| max.max(v.abs()));
+    if scale == T::zero() || !scale.is_finite() {
+        return scale;
+    }
+
+    let sum = x.iter().fold(T::zero(), |sum, &v| {
+        let v = v / scale;
+        sum + v * v
+    });
+    scale * sum.sqrt()
+}
+
+/// Computes a Householder reflector `H = I - tau * v * v^T` with `H * x = beta * e_1`.
+///
+/// On return `x[0]` holds `beta` and `x[1..]` holds `v[1..]`; `v[0]` is implicitly one.
+/// Returns `tau`, which is zero when `x` is already a multiple of `e_1`.
+fn householder<T: Float + 'static>(x: &mut [T]) -> T {
+    let (alpha, tail) = match x.split_first_mut() {
+        Some((alpha, tail)) => (alpha, tail),
+        None => return T::zero(),
+    };
+
+    let tail_norm = norm2(tail);
+    if tail_norm == T::zero() {
+        return T::zero();
+    }
+
+    let norm = alpha.hypot(tail_norm);
+    let beta = if *alpha >= T::zero() { -norm } else { norm };
+    let tau = (beta - *alpha) / beta;
+    simd::scale(tail, T::one() / (*alpha - beta));
+    *alpha = beta;
+    tau
+}
+
+/// Applies the reflector with tail `v` (see [`householder`]) to `y`, whose first entry lines
+/// up with the implicit one.
+fn apply_householder<T: Float + 'static>(v: &[T], tau: T, y: &mut [T]) {
+    if tau == T::zero() {
+        return;
+    }
+
+    let (head, tail) = y.split_first_mut().unwrap();
+    let w = tau * (*head + simd::dot(v, tail));
+    *head = *head - w;
+    simd::axpy(-w, v, tail);
+}
+
+/// Forms the column-major `n x n` product `H_0 * H_1 * ...` of the reflectors left by a
+/// tridiagonal or Hessenberg reduction of `a`. Reflector `k` acts on rows `k + 1..n` and its
+/// tail is stored below the subdiagonal in column `k`.
+fn subdiagonal_reflector_product<T: Float + 'static>(a: &[T], tau: &[T], n: usize) -> Vec<T> {
+    let mut q = vec![T::zero(); n * n];
+    for j in 0..n {
+        q[j * n + j] = T::one();
+    }
+
+    for (k, &tau) in tau.iter().enumerate().rev() {
+        let v = &a[k * n + k + 2..(k + 1) * n];
+        for col in q.chunks_exact_mut(n).skip(k + 1) {
+            apply_householder(v, tau, &mut col[k + 1..]);
+        }
+    }
+    q
+}
+
+/// Returns `(c, s, r)` such that `c * f + s * g = r` and `c * g - s * f = 0`.
+fn givens<T: Float>(f: T, g: T) -> (T, T, T) {
+    let r = f.hypot(g);
+    if r == T::zero() {
+        (T::one(), T::zero(), T::zero())
+    } else {
+        (f / r, g / r, r)
+    }
+}
+
+/// Rotates columns `i` and `j` of column-major `data`, whose columns have length `len`:
+/// column `i` becomes `c * x_i + s * x_j` and column `j` becomes `c * x_j - s * x_i`. Does
+/// nothing if `data` is empty, so callers can skip accumulating a factor they do not need.
+fn rotate_cols<T: Float>(data: &mut [T], len: usize, i: usize, j: usize, c: T, s: T) {
+    if data.is_empty() {
+        return;
+    }
+
+    let (lo, hi) = (i.min(j), i.max(j));
+    let (left, right) = data.split_at_mut(hi * len);
+    let (first, second) = (&mut left[lo * len..(lo + 1) * len], &mut right[..len]);
+    let (x, y) = if i < j {
+        (first, second)
+    } else {
+        (second, first)
+    };
+
+    for (x, y) in x.iter_mut().zip(y) {
+        let (xv, yv) = (*x, *y);
+        *x = c * xv + s * yv;
+        *y = c * yv - s * xv;
+    }
+}
+
+/// Fixtures shared by the factorization tests.
+#[cfg(test)]
+pub(super) mod test_util {
+    use crate::{Matrix, Order};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    pub(super) fn from_rows<O: Order>(rows: &[&[f64]]) -> Matrix<f64, O> {
+        let mut m: Matrix<f64, O> = Matrix::new(rows.len(), rows[0].len()).unwrap();
+        for (i, row) in rows.iter().enumerate() {
+            for (j, &x) in row.iter().enumerate() {
+                m[(i, j)] = x;
+            }
+        }
+        m
+    }
+
+    /// A matrix of uniform entries in `[-1, 1)`, the same for a given seed.
+    pub(super) fn random<O: Order>(rows: usize, cols: usize, seed: u64) -> Matrix<f64, O> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut m: Matrix<f64, O> = Matrix::new(rows, cols).unwrap();
+        m.data
+            .iter_mut()
+            .for_each(|x| *x = rng.gen_range(-1.0..1.0));
+        m
+    }
+
+    pub(super) fn assert_close<O: Order, O2: Order>(
+        a: &Matrix<f64, O>,
+        b: &Matrix<f64, O2>,
+        tol: f64,
+    ) {
+        assert_eq!((a.num_rows, a.num_cols), (b.num_rows, b.num_cols));
+        for i in 0..a.num_rows {
+            for j in 0..a.num_cols {
+                assert!(
+                    (a[(i, j)] - b[(i, j)]).abs() <= tol,
+                    "mismatch at ({}, {})",
+                    i,
+                    j
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,360 @@
+use super::{apply_householder, householder, norm2, split_cols, MatrixError};
+use crate::transpose::{from_storage, storage_in};
+use crate::{simd, ColMajor, Matrix, Order, RealField};
+use std::marker::PhantomData;
+
+/// Householder QR factorization, `A * P = Q * R`.
+///
+/// The column-major `m x n` buffer holds `R` on and above the diagonal and the Householder
+/// vectors below it. `P` is a column permutation, the identity unless the factorization was
+/// computed with [`Matrix::qr_pivoted`].
+pub struct Qr<T, O> {
+    rows: usize,
+    cols: usize,
+    factors: Vec<T>,
+    tau: Vec<T>,
+    perm: Vec<usize>,
+    pivoted: bool,
+    _order: PhantomData<O>,
+}
+
+impl<T: RealField, O: Order> Matrix<T, O> {
+    /// Factorizes a matrix of any shape.
+    pub fn qr(&self) -> Qr<T, O> {
+        Qr::factorize(self, false)
+    }
+
+    /// Factorizes a matrix with column pivoting: at every step the remaining column with the
+    /// largest norm is moved to the front, so the diagonal of `R` is non-increasing in
+    /// magnitude and [`Qr::rank`] is reliable.
+    pub fn qr_pivoted(&self) -> Qr<T, O> {
+        Qr::factorize(self, true)
+    }
+
+    /// Minimizes `||A * X - B||` column by column using column-pivoted QR. If `A` is rank
+    /// deficient, the basic solution with `n - rank` zero entries is returned.
+    pub fn least_squares<O2: Order>(
+        &self,
+        b: &Matrix<T, O2>,
+    ) -> Result<Matrix<T, O2>, MatrixError> {
+        self.qr_pivoted().least_squares(b)
+    }
+}
+
+impl<T: RealField, O: Order> Qr<T, O> {
+    fn factorize(a: &Matrix<T, O>, pivoting: bool) -> Self {
+        let (m, n) = (a.num_rows, a.num_cols);
+        let mut f = storage_in::<T, ColMajor, O>(a).into_owned();
+        let mut tau = Vec::with_capacity(m.min(n));
+        let mut perm: Vec<usize> = (0..n).collect();
+
+        // Column norms are downdated as rows are eliminated and recomputed once cancellation
+        // makes the downdated value unreliable, as in LAPACK's `xGEQP3`.
+        let mut norms: Vec<T> = if pivoting {
+            f.chunks_exact(m).map(norm2).collect()
+        } else {
+            Vec::new()
+        };
+        let mut exact = norms.clone();
+        let threshold = T::epsilon().sqrt();
+
+        for j in 0..m.min(n) {
+            if pivoting {
+                let p = (j..n).fold(j, |p, c| if norms[c] > norms[p] { c } else { p });
+                if p != j {
+                    for i in 0..m {
+                        f.swap(j * m + i, p * m + i);
+                    }
+                    perm.swap(j, p);
+                    norms.swap(j, p);
+                    exact.swap(j, p);
+                }
+            }
+
+            let t = householder(&mut f[j * m + j..(j + 1) * m]);
+            tau.push(t);
+
+            for c in j + 1..n {
+                let (col_j, col_c) = split_cols(&mut f, m, j, c);
+                apply_householder(&col_j[j + 1..], t, &mut col_c[j..]);
+
+                if pivoting && norms[c] != T::zero() {
+                    let ratio = col_c[j].abs() / norms[c];
+                    let shrink = (T::one() - ratio * ratio).max(T::zero());
+                    let drift = norms[c] / exact[c];
+                    if shrink * drift * drift <= threshold {
+                        norms[c] = norm2(&col_c[j + 1..]);
+                        exact[c] = norms[c];
+                    } else {
+                        norms[c] = norms[c] * shrink.sqrt();
+                    }
+                }
+            }
+        }
+
+        Qr {
+            rows: m,
+            cols: n,
+            factors: f,
+            tau,
+            perm,
+            pivoted: pivoting,
+            _order: PhantomData,
+        }
+    }
+
+    /// Returns the `m x min(m, n)` factor with orthonormal columns.
+    pub fn q(&self) -> Matrix<T, O> {
+        self.form_q(self.tau.len())
+    }
+
+    /// Returns the full `m x m` orthogonal factor.
+    pub fn q_full(&self) -> Matrix<T, O> {
+        self.form_q(self.rows)
+    }
+
+    /// Returns the `min(m, n) x n` upper triangular factor.
+    pub fn r(&self) -> Matrix<T, O> {
+        self.form_r(self.tau.len())
+    }
+
+    /// Returns the `m x n` upper triangular factor, padded with zero rows.
+    pub fn r_full(&self) -> Matrix<T, O> {
+        self.form_r(self.rows)
+    }
+
+    /// Returns the column permutation: column `j` of `A * P` is column `permutation()[j]` of
+    /// `A`.
+    pub fn permutation(&self) -> Vec<usize> {
+        self.perm.clone()
+    }
+
+    /// Returns the numerical rank, the number of leading diagonal entries of `R` larger than
+    /// `max |R_ii| * eps * max(m, n)`.
+    pub fn rank(&self) -> usize {
+        let m = self.rows;
+        let diag = |i: usize| self.factors[i * m + i].abs();
+
+        let k = self.tau.len();
+        let max = (0..k).map(diag).fold(T::zero(), T::max);
+        let tol = max * T::epsilon() * T::from(m.max(self.cols)).unwrap();
+        (0..k).take_while(|&i| diag(i) > tol).count()
+    }
+
+    /// Minimizes `||A * X - B||` column by column.
+    ///
+    /// With column pivoting, a rank deficient `A` yields the basic solution with `n - rank`
+    /// zero entries. Without it, rank deficiency is reported as `MatrixError::Singular`.
+    pub fn least_squares<O2: Order>(
+        &self,
+        b: &Matrix<T, O2>,
+    ) -> Result<Matrix<T, O2>, MatrixError> {
+        let (m, n) = (self.rows, self.cols);
+        if b.num_rows != m {
+            return Err(MatrixError::DimensionMismatch {
+                expected: (m, b.num_cols),
+                found: (b.num_rows, b.num_cols),
+            });
+        }
+
+        let rank = self.rank();
+        if rank < n && !self.pivoted {
+            return Err(MatrixError::Singular { pivot: rank });
+        }
+
+        let mut c = storage_in::<T, ColMajor, O2>(b).into_owned();
+        let mut x = vec![T::zero(); n * b.num_cols];
+        for (c, x) in c.chunks_exact_mut(m).zip(x.chunks_exact_mut(n)) {
+            self.apply_qt(c);
+
+            let z = &mut c[..rank];
+            for k in (0..rank).rev() {
+                z[k] = z[k] / self.factors[k * m + k];
+                let zk = z[k];
+                if zk != T::zero() {
+                    simd::axpy(-zk, &self.factors[k * m..k * m + k], &mut z[..k]);
+                }
+            }
+
+            for (j, &zj) in z.iter().enumerate() {
+                x[self.perm[j]] = zj;
+            }
+        }
+
+        Ok(from_storage::<T, O2, ColMajor>((n, b.num_cols), x))
+    }
+
+    /// Overwrites `b` with `Q^T * b`.
+    fn apply_qt(&self, b: &mut [T]) {
+        let m = self.rows;
+        for (i, &tau) in self.tau.iter().enumerate() {
+            apply_householder(&self.factors[i * m + i + 1..(i + 1) * m], tau, &mut b[i..]);
+        }
+    }
+
+    fn form_q(&self, cols: usize) -> Matrix<T, O> {
+        let m = self.rows;
+        let mut q = vec![T::zero(); m * cols];
+        for j in 0..cols {
+            q[j * m + j] = T::one();
+        }
+
+        // Columns before `i` are still unit vectors with zeros where reflector `i` acts.
+        for (i, &tau) in self.tau.iter().enumerate().rev() {
+            let v = &self.factors[i * m + i + 1..(i + 1) * m];
+            for col in q.chunks_exact_mut(m).skip(i) {
+                apply_householder(v, tau, &mut col[i..]);
+            }
+        }
+
+        from_storage::<T, O, ColMajor>((m, cols), q)
+    }
+
+    fn form_r(&self, rows: usize) -> Matrix<T, O> {
+        let (m, n) = (self.rows, self.cols);
+        let data = (0..rows * n)
+            .map(|idx| {
+                let (i, j) = (idx % rows, idx / rows);
+                if i <= j {
+                    self.factors[j * m + i]
+                } else {
+                    T::zero()
+                }
+            })
+            .collect();
+
+        from_storage::<T, O, ColMajor>((rows, n), data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linalg::test_util::{assert_close, from_rows};
+    use crate::RowMajor;
+
+    fn identity(n: usize) -> Matrix<f64, RowMajor> {
+        let mut m = Matrix::new(n, n).unwrap();
+        m.set_identity().unwrap();
+        m
+    }
+
+    fn permuted<O: Order>(a: &Matrix<f64, O>, perm: &[usize]) -> Matrix<f64, O> {
+        let mut ap = a.clone();
+        for (j, &p) in perm.iter().enumerate() {
+            for i in 0..a.num_rows {
+                ap[(i, j)] = a[(i, p)];
+            }
+        }
+        ap
+    }
+
+    #[test]
+    fn factors_reconstruct_input() {
+        let tall = from_rows::<RowMajor>(&[
+            &[12.0, -51.0, 4.0],
+            &[6.0, 167.0, -68.0],
+            &[-4.0, 24.0, -41.0],
+            &[-1.0, 1.0, 0.0],
+            &[2.0, 0.0, 3.0],
+        ]);
+        let wide = from_rows::<ColMajor>(&[
+            &[1.0, 2.0, 3.0, 4.0, 5.0],
+            &[0.0, 1.0, -1.0, 2.0, 1.0],
+            &[2.0, 2.0, 0.0, 1.0, 3.0],
+        ]);
+
+        for qr in [tall.qr(), tall.qr_pivoted()] {
+            let (q, r) = (qr.q(), qr.r());
+            assert_eq!((q.num_rows, q.num_cols, r.num_rows), (5, 3, 3));
+            assert_close(&(&q * &r), &permuted(&tall, &qr.permutation()), 1e-10);
+            assert_close(&(&q.transpose().unwrap() * &q), &identity(3), 1e-12);
+
+            let q_full = qr.q_full();
+            assert_close(&(&q_full * &qr.r_full()), &(&q * &r), 1e-10);
+            assert_close(
+                &(&q_full * &q_full.transpose().unwrap()),
+                &identity(5),
+                1e-12,
+            );
+        }
+
+        for qr in [wide.qr(), wide.qr_pivoted()] {
+            let (q, r) = (qr.q(), qr.r());
+            assert_eq!((q.num_rows, q.num_cols, r.num_cols), (3, 3, 5));
+            assert_close(&(&q * &r), &permuted(&wide, &qr.permutation()), 1e-12);
+        }
+    }
+
+    #[test]
+    fn pivoting_reveals_rank() {
+        let a = from_rows::<RowMajor>(&[
+            &[1.0, 2.0, 1.0],
+            &[2.0, 4.0, 0.0],
+            &[3.0, 6.0, 1.0],
+            &[4.0, 8.0, 5.0],
+        ]);
+
+        let qr = a.qr_pivoted();
+        assert_eq!(qr.rank(), 2);
+        let r = qr.r();
+        assert!(r[(0, 0)].abs() >= r[(1, 1)].abs() && r[(1, 1)].abs() >= r[(2, 2)].abs());
+    }
+
+    #[test]
+    fn least_squares_fit() {
+        // Fitting a line through (0, 1), (1, 2), (2, 2); the normal equations give 7/6 + x/2.
+        let a = from_rows::<RowMajor>(&[&[1.0, 0.0], &[1.0, 1.0], &[1.0, 2.0]]);
+        let b = from_rows::<ColMajor>(&[&[1.0], &[2.0], &[2.0]]);
+
+        let expected = from_rows::<ColMajor>(&[&[7.0 / 6.0], &[0.5]]);
+        assert_close(&a.qr().least_squares(&b).unwrap(), &expected, 1e-12);
+        assert_close(&a.least_squares(&b).unwrap(), &expected, 1e-12);
+
+        let wrong = from_rows::<ColMajor>(&[&[1.0], &[2.0]]);
+        assert!(matches!(
+            a.least_squares(&wrong),
+            Err(MatrixError::DimensionMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn rank_deficient_least_squares() {
+        let a = from_rows::<RowMajor>(&[
+            &[1.0, 2.0, 1.0],
+            &[2.0, 4.0, 0.0],
+            &[3.0, 6.0, 1.0],
+            &[4.0, 8.0, 5.0],
+        ]);
+        let b = from_rows::<RowMajor>(&[&[1.0], &[0.0], &[2.0], &[1.0]]);
+
+        assert!(matches!(
+            a.qr().least_squares(&b).err(),
+            Some(MatrixError::Singular { pivot: 1 })
+        ));
+
+        // The basic solution still satisfies the normal equations `A^T (A x - b) = 0`.
+        let x = a.least_squares(&b).unwrap();
+        let residual = &(&a * &x) - &b;
+        let gradient = &a.transpose().unwrap() * &residual;
+        assert_close(
+            &gradient,
+            &from_rows::<RowMajor>(&[&[0.0], &[0.0], &[0.0]]),
+            1e-10,
+        );
+        assert_eq!(x.data.iter().filter(|&&v| v == 0.0).count(), 1);
+    }
+
+    #[test]
+    fn rank_deficient_without_column_swaps() {
+        // The column norms already descend, so pivoting leaves the permutation unchanged.
+        let a = from_rows::<RowMajor>(&[&[2.0, 1.0], &[2.0, 1.0], &[2.0, 1.0]]);
+        let b = from_rows::<RowMajor>(&[&[1.0], &[2.0], &[3.0]]);
+        let qr = a.qr_pivoted();
+        assert_eq!(qr.permutation(), vec![0, 1]);
+        assert_eq!(qr.rank(), 1);
+
+        let x = a.least_squares(&b).unwrap();
+        assert_close(&x, &from_rows::<RowMajor>(&[&[1.0], &[0.0]]), 1e-12);
+    }
+}
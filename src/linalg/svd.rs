@@ -0,0 +1,538 @@
+use super::{apply_householder, givens, householder, rotate_cols, split_cols, MatrixError};
+use crate::transpose::{from_storage, storage_in};
+use crate::{simd, ColMajor, Matrix, Order, RealField, RowMajor};
+use num_traits::Float;
+use std::marker::PhantomData;
+
+/// Singular value decomposition, `A = U * Σ * V^T`, with the singular values in descending
+/// order.
+///
+/// For an `m x n` matrix with `k = min(m, n)`, the thin decomposition has a `m x k` factor `U`
+/// and a `k x n` factor `V^T`; the full one has square `U` and `V^T`.
+pub struct Svd<T, O> {
+    u: Matrix<T, O>,
+    singular_values: Vec<T>,
+    vt: Matrix<T, O>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Vectors {
+    None,
+    Thin,
+    Full,
+}
+
+impl<T: RealField, O: Order> Matrix<T, O> {
+    /// Computes the thin singular value decomposition by Golub–Kahan bidiagonalization
+    /// followed by implicit-shift QR iterations on the bidiagonal matrix.
+    pub fn svd(&self) -> Result<Svd<T, O>, MatrixError> {
+        Svd::compute(self, Vectors::Thin)
+    }
+
+    /// Computes the singular value decomposition with square `U` and `V^T`.
+    pub fn svd_full(&self) -> Result<Svd<T, O>, MatrixError> {
+        Svd::compute(self, Vectors::Full)
+    }
+
+    /// Computes only the singular values, in descending order, which skips accumulating the
+    /// singular vectors.
+    pub fn singular_values(&self) -> Result<Vec<T>, MatrixError> {
+        Svd::compute(self, Vectors::None).map(|svd| svd.singular_values)
+    }
+
+    /// Returns the Moore–Penrose pseudoinverse; see [`Svd::pinv`].
+    pub fn pinv(&self) -> Result<Matrix<T, O>, MatrixError> {
+        Ok(self.svd()?.pinv())
+    }
+
+    /// Returns the number of singular values larger than `tol`.
+    pub fn rank(&self, tol: T) -> Result<usize, MatrixError> {
+        Ok(self.singular_values()?.iter().filter(|&&s| s > tol).count())
+    }
+
+    /// Returns an orthonormal basis of the null space as the columns of an `n x (n - rank)`
+    /// matrix, or `None` if `A` has full column rank. The rank is determined with the
+    /// tolerance described in [`Svd::pinv`].
+    pub fn null_space(&self) -> Result<Option<Matrix<T, O>>, MatrixError> {
+        let svd = self.svd_full()?;
+        let n = self.num_cols;
+        let rank = svd.rank(svd.default_tol());
+        if rank == n {
+            return Ok(None);
+        }
+
+        // The trailing rows of `V^T` are the columns of the basis.
+        let data = (rank..n)
+            .flat_map(|i| (0..n).map(move |j| (i, j)))
+            .map(|(i, j)| svd.vt[(i, j)])
+            .collect();
+
+        Ok(Some(from_storage::<T, O, ColMajor>((n, n - rank), data)))
+    }
+
+    /// Returns the 2-norm condition number, the ratio of the largest to the smallest singular
+    /// value. It is infinite for a rank deficient matrix.
+    pub fn cond2(&self) -> Result<T, MatrixError> {
+        Ok(Svd::<T, O>::cond(&self.singular_values()?))
+    }
+}
+
+impl<T: RealField, O: Order> Svd<T, O> {
+    fn compute(a: &Matrix<T, O>, vectors: Vectors) -> Result<Self, MatrixError> {
+        let (m, n) = (a.num_rows, a.num_cols);
+
+        // The algorithm needs `m >= n`. A wide matrix is handled through its transpose, whose
+        // column-major storage is the row-major storage of `A`, with the roles of `U` and `V`
+        // exchanged.
+        let (s, u, v) = if m >= n {
+            let f = decompose(storage_in::<T, ColMajor, O>(a).into_owned(), m, n, vectors)?;
+            (f.s, f.u, f.v)
+        } else {
+            let f = decompose(storage_in::<T, RowMajor, O>(a).into_owned(), n, m, vectors)?;
+            (f.s, f.v, f.u)
+        };
+
+        let (u_cols, v_cols) = match vectors {
+            Vectors::None => (0, 0),
+            Vectors::Thin => (m.min(n), m.min(n)),
+            Vectors::Full => (m, n),
+        };
+
+        // `V` in column-major order is `V^T` in row-major order.
+        Ok(Svd {
+            u: from_storage::<T, O, ColMajor>((m, u_cols), u),
+            singular_values: s,
+            vt: from_storage::<T, O, RowMajor>((v_cols, n), v),
+        })
+    }
+
+    pub fn u(&self) -> &Matrix<T, O> {
+        &self.u
+    }
+
+    pub fn singular_values(&self) -> &[T] {
+        &self.singular_values
+    }
+
+    /// Returns `Σ`, shaped to fit between `U` and `V^T`.
+    pub fn sigma(&self) -> Matrix<T, O> {
+        let (rows, cols) = (self.u.num_cols, self.vt.num_rows);
+        let mut data = vec![T::zero(); rows * cols];
+        for (i, &s) in self.singular_values.iter().enumerate() {
+            data[i * rows + i] = s;
+        }
+
+        from_storage::<T, O, ColMajor>((rows, cols), data)
+    }
+
+    pub fn vt(&self) -> &Matrix<T, O> {
+        &self.vt
+    }
+
+    /// Returns the number of singular values larger than `tol`.
+    pub fn rank(&self, tol: T) -> usize {
+        self.singular_values.iter().filter(|&&s| s > tol).count()
+    }
+
+    /// Returns the ratio of the largest to the smallest singular value.
+    pub fn cond2(&self) -> T {
+        Self::cond(&self.singular_values)
+    }
+
+    /// Returns the Moore–Penrose pseudoinverse `V * Σ^+ * U^T`. Singular values no larger
+    /// than `max(m, n) * eps * σ_max` are treated as zero.
+    pub fn pinv(&self) -> Matrix<T, O> {
+        let (m, n) = (self.u.num_rows, self.vt.num_cols);
+        let tol = self.default_tol();
+
+        let mut v = self.vt.clone().into_transposed_layout();
+        for (j, &s) in self.singular_values.iter().enumerate() {
+            let scale = if s > tol { T::one() / s } else { T::zero() };
+            for i in 0..n {
+                v[(i, j)] = v[(i, j)] * scale;
+            }
+        }
+        let ut = self.u.clone().into_transposed_layout();
+
+        let mut pinv = Matrix {
+            num_rows: n,
+            num_cols: m,
+            data: vec![T::zero(); n * m],
+            _order: PhantomData,
+        };
+        Matrix::gemm(T::one(), &v, &ut, T::zero(), &mut pinv)
+            .expect("factor dimensions always agree");
+        pinv
+    }
+
+    fn default_tol(&self) -> T {
+        let (m, n) = (self.u.num_rows, self.vt.num_cols);
+        let max = self
+            .singular_values
+            .first()
+            .copied()
+            .unwrap_or_else(T::zero);
+        max * T::epsilon() * T::from(m.max(n)).unwrap()
+    }
+
+    fn cond(s: &[T]) -> T {
+        match (s.first(), s.last()) {
+            (Some(&max), Some(&min)) if min > T::zero() => max / min,
+            _ => T::infinity(),
+        }
+    }
+}
+
+/// Singular values and column-major singular vectors of an `m x n` matrix with `m >= n`.
+struct Factors<T> {
+    s: Vec<T>,
+    u: Vec<T>,
+    v: Vec<T>,
+}
+
+fn decompose<T: Float + 'static>(
+    mut a: Vec<T>,
+    m: usize,
+    n: usize,
+    vectors: Vectors,
+) -> Result<Factors<T>, MatrixError> {
+    let (mut d, mut e, tau_u, tau_v) = bidiagonalize(&mut a, m, n);
+
+    let (mut u, mut v) = (Vec::new(), Vec::new());
+    if vectors != Vectors::None {
+        let u_cols = if vectors == Vectors::Full { m } else { n };
+        u = vec![T::zero(); m * u_cols];
+        for j in 0..u_cols {
+            u[j * m + j] = T::one();
+        }
+        for (k, &tau) in tau_u.iter().enumerate().rev() {
+            let reflector = &a[k * m + k + 1..(k + 1) * m];
+            for col in u.chunks_exact_mut(m).skip(k) {
+                apply_householder(reflector, tau, &mut col[k..]);
+            }
+        }
+
+        v = vec![T::zero(); n * n];
+        for j in 0..n {
+            v[j * n + j] = T::one();
+        }
+        for (k, &tau) in tau_v.iter().enumerate().rev() {
+            let reflector: Vec<T> = (k + 2..n).map(|j| a[j * m + k]).collect();
+            for col in v.chunks_exact_mut(n).skip(k + 1) {
+                apply_householder(&reflector, tau, &mut col[k + 1..]);
+            }
+        }
+    }
+
+    bidiagonal_qr(&mut d, &mut e, &mut u, m, &mut v)?;
+
+    for (i, s) in d.iter_mut().enumerate() {
+        if *s < T::zero() {
+            *s = -*s;
+            if !v.is_empty() {
+                simd::scale(&mut v[i * n..(i + 1) * n], -T::one());
+            }
+        }
+    }
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&i, &j| d[j].partial_cmp(&d[i]).unwrap_or(std::cmp::Ordering::Equal));
+    let s = order.iter().map(|&i| d[i]).collect();
+    if !u.is_empty() {
+        let sorted_cols = |data: &[T], len: usize| -> Vec<T> {
+            order
+                .iter()
+                .flat_map(|&i| &data[i * len..(i + 1) * len])
+                .copied()
+                .collect()
+        };
+        let mut sorted_u = sorted_cols(&u, m);
+        sorted_u.extend_from_slice(&u[n * m..]);
+        u = sorted_u;
+        v = sorted_cols(&v, n);
+    }
+
+    Ok(Factors { s, u, v })
+}
+
+/// Reduces the column-major `m x n` matrix `a`, `m >= n`, to upper bidiagonal form with
+/// Householder reflectors applied alternately from the left and the right.
+///
+/// Returns the diagonal, the superdiagonal and the scalar factors of both sets of reflectors.
+/// Left reflector `k` is stored below the diagonal in column `k`; right reflector `k` is
+/// stored to the right of the superdiagonal in row `k`.
+#[allow(clippy::type_complexity)]
+fn bidiagonalize<T: Float + 'static>(
+    a: &mut [T],
+    m: usize,
+    n: usize,
+) -> (Vec<T>, Vec<T>, Vec<T>, Vec<T>) {
+    let mut d = Vec::with_capacity(n);
+    let mut e = Vec::with_capacity(n.saturating_sub(1));
+    let mut tau_u = Vec::with_capacity(n);
+    let mut tau_v = Vec::with_capacity(n.saturating_sub(1));
+    let mut w = vec![T::zero(); m];
+
+    for k in 0..n {
+        let tau = householder(&mut a[k * m + k..(k + 1) * m]);
+        d.push(a[k * m + k]);
+        tau_u.push(tau);
+        for j in k + 1..n {
+            let (col_k, col_j) = split_cols(a, m, k, j);
+            apply_householder(&col_k[k + 1..], tau, &mut col_j[k..]);
+        }
+
+        if k + 1 == n {
+            break;
+        }
+
+        let mut row: Vec<T> = (k + 1..n).map(|j| a[j * m + k]).collect();
+        let tau = householder(&mut row);
+        e.push(row[0]);
+        tau_v.push(tau);
+        for (j, &x) in (k + 1..n).zip(&row) {
+            a[j * m + k] = x;
+        }
+
+        if tau != T::zero() {
+            // Rows below `k` become `A - tau * (A * v) * v^T`, one column at a time.
+            let vj = |idx: usize| if idx == 0 { T::one() } else { row[idx] };
+            let w = &mut w[k + 1..];
+            w.iter_mut().for_each(|x| *x = T::zero());
+            for (idx, j) in (k + 1..n).enumerate() {
+                simd::axpy(vj(idx), &a[j * m + k + 1..(j + 1) * m], w);
+            }
+            for (idx, j) in (k + 1..n).enumerate() {
+                simd::axpy(-tau * vj(idx), w, &mut a[j * m + k + 1..(j + 1) * m]);
+            }
+        }
+    }
+
+    (d, e, tau_u, tau_v)
+}
+
+/// Diagonalizes the upper bidiagonal matrix with diagonal `d` and superdiagonal `e` by
+/// implicit-shift QR sweeps (Golub and Van Loan, algorithm 8.6.2). The left rotations are
+/// accumulated into the columns of `u`, whose columns have length `m`, and the right ones
+/// into `v`; either may be empty.
+fn bidiagonal_qr<T: Float + 'static>(
+    d: &mut [T],
+    e: &mut [T],
+    u: &mut [T],
+    m: usize,
+    v: &mut [T],
+) -> Result<(), MatrixError> {
+    let n = d.len();
+    let eps = T::epsilon();
+    let norm = (0..n).fold(T::zero(), |max, i| {
+        max.max(d[i].abs() + e.get(i).map_or(T::zero(), |e| e.abs()))
+    });
+    let small = eps * norm;
+
+    let max_sweeps = 75 * n;
+    let mut sweeps = 0;
+    loop {
+        for i in 0..n.saturating_sub(1) {
+            if e[i].abs() <= eps * (d[i].abs() + d[i + 1].abs()) {
+                e[i] = T::zero();
+            }
+        }
+
+        // Find the trailing unreduced block `lo..=hi`.
+        let mut hi = n - 1;
+        while hi > 0 && e[hi - 1] == T::zero() {
+            hi -= 1;
+        }
+        if hi == 0 {
+            break;
+        }
+        let mut lo = hi - 1;
+        while lo > 0 && e[lo - 1] != T::zero() {
+            lo -= 1;
+        }
+
+        sweeps += 1;
+        if sweeps > max_sweeps {
+            return Err(MatrixError::NoConvergence);
+        }
+
+        // A zero on the diagonal splits the block once its row is rotated away from the left.
+        if let Some(i) = (lo..hi).find(|&i| d[i].abs() <= small) {
+            d[i] = T::zero();
+            let mut f = e[i];
+            e[i] = T::zero();
+            for j in i + 1..=hi {
+                let (c, s, r) = givens(d[j], f);
+                d[j] = r;
+                rotate_cols(u, m, j, i, c, s);
+                if j < hi {
+                    f = -s * e[j];
+                    e[j] = c * e[j];
+                }
+            }
+            continue;
+        }
+
+        // A zero at the end of the block is handled the same way with rotations from the right.
+        if d[hi].abs() <= small {
+            d[hi] = T::zero();
+            let mut f = e[hi - 1];
+            e[hi - 1] = T::zero();
+            for j in (lo..hi).rev() {
+                let (c, s, r) = givens(d[j], f);
+                d[j] = r;
+                rotate_cols(v, n, j, hi, c, s);
+                if j > lo {
+                    f = -s * e[j - 1];
+                    e[j - 1] = c * e[j - 1];
+                }
+            }
+            continue;
+        }
+
+        // Wilkinson shift from the trailing 2x2 block of `B^T * B`.
+        let before = if hi - 1 > lo { e[hi - 2] } else { T::zero() };
+        let a = d[hi - 1] * d[hi - 1] + before * before;
+        let b = d[hi - 1] * e[hi - 1];
+        let c = d[hi] * d[hi] + e[hi - 1] * e[hi - 1];
+        let delta = (a - c) / (T::one() + T::one());
+        let root = delta.hypot(b);
+        let shift = if delta >= T::zero() {
+            c - b * b / (delta + root)
+        } else {
+            c - b * b / (delta - root)
+        };
+
+        // Chase the bulge created by the shifted first rotation down the block.
+        let mut y = d[lo] * d[lo] - shift;
+        let mut z = d[lo] * e[lo];
+        for k in lo..hi {
+            let (c, s, r) = givens(y, z);
+            if k > lo {
+                e[k - 1] = r;
+            }
+            let (dk, ek, dk1) = (d[k], e[k], d[k + 1]);
+            d[k] = c * dk + s * ek;
+            e[k] = c * ek - s * dk;
+            let bulge = s * dk1;
+            d[k + 1] = c * dk1;
+            rotate_cols(v, n, k, k + 1, c, s);
+
+            let (c, s, r) = givens(d[k], bulge);
+            d[k] = r;
+            let (ek, dk1) = (e[k], d[k + 1]);
+            e[k] = c * ek + s * dk1;
+            d[k + 1] = c * dk1 - s * ek;
+            rotate_cols(u, m, k, k + 1, c, s);
+
+            if k + 1 < hi {
+                y = e[k];
+                z = s * e[k + 1];
+                e[k + 1] = c * e[k + 1];
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linalg::test_util::{assert_close, from_rows, random};
+
+    fn assert_orthonormal_cols<O: Order>(q: &Matrix<f64, O>) {
+        let mut identity: Matrix<f64, O> = Matrix::new(q.num_cols, q.num_cols).unwrap();
+        identity.set_identity().unwrap();
+        assert_close(&(&q.transpose().unwrap() * q), &identity, 1e-12);
+    }
+
+    #[test]
+    fn known_singular_values() {
+        let a = from_rows::<RowMajor>(&[&[3.0, 2.0, 2.0], &[2.0, 3.0, -2.0]]);
+        let s = a.singular_values().unwrap();
+        assert_eq!(s.len(), 2);
+        assert!((s[0] - 5.0).abs() < 1e-12 && (s[1] - 3.0).abs() < 1e-12);
+
+        assert!((a.cond2().unwrap() - 5.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn thin_and_full_reconstruct_input() {
+        let shapes = [(7, 4), (4, 7), (6, 6), (1, 5), (5, 1)];
+        for (seed, &(m, n)) in shapes.iter().enumerate() {
+            let a = random::<ColMajor>(m, n, seed as u64);
+
+            for svd in [a.svd().unwrap(), a.svd_full().unwrap()] {
+                let usv = &(svd.u() * &svd.sigma()) * svd.vt();
+                assert_close(&usv, &a, 1e-12);
+                assert_orthonormal_cols(svd.u());
+                assert_orthonormal_cols(&svd.vt().transpose().unwrap());
+
+                let s = svd.singular_values();
+                assert!(s.windows(2).all(|w| w[0] >= w[1]) && s[s.len() - 1] >= 0.0);
+            }
+
+            let full = a.svd_full().unwrap();
+            assert_eq!((full.u().num_cols, full.vt().num_rows), (m, n));
+            let values = a.singular_values().unwrap();
+            assert!(values
+                .iter()
+                .zip(full.singular_values())
+                .all(|(a, b)| (a - b).abs() < 1e-12));
+        }
+    }
+
+    #[test]
+    fn rank_deficient() {
+        // The third column is the sum of the first two.
+        let a = from_rows::<RowMajor>(&[
+            &[1.0, 0.0, 1.0],
+            &[0.0, 1.0, 1.0],
+            &[1.0, 1.0, 2.0],
+            &[2.0, -1.0, 1.0],
+        ]);
+
+        assert_eq!(a.rank(1e-10).unwrap(), 2);
+        assert!(a.cond2().unwrap() > 1e12);
+
+        let pinv = a.pinv().unwrap();
+        assert_eq!((pinv.num_rows, pinv.num_cols), (3, 4));
+        assert_close(&(&(&a * &pinv) * &a), &a, 1e-12);
+        assert_close(&(&(&pinv * &a) * &pinv), &pinv, 1e-12);
+
+        let null = a.null_space().unwrap().unwrap();
+        assert_eq!((null.num_rows, null.num_cols), (3, 1));
+        assert_close(
+            &(&a * &null),
+            &Matrix::<f64, RowMajor>::new(4, 1).unwrap(),
+            1e-12,
+        );
+        assert_orthonormal_cols(&null);
+
+        let null_t = a.transpose().unwrap().null_space().unwrap().unwrap();
+        assert_eq!(null_t.num_cols, 2);
+    }
+
+    #[test]
+    fn pinv_of_invertible_is_inverse() {
+        let a = random::<RowMajor>(5, 5, 42);
+        assert_close(&a.pinv().unwrap(), &a.lu().unwrap().inverse(), 1e-10);
+    }
+
+    #[test]
+    fn full_rank_has_no_null_space() {
+        assert!(random::<RowMajor>(5, 5, 7).null_space().unwrap().is_none());
+        assert!(random::<ColMajor>(6, 3, 8).null_space().unwrap().is_none());
+    }
+
+    #[test]
+    fn zero_matrix() {
+        let a: Matrix<f64, RowMajor> = Matrix::new(3, 2).unwrap();
+        let svd = a.svd().unwrap();
+        assert_eq!(svd.singular_values(), &[0.0, 0.0]);
+        assert_close(&(&(svd.u() * &svd.sigma()) * svd.vt()), &a, 0.0);
+        assert_eq!(a.rank(0.0).unwrap(), 0);
+        assert!(a.cond2().unwrap().is_infinite());
+    }
+}
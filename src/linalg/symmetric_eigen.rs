@@ -0,0 +1,643 @@
+use super::{
+    apply_householder, householder, rotate_cols, subdiagonal_reflector_product, MatrixError,
+};
+use crate::transpose::{from_storage, storage_in};
+use crate::{simd, ColMajor, Matrix, Order, RealField};
+use num_traits::Float;
+use std::ops::Range;
+
+/// Matrices up to this size are diagonalized with Jacobi rotations by
+/// [`Matrix::symmetric_eigen`].
+const JACOBI_MAX_SIZE: usize = 16;
+
+/// Eigendecomposition of a symmetric matrix, `A = V * Λ * V^T`, with the eigenvalues in
+/// ascending order and the matching orthonormal eigenvectors in the columns of `V`.
+pub struct SymmetricEigen<T, O> {
+    values: Vec<T>,
+    vectors: Matrix<T, O>,
+}
+
+impl<T: RealField, O: Order> Matrix<T, O> {
+    /// Computes all eigenvalues and eigenvectors of a symmetric matrix. Only the lower
+    /// triangle is read.
+    ///
+    /// Small matrices use [`Matrix::symmetric_eigen_jacobi`]; larger ones are reduced to
+    /// tridiagonal form with Householder reflectors and diagonalized with implicit QL sweeps.
+    pub fn symmetric_eigen(&self) -> Result<SymmetricEigen<T, O>, MatrixError> {
+        let n = self.check_square()?;
+        if n <= JACOBI_MAX_SIZE {
+            return self.symmetric_eigen_jacobi();
+        }
+
+        let mut a = symmetric_storage(self);
+        let (mut d, mut e, tau) = tridiagonalize(&mut a, n);
+        let mut z = subdiagonal_reflector_product(&a, &tau, n);
+        tridiagonal_ql(&mut d, &mut e, &mut z)?;
+
+        Ok(SymmetricEigen::sorted(d, z))
+    }
+
+    /// Computes all eigenvalues and eigenvectors with the cyclic Jacobi method. It costs
+    /// several times as much as [`Matrix::symmetric_eigen`] for large matrices, but computes
+    /// small eigenvalues of well-scaled matrices to high relative accuracy.
+    pub fn symmetric_eigen_jacobi(&self) -> Result<SymmetricEigen<T, O>, MatrixError> {
+        let n = self.check_square()?;
+        let mut a = symmetric_storage(self);
+        let mut v = vec![T::zero(); n * n];
+        for j in 0..n {
+            v[j * n + j] = T::one();
+        }
+        jacobi(&mut a, n, &mut v)?;
+
+        let d = (0..n).map(|i| a[i * n + i]).collect();
+        Ok(SymmetricEigen::sorted(d, v))
+    }
+
+    /// Computes only the eigenvalues of a symmetric matrix, in ascending order.
+    pub fn symmetric_eigenvalues(&self) -> Result<Vec<T>, MatrixError> {
+        let n = self.check_square()?;
+        let mut a = symmetric_storage(self);
+        let (mut d, mut e, _) = tridiagonalize(&mut a, n);
+        tridiagonal_ql(&mut d, &mut e, &mut [])?;
+
+        d.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(d)
+    }
+
+    /// Computes the eigenvalues with indices in `range`, counting from the smallest, and
+    /// their eigenvectors. The eigenvalues of the tridiagonal form are found by bisection and
+    /// the eigenvectors by inverse iteration, so the cost beyond the reduction grows with
+    /// the size of the range rather than that of the matrix. An empty range is reported as
+    /// [`MatrixError::ZeroDimension`], since it has no eigenvectors to return.
+    pub fn symmetric_eigen_range(
+        &self,
+        range: Range<usize>,
+    ) -> Result<SymmetricEigen<T, O>, MatrixError> {
+        let n = self.check_square()?;
+        if range.start > range.end || range.end > n {
+            return Err(MatrixError::IndexOutOfBounds {
+                index: range.end.max(range.start),
+                len: n,
+            });
+        }
+        if range.is_empty() {
+            return Err(MatrixError::ZeroDimension { rows: n, cols: 0 });
+        }
+
+        let mut a = symmetric_storage(self);
+        let (d, e, tau) = tridiagonalize(&mut a, n);
+
+        let values: Vec<T> = range.map(|k| bisect(&d, &e, k)).collect();
+        let mut z = inverse_iteration(&d, &e, &values);
+        for col in z.chunks_exact_mut(n) {
+            for (k, &tau) in tau.iter().enumerate().rev() {
+                apply_householder(&a[k * n + k + 2..(k + 1) * n], tau, &mut col[k + 1..]);
+            }
+        }
+
+        let vectors = from_storage::<T, O, ColMajor>((n, values.len()), z);
+        Ok(SymmetricEigen { values, vectors })
+    }
+
+    pub(super) fn check_square(&self) -> Result<usize, MatrixError> {
+        if self.is_square() {
+            Ok(self.num_rows)
+        } else {
+            Err(MatrixError::NotSquare {
+                rows: self.num_rows,
+                cols: self.num_cols,
+            })
+        }
+    }
+}
+
+impl<T: RealField, O: Order> SymmetricEigen<T, O> {
+    /// Sorts the eigenvalues `d` in ascending order along with the columns of the
+    /// column-major `n x n` matrix `z`.
+    fn sorted(d: Vec<T>, z: Vec<T>) -> Self {
+        let n = d.len();
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|&i, &j| d[i].partial_cmp(&d[j]).unwrap_or(std::cmp::Ordering::Equal));
+
+        let values = order.iter().map(|&i| d[i]).collect();
+        let z = order
+            .iter()
+            .flat_map(|&i| &z[i * n..(i + 1) * n])
+            .copied()
+            .collect();
+
+        SymmetricEigen {
+            values,
+            vectors: from_storage::<T, O, ColMajor>((n, n), z),
+        }
+    }
+
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    /// Returns the eigenvectors as the columns of a matrix.
+    pub fn vectors(&self) -> &Matrix<T, O> {
+        &self.vectors
+    }
+}
+
+/// Copies the lower triangle of `m` into both triangles of a column-major buffer.
+fn symmetric_storage<T: RealField, O: Order>(m: &Matrix<T, O>) -> Vec<T> {
+    let n = m.num_rows;
+    let mut a = storage_in::<T, ColMajor, O>(m).into_owned();
+    for j in 0..n {
+        for i in j + 1..n {
+            a[i * n + j] = a[j * n + i];
+        }
+    }
+    a
+}
+
+/// Diagonalizes the full symmetric column-major matrix `a` in place with cyclic sweeps of
+/// Jacobi rotations, accumulating them into the columns of `v`.
+///
+/// An off-diagonal entry is skipped once it is negligible relative to its two diagonal
+/// entries, which is what lets the method resolve small eigenvalues accurately.
+fn jacobi<T: Float + 'static>(a: &mut [T], n: usize, v: &mut [T]) -> Result<(), MatrixError> {
+    const MAX_SWEEPS: usize = 50;
+    let eps = T::epsilon();
+    let two = T::one() + T::one();
+
+    for _ in 0..MAX_SWEEPS {
+        let mut rotated = false;
+        for p in 0..n {
+            for q in p + 1..n {
+                let apq = a[q * n + p];
+                let (app, aqq) = (a[p * n + p], a[q * n + q]);
+                if apq.abs() <= eps * (app.abs() * aqq.abs()).sqrt() {
+                    continue;
+                }
+                rotated = true;
+
+                let theta = (aqq - app) / (two * apq);
+                let t = theta.signum() / (theta.abs() + theta.hypot(T::one()));
+                let c = T::one() / t.hypot(T::one());
+                let s = t * c;
+
+                rotate_cols(a, n, p, q, c, -s);
+                for j in 0..n {
+                    let (x, y) = (a[j * n + p], a[j * n + q]);
+                    a[j * n + p] = c * x - s * y;
+                    a[j * n + q] = s * x + c * y;
+                }
+                a[p * n + p] = app - t * apq;
+                a[q * n + q] = aqq + t * apq;
+                a[q * n + p] = T::zero();
+                a[p * n + q] = T::zero();
+
+                rotate_cols(v, n, p, q, c, -s);
+            }
+        }
+
+        if !rotated {
+            return Ok(());
+        }
+    }
+
+    Err(MatrixError::NoConvergence)
+}
+
+/// Reduces the full symmetric column-major matrix `a` to tridiagonal form
+/// `Q^T * A * Q = T` with Householder reflectors.
+///
+/// Returns the diagonal and subdiagonal of `T` and the scalar factors of the reflectors.
+/// Reflector `k` acts on rows `k + 1..n` and is stored below the subdiagonal in column `k`.
+fn tridiagonalize<T: Float + 'static>(a: &mut [T], n: usize) -> (Vec<T>, Vec<T>, Vec<T>) {
+    let mut tau = Vec::with_capacity(n.saturating_sub(2));
+    let mut p = vec![T::zero(); n];
+    let half = T::one() / (T::one() + T::one());
+
+    for k in 0..n.saturating_sub(2) {
+        let t = householder(&mut a[k * n + k + 1..(k + 1) * n]);
+        tau.push(t);
+        if t == T::zero() {
+            continue;
+        }
+
+        // The trailing block becomes `A - v * w^T - w * v^T` with `w = p - (tau / 2) (p^T v) v`
+        // and `p = tau * A * v`.
+        let (head, tail) = a.split_at_mut((k + 1) * n);
+        let v_tail = &head[k * n + k + 2..(k + 1) * n];
+        let v = |i: usize| if i == 0 { T::one() } else { v_tail[i - 1] };
+        let m = n - k - 1;
+
+        let p = &mut p[..m];
+        p.iter_mut().for_each(|x| *x = T::zero());
+        for (i, col) in tail.chunks_exact(n).enumerate() {
+            simd::axpy(t * v(i), &col[k + 1..], p);
+        }
+        let pv = p[0] + simd::dot(&p[1..], v_tail);
+        let kappa = half * t * pv;
+        p[0] = p[0] - kappa;
+        simd::axpy(-kappa, v_tail, &mut p[1..]);
+
+        for (i, col) in tail.chunks_exact_mut(n).enumerate() {
+            let col = &mut col[k + 1..];
+            let (vi, wi) = (v(i), p[i]);
+            simd::axpy(-vi, p, col);
+            col[0] = col[0] - wi;
+            simd::axpy(-wi, v_tail, &mut col[1..]);
+        }
+    }
+
+    let d = (0..n).map(|i| a[i * n + i]).collect();
+    let e = (0..n.saturating_sub(1)).map(|i| a[i * n + i + 1]).collect();
+    (d, e, tau)
+}
+
+/// Diagonalizes the symmetric tridiagonal matrix with diagonal `d` and subdiagonal `e` by
+/// implicit QL sweeps with Wilkinson shifts (EISPACK's `tql2`), leaving the eigenvalues in
+/// `d` unsorted. The rotations are accumulated into the columns of `z` unless it is empty.
+fn tridiagonal_ql<T: Float + 'static>(
+    d: &mut [T],
+    e: &mut [T],
+    z: &mut [T],
+) -> Result<(), MatrixError> {
+    const MAX_SWEEPS_PER_VALUE: usize = 30;
+    let n = d.len();
+    let eps = T::epsilon();
+    let two = T::one() + T::one();
+
+    // A trailing zero keeps the indexing below uniform.
+    let mut e: Vec<T> = e
+        .iter()
+        .copied()
+        .chain(std::iter::once(T::zero()))
+        .collect();
+
+    for l in 0..n {
+        let mut sweeps = 0;
+        loop {
+            let mut m = l;
+            while m + 1 < n && e[m].abs() > eps * (d[m].abs() + d[m + 1].abs()) {
+                m += 1;
+            }
+            if m == l {
+                break;
+            }
+
+            sweeps += 1;
+            if sweeps > MAX_SWEEPS_PER_VALUE {
+                return Err(MatrixError::NoConvergence);
+            }
+
+            let g = (d[l + 1] - d[l]) / (two * e[l]);
+            let r = g.hypot(T::one());
+            let mut g = d[m] - d[l] + e[l] / (g + if g >= T::zero() { r } else { -r });
+            let (mut s, mut c, mut p) = (T::one(), T::one(), T::zero());
+
+            let mut deflated = false;
+            for i in (l..m).rev() {
+                let f = s * e[i];
+                let b = c * e[i];
+                let r = f.hypot(g);
+                e[i + 1] = r;
+                if r == T::zero() {
+                    d[i + 1] = d[i + 1] - p;
+                    e[m] = T::zero();
+                    deflated = true;
+                    break;
+                }
+
+                s = f / r;
+                c = g / r;
+                g = d[i + 1] - p;
+                let r = (d[i] - g) * s + two * c * b;
+                p = s * r;
+                d[i + 1] = g + p;
+                g = c * r - b;
+
+                if !z.is_empty() {
+                    rotate_cols(z, n, i, i + 1, c, -s);
+                }
+            }
+
+            if !deflated {
+                d[l] = d[l] - p;
+                e[l] = g;
+                e[m] = T::zero();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the number of eigenvalues of the tridiagonal matrix smaller than `x`, from the
+/// signs of the pivots of `T - x * I` (Sturm sequence count).
+fn count_below<T: Float>(d: &[T], e: &[T], x: T, pivmin: T) -> usize {
+    let mut count = 0;
+    let mut q = T::one();
+    for i in 0..d.len() {
+        let off = if i > 0 {
+            e[i - 1] * e[i - 1] / q
+        } else {
+            T::zero()
+        };
+        q = d[i] - x - off;
+        if q.abs() < pivmin {
+            q = -pivmin;
+        }
+        if q < T::zero() {
+            count += 1;
+        }
+    }
+    count
+}
+
+/// Finds the `k`-th smallest eigenvalue of the tridiagonal matrix by bisection, starting
+/// from the Gershgorin interval.
+fn bisect<T: Float>(d: &[T], e: &[T], k: usize) -> T {
+    let n = d.len();
+    let radius = |i: usize| {
+        let above = if i > 0 { e[i - 1].abs() } else { T::zero() };
+        let below = if i + 1 < n { e[i].abs() } else { T::zero() };
+        above + below
+    };
+    let mut lo = (0..n).fold(T::infinity(), |lo, i| lo.min(d[i] - radius(i)));
+    let mut hi = (0..n).fold(T::neg_infinity(), |hi, i| hi.max(d[i] + radius(i)));
+
+    let eps = T::epsilon();
+    let norm = lo.abs().max(hi.abs());
+    let pivmin = T::min_positive_value() * e.iter().fold(T::one(), |max, e| max.max(*e * *e));
+    let two = T::one() + T::one();
+    lo = lo - two * eps * norm - pivmin;
+    hi = hi + two * eps * norm + pivmin;
+
+    while hi - lo > two * eps * lo.abs().max(hi.abs()) + pivmin {
+        let mid = (lo + hi) / two;
+        if mid <= lo || mid >= hi {
+            break;
+        }
+        if count_below(d, e, mid, pivmin) > k {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+    (lo + hi) / two
+}
+
+/// Computes eigenvectors of the tridiagonal matrix for the ascending eigenvalues `values`
+/// by inverse iteration, returned as the columns of a column-major `n x values.len()`
+/// matrix. Vectors of eigenvalues closer than `1e-3 * ||T||` are kept orthogonal with
+/// Gram–Schmidt, as in LAPACK's `xSTEIN`.
+fn inverse_iteration<T: Float + 'static>(d: &[T], e: &[T], values: &[T]) -> Vec<T> {
+    const ITERATIONS: usize = 3;
+    let n = d.len();
+    let norm = (0..n).fold(T::zero(), |max, i| {
+        let off = e.get(i).map_or(T::zero(), |e| e.abs());
+        let prev = if i > 0 { e[i - 1].abs() } else { T::zero() };
+        max.max(d[i].abs() + off + prev)
+    });
+    let cluster_gap = T::from(1e-3).unwrap() * norm;
+    let tiny = T::epsilon() * norm.max(T::min_positive_value());
+
+    let mut z = vec![T::zero(); n * values.len()];
+    let mut cluster_start = 0;
+    for (j, &lambda) in values.iter().enumerate() {
+        if j > 0 && lambda - values[j - 1] > cluster_gap {
+            cluster_start = j;
+        }
+
+        let lu = TridiagonalLu::new(d, e, lambda, tiny);
+        let (done, rest) = z.split_at_mut(j * n);
+        let x = &mut rest[..n];
+
+        // A fixed, irregular starting vector avoids being orthogonal to the eigenvector.
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64 ^ j as u64;
+        for x in x.iter_mut() {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            *x = T::from((seed >> 11) as f64 / (1u64 << 53) as f64 - 0.5).unwrap();
+        }
+
+        for _ in 0..ITERATIONS {
+            lu.solve(x);
+            for prev in done.chunks_exact(n).skip(cluster_start) {
+                let proj = simd::dot(prev, x);
+                simd::axpy(-proj, prev, x);
+            }
+            let scale = super::norm2(x);
+            simd::scale(x, T::one() / scale);
+        }
+    }
+    z
+}
+
+/// LU factorization with partial pivoting of `T - lambda * I` for a symmetric tridiagonal
+/// `T`. The upper factor has two superdiagonals because of the row interchanges.
+struct TridiagonalLu<T> {
+    diag: Vec<T>,
+    super1: Vec<T>,
+    super2: Vec<T>,
+    multipliers: Vec<T>,
+    swapped: Vec<bool>,
+}
+
+impl<T: Float> TridiagonalLu<T> {
+    /// Factorizes `T - lambda * I`, replacing pivots smaller than `tiny` by `tiny` so that
+    /// the factorization of a (numerically) singular matrix can still be used for inverse
+    /// iteration.
+    fn new(d: &[T], e: &[T], lambda: T, tiny: T) -> Self {
+        let n = d.len();
+        let mut diag: Vec<T> = d.iter().map(|&d| d - lambda).collect();
+        let mut super1 = e.to_vec();
+        let mut super2 = vec![T::zero(); n.saturating_sub(2)];
+        let mut multipliers = Vec::with_capacity(n.saturating_sub(1));
+        let mut swapped = Vec::with_capacity(n.saturating_sub(1));
+
+        for i in 0..n.saturating_sub(1) {
+            let sub = e[i];
+            if diag[i].abs() >= sub.abs() {
+                let m = if diag[i] == T::zero() {
+                    T::zero()
+                } else {
+                    sub / diag[i]
+                };
+                diag[i + 1] = diag[i + 1] - m * super1[i];
+                multipliers.push(m);
+                swapped.push(false);
+            } else {
+                let m = diag[i] / sub;
+                let (d_next, s_next) = (diag[i + 1], super1.get(i + 1).copied());
+                diag[i] = sub;
+                diag[i + 1] = super1[i] - m * d_next;
+                super1[i] = d_next;
+                if let Some(s_next) = s_next {
+                    super2[i] = s_next;
+                    super1[i + 1] = -m * s_next;
+                }
+                multipliers.push(m);
+                swapped.push(true);
+            }
+        }
+
+        for d in &mut diag {
+            if d.abs() < tiny {
+                *d = if *d < T::zero() { -tiny } else { tiny };
+            }
+        }
+
+        TridiagonalLu {
+            diag,
+            super1,
+            super2,
+            multipliers,
+            swapped,
+        }
+    }
+
+    fn solve(&self, b: &mut [T]) {
+        let n = b.len();
+        for i in 0..n.saturating_sub(1) {
+            if self.swapped[i] {
+                b.swap(i, i + 1);
+            }
+            b[i + 1] = b[i + 1] - self.multipliers[i] * b[i];
+        }
+
+        for i in (0..n).rev() {
+            let mut x = b[i];
+            if i + 1 < n {
+                x = x - self.super1[i] * b[i + 1];
+            }
+            if i + 2 < n {
+                x = x - self.super2[i] * b[i + 2];
+            }
+            b[i] = x / self.diag[i];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RowMajor;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn random_symmetric(n: usize, seed: u64) -> Matrix<f64, RowMajor> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut m: Matrix<f64, RowMajor> = Matrix::new(n, n).unwrap();
+        for i in 0..n {
+            for j in 0..=i {
+                let x = rng.gen_range(-1.0..1.0);
+                m[(i, j)] = x;
+                m[(j, i)] = x;
+            }
+        }
+        m
+    }
+
+    /// Checks `A * V = V * Λ`, `V^T * V = I` and the ordering of the eigenvalues.
+    fn assert_eigenpairs(a: &Matrix<f64, RowMajor>, eig: &SymmetricEigen<f64, RowMajor>) {
+        let (values, v) = (eig.values(), eig.vectors());
+        assert!(values.windows(2).all(|w| w[0] <= w[1]));
+
+        let av = a * v;
+        for (j, &lambda) in values.iter().enumerate() {
+            for i in 0..a.num_rows {
+                assert!((av[(i, j)] - lambda * v[(i, j)]).abs() < 1e-10);
+            }
+        }
+
+        let vtv = &v.transpose().unwrap() * v;
+        for i in 0..values.len() {
+            for j in 0..values.len() {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((vtv[(i, j)] - expected).abs() < 1e-10);
+            }
+        }
+    }
+
+    #[test]
+    fn known_eigenvalues() {
+        let mut a: Matrix<f64, RowMajor> = Matrix::new(3, 3).unwrap();
+        a.data = vec![2.0, -1.0, 0.0, -1.0, 2.0, -1.0, 0.0, -1.0, 2.0];
+        let expected = [2.0 - 2f64.sqrt(), 2.0, 2.0 + 2f64.sqrt()];
+
+        let eig = a.symmetric_eigen().unwrap();
+        assert_eigenpairs(&a, &eig);
+        let values = a.symmetric_eigenvalues().unwrap();
+        for (x, y) in eig.values().iter().zip(&expected) {
+            assert!((x - y).abs() < 1e-14);
+        }
+        assert!(values
+            .iter()
+            .zip(&expected)
+            .all(|(x, y)| (x - y).abs() < 1e-14));
+    }
+
+    #[test]
+    fn jacobi_and_ql_agree() {
+        for n in [1, 2, 5, 40] {
+            let a = random_symmetric(n, n as u64);
+            let jacobi = a.symmetric_eigen_jacobi().unwrap();
+            let ql = a.symmetric_eigen().unwrap();
+            assert_eigenpairs(&a, &jacobi);
+            assert_eigenpairs(&a, &ql);
+
+            let values = a.symmetric_eigenvalues().unwrap();
+            for ((x, y), z) in jacobi.values().iter().zip(ql.values()).zip(&values) {
+                assert!((x - y).abs() < 1e-12 && (y - z).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn index_range() {
+        let a = random_symmetric(30, 7);
+        let all = a.symmetric_eigenvalues().unwrap();
+
+        let subset = a.symmetric_eigen_range(3..9).unwrap();
+        assert_eq!(subset.values().len(), 6);
+        assert_eq!(subset.vectors().dims().cols, 6);
+        assert!(subset
+            .values()
+            .iter()
+            .zip(&all[3..9])
+            .all(|(x, y)| (x - y).abs() < 1e-12));
+        assert_eigenpairs(&a, &subset);
+
+        assert!(matches!(
+            a.symmetric_eigen_range(25..31).err(),
+            Some(MatrixError::IndexOutOfBounds { index: 31, len: 30 })
+        ));
+        assert!(matches!(
+            a.symmetric_eigen_range(3..3).err(),
+            Some(MatrixError::ZeroDimension { rows: 30, cols: 0 })
+        ));
+    }
+
+    #[test]
+    fn repeated_eigenvalues() {
+        // `Q * diag(1, 1, 1, 3, 5) * Q^T` for an orthogonal `Q`.
+        let q = random_symmetric(5, 11).qr().q();
+        let mut d: Matrix<f64, RowMajor> = Matrix::new(5, 5).unwrap();
+        for (i, &x) in [1.0, 1.0, 1.0, 3.0, 5.0].iter().enumerate() {
+            d[(i, i)] = x;
+        }
+        let a = &(&q * &d) * &q.transpose().unwrap();
+
+        let subset = a.symmetric_eigen_range(0..4).unwrap();
+        assert_eigenpairs(&a, &subset);
+        assert_eigenpairs(&a, &a.symmetric_eigen().unwrap());
+    }
+
+    #[test]
+    fn not_square() {
+        let a: Matrix<f64, RowMajor> = Matrix::new(2, 3).unwrap();
+        assert!(matches!(
+            a.symmetric_eigen().err(),
+            Some(MatrixError::NotSquare { rows: 2, cols: 3 })
+        ));
+    }
+}
@@ -0,0 +1,733 @@
+//! Reading and writing matrices in the Matrix Market exchange format.
+//!
+//! A file starts with a banner such as `%%MatrixMarket matrix coordinate real symmetric`,
+//! followed by `%` comment lines, a size line and the entries. `array` files list every stored
+//! value in column-major order; `coordinate` files list `row col value` triples with 1-based
+//! indices. Symmetric and skew-symmetric files store only the lower triangle.
+
+use crate::csv_io::{write_number, FieldDeserializer, NumberFormat};
+use crate::{checked_len, Matrix, MatrixError, Order, Scalar};
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// How a Matrix Market file lists its entries: the `format` word of the banner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MtxLayout {
+    /// Every stored value, column by column.
+    Array,
+    /// One `row col value` line per nonzero entry.
+    Coordinate,
+}
+
+/// The type of the values: the `field` word of the banner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MtxField {
+    Real,
+    Integer,
+    /// Coordinate entries without values. Every listed position holds 1.
+    Pattern,
+}
+
+/// Which part of the matrix is stored: the `symmetry` word of the banner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MtxSymmetry {
+    General,
+    /// Only the lower triangle and the diagonal are stored; `a[j][i] = a[i][j]`.
+    Symmetric,
+    /// Only the strictly lower triangle is stored; `a[j][i] = -a[i][j]` and the diagonal is 0.
+    SkewSymmetric,
+}
+
+impl MtxLayout {
+    fn name(self) -> &'static str {
+        match self {
+            MtxLayout::Array => "array",
+            MtxLayout::Coordinate => "coordinate",
+        }
+    }
+}
+
+impl MtxField {
+    fn name(self) -> &'static str {
+        match self {
+            MtxField::Real => "real",
+            MtxField::Integer => "integer",
+            MtxField::Pattern => "pattern",
+        }
+    }
+}
+
+impl MtxSymmetry {
+    fn name(self) -> &'static str {
+        match self {
+            MtxSymmetry::General => "general",
+            MtxSymmetry::Symmetric => "symmetric",
+            MtxSymmetry::SkewSymmetric => "skew-symmetric",
+        }
+    }
+
+    /// The first stored row of column `j`.
+    fn first_row(self, j: usize) -> usize {
+        match self {
+            MtxSymmetry::General => 0,
+            MtxSymmetry::Symmetric => j,
+            MtxSymmetry::SkewSymmetric => j + 1,
+        }
+    }
+}
+
+/// Options for writing Matrix Market files, set with chained builder calls on
+/// [`MtxWriteOptions::new`].
+///
+/// The defaults write a dense `array real general` file with the shortest number format.
+#[derive(Debug, Clone)]
+pub struct MtxWriteOptions {
+    layout: MtxLayout,
+    field: MtxField,
+    symmetry: MtxSymmetry,
+    format: NumberFormat,
+}
+
+impl Default for MtxWriteOptions {
+    fn default() -> Self {
+        MtxWriteOptions {
+            layout: MtxLayout::Array,
+            field: MtxField::Real,
+            symmetry: MtxSymmetry::General,
+            format: NumberFormat::Shortest,
+        }
+    }
+}
+
+impl MtxWriteOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `Coordinate` writes only the entries that differ from `T::default()`.
+    pub fn layout(mut self, layout: MtxLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Names the field in the banner. `Pattern` requires the coordinate layout and writes
+    /// positions only. `Integer` writes every value in the shortest format and fails if one
+    /// is not a whole number; `Real` uses the number format.
+    pub fn field(mut self, field: MtxField) -> Self {
+        self.field = field;
+        self
+    }
+
+    /// Writes only the part of the matrix that `symmetry` stores. The matrix must be square,
+    /// and the upper triangle is not checked.
+    pub fn symmetry(mut self, symmetry: MtxSymmetry) -> Self {
+        self.symmetry = symmetry;
+        self
+    }
+
+    pub fn format(mut self, format: NumberFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+impl<T: Scalar + Default + for<'a> Deserialize<'a>, O: Order> Matrix<T, O> {
+    /// Reads a matrix in Matrix Market format, expanding symmetric and skew-symmetric storage
+    /// into the full matrix. Positions missing from a coordinate file hold `T::default()`, and
+    /// a repeated position keeps the last value.
+    ///
+    /// Complex and Hermitian files are rejected with `MatrixError::Unsupported`.
+    pub fn from_mtx<R: Read>(reader: R) -> Result<Self, MatrixError> {
+        let mut lines = Lines {
+            reader: BufReader::new(reader),
+            buf: String::new(),
+            line_no: 0,
+        };
+        let (layout, field, symmetry) = read_banner(&mut lines)?;
+
+        let size_fields = match layout {
+            MtxLayout::Array => 2,
+            MtxLayout::Coordinate => 3,
+        };
+        let Some((size_line, size)) = lines.next_fields()? else {
+            return Err(missing(lines.line_no + 1, "a size line"));
+        };
+        if size.len() != size_fields {
+            return Err(MatrixError::RaggedRow {
+                line: size_line,
+                expected: size_fields,
+                found: size.len(),
+            });
+        }
+        let count = |k: usize| -> Result<usize, MatrixError> {
+            size[k].parse::<usize>().map_err(|err| MatrixError::Parse {
+                line: size_line,
+                column: k + 1,
+                text: size[k].to_string(),
+                source: Box::new(err),
+            })
+        };
+        let (num_rows, num_cols) = (count(0)?, count(1)?);
+        if symmetry != MtxSymmetry::General && num_rows != num_cols {
+            return Err(MatrixError::NotSquare {
+                rows: num_rows,
+                cols: num_cols,
+            });
+        }
+        let len = checked_len::<T>(num_rows, num_cols)?;
+        let num_entries = match layout {
+            MtxLayout::Array => (0..num_cols)
+                .map(|j| num_rows.saturating_sub(symmetry.first_row(j)))
+                .sum(),
+            MtxLayout::Coordinate => count(2)?,
+        };
+
+        let mut m = Matrix {
+            num_rows,
+            num_cols,
+            data: vec![T::default(); len],
+            _order: std::marker::PhantomData,
+        };
+        let one = match field {
+            MtxField::Pattern => Some(parse_value::<T>("1", 1, 4)?),
+            _ => None,
+        };
+        let mut positions =
+            (0..num_cols).flat_map(|j| (symmetry.first_row(j)..num_rows).map(move |i| (i, j)));
+
+        for entry in 0..num_entries {
+            let Some((line, fields)) = lines.next_fields()? else {
+                let expected = format!("{} entries, found {}", num_entries, entry);
+                return Err(missing(lines.line_no + 1, &expected));
+            };
+
+            let value_column = match layout {
+                MtxLayout::Array => 0,
+                MtxLayout::Coordinate => 2,
+            };
+            let expected = value_column + usize::from(one.is_none());
+            if fields.len() != expected {
+                return Err(MatrixError::RaggedRow {
+                    line,
+                    expected,
+                    found: fields.len(),
+                });
+            }
+
+            let (i, j) = match layout {
+                MtxLayout::Array => positions.next().unwrap_or_default(),
+                MtxLayout::Coordinate => (
+                    parse_index(&fields, 0, num_rows, line)?,
+                    parse_index(&fields, 1, num_cols, line)?,
+                ),
+            };
+            if symmetry == MtxSymmetry::SkewSymmetric && i == j {
+                return Err(MatrixError::Parse {
+                    line,
+                    column: 1,
+                    text: fields[0].to_string(),
+                    source: "a skew-symmetric file stores no diagonal entries".into(),
+                });
+            }
+            let (x, mirrored) = match one {
+                Some(one) => (one, one),
+                None => {
+                    let text = fields[value_column];
+                    let x = parse_value(text, line, value_column + 1)?;
+                    let mirrored = match symmetry {
+                        MtxSymmetry::SkewSymmetric => {
+                            parse_value(&negated(text), line, value_column + 1)?
+                        }
+                        _ => x,
+                    };
+                    (x, mirrored)
+                }
+            };
+            m[(i, j)] = x;
+            if symmetry != MtxSymmetry::General && i != j {
+                m[(j, i)] = mirrored;
+            }
+        }
+
+        if let Some((line, fields)) = lines.next_fields()? {
+            return Err(MatrixError::Parse {
+                line,
+                column: 1,
+                text: fields[0].to_string(),
+                source: format!("the size line declares only {} entries", num_entries).into(),
+            });
+        }
+
+        Ok(m)
+    }
+
+    pub fn from_mtx_path<P: AsRef<Path>>(path: P) -> Result<Self, MatrixError> {
+        Self::from_mtx(File::open(path)?)
+    }
+}
+
+impl<T: fmt::Display + fmt::LowerExp + PartialEq + Default, O: Order> Matrix<T, O> {
+    /// Writes the matrix in Matrix Market format as configured by `opts`.
+    pub fn to_mtx<W: Write>(&self, writer: W, opts: &MtxWriteOptions) -> Result<(), MatrixError> {
+        if opts.field == MtxField::Pattern {
+            if opts.layout == MtxLayout::Array {
+                return Err(MatrixError::Unsupported(
+                    "Matrix Market pattern field with the array layout".to_string(),
+                ));
+            }
+            if opts.symmetry == MtxSymmetry::SkewSymmetric {
+                return Err(MatrixError::Unsupported(
+                    "skew-symmetric Matrix Market pattern".to_string(),
+                ));
+            }
+        }
+        if opts.symmetry != MtxSymmetry::General && !self.is_square() {
+            return Err(MatrixError::NotSquare {
+                rows: self.num_rows,
+                cols: self.num_cols,
+            });
+        }
+
+        let zero = T::default();
+        let stored = (0..self.num_cols)
+            .flat_map(|j| (opts.symmetry.first_row(j)..self.num_rows).map(move |i| (i, j)));
+        let mut buf = String::new();
+        let format = match opts.field {
+            MtxField::Integer => NumberFormat::Shortest,
+            _ => opts.format,
+        };
+        if opts.field == MtxField::Integer {
+            // Other readers reject anything but plain integers under an `integer` banner.
+            for pos in stored.clone() {
+                buf.clear();
+                write_number(&mut buf, &self[pos], format);
+                let digits = buf.strip_prefix('-').unwrap_or(&buf);
+                if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(MatrixError::Unsupported(format!(
+                        "the non-integral value {} in a Matrix Market integer file",
+                        buf
+                    )));
+                }
+            }
+        }
+
+        let mut w = BufWriter::new(writer);
+        writeln!(
+            w,
+            "%%MatrixMarket matrix {} {} {}",
+            opts.layout.name(),
+            opts.field.name(),
+            opts.symmetry.name()
+        )?;
+
+        match opts.layout {
+            MtxLayout::Array => {
+                writeln!(w, "{} {}", self.num_rows, self.num_cols)?;
+                for (i, j) in stored {
+                    buf.clear();
+                    write_number(&mut buf, &self[(i, j)], format);
+                    writeln!(w, "{}", buf)?;
+                }
+            }
+            MtxLayout::Coordinate => {
+                let nonzeros = stored.clone().filter(|&pos| self[pos] != zero);
+                let nnz = nonzeros.clone().count();
+                writeln!(w, "{} {} {}", self.num_rows, self.num_cols, nnz)?;
+                for (i, j) in nonzeros {
+                    if opts.field == MtxField::Pattern {
+                        writeln!(w, "{} {}", i + 1, j + 1)?;
+                    } else {
+                        buf.clear();
+                        write_number(&mut buf, &self[(i, j)], format);
+                        writeln!(w, "{} {} {}", i + 1, j + 1, buf)?;
+                    }
+                }
+            }
+        }
+
+        w.flush()?;
+        Ok(())
+    }
+
+    pub fn to_mtx_path<P: AsRef<Path>>(
+        &self,
+        path: P,
+        opts: &MtxWriteOptions,
+    ) -> Result<(), MatrixError> {
+        self.to_mtx(File::create(path)?, opts)
+    }
+}
+
+/// The lines of a file, with their 1-based numbers.
+struct Lines<R> {
+    reader: R,
+    buf: String,
+    line_no: usize,
+}
+
+impl<R: BufRead> Lines<R> {
+    /// Reads the next line, or returns `false` at the end of the input.
+    fn next_line(&mut self) -> Result<bool, MatrixError> {
+        self.buf.clear();
+        if self.reader.read_line(&mut self.buf)? == 0 {
+            return Ok(false);
+        }
+        self.line_no += 1;
+        Ok(true)
+    }
+
+    /// Returns the number and the whitespace-separated fields of the next line that is not
+    /// empty or a comment, or `None` at the end of the input.
+    fn next_fields(&mut self) -> Result<Option<(usize, Vec<&str>)>, MatrixError> {
+        loop {
+            if !self.next_line()? {
+                return Ok(None);
+            }
+            let line = self.buf.trim_start();
+            if !line.is_empty() && !line.starts_with('%') {
+                break;
+            }
+        }
+        Ok(Some((
+            self.line_no,
+            self.buf.split_ascii_whitespace().collect(),
+        )))
+    }
+}
+
+fn read_banner<R: BufRead>(
+    lines: &mut Lines<R>,
+) -> Result<(MtxLayout, MtxField, MtxSymmetry), MatrixError> {
+    if !lines.next_line()? {
+        return Err(missing(1, "a %%MatrixMarket banner"));
+    }
+    let words: Vec<String> = lines
+        .buf
+        .split_ascii_whitespace()
+        .map(str::to_ascii_lowercase)
+        .collect();
+    let invalid = |column: usize, expected: &str| MatrixError::Parse {
+        line: 1,
+        column,
+        text: words.get(column - 1).cloned().unwrap_or_default(),
+        source: format!("expected {}", expected).into(),
+    };
+
+    if words.first().map(String::as_str) != Some("%%matrixmarket") {
+        return Err(invalid(1, "a %%MatrixMarket banner"));
+    }
+    match words.get(1).map(String::as_str) {
+        Some("matrix") => {}
+        Some(object) => {
+            return Err(MatrixError::Unsupported(format!(
+                "Matrix Market object `{}`",
+                object
+            )))
+        }
+        None => return Err(invalid(2, "`matrix`")),
+    }
+    let layout = match words.get(2).map(String::as_str) {
+        Some("array") => MtxLayout::Array,
+        Some("coordinate") => MtxLayout::Coordinate,
+        _ => return Err(invalid(3, "`array` or `coordinate`")),
+    };
+    let field = match words.get(3).map(String::as_str) {
+        Some("real" | "double") => MtxField::Real,
+        Some("integer") => MtxField::Integer,
+        Some("pattern") if layout == MtxLayout::Coordinate => MtxField::Pattern,
+        Some("pattern") => return Err(invalid(4, "a numeric field for an array")),
+        Some("complex") => {
+            return Err(MatrixError::Unsupported(
+                "complex Matrix Market field".to_string(),
+            ))
+        }
+        _ => return Err(invalid(4, "`real`, `integer` or `pattern`")),
+    };
+    let symmetry = match words.get(4).map(String::as_str) {
+        Some("general") => MtxSymmetry::General,
+        Some("symmetric") => MtxSymmetry::Symmetric,
+        Some("skew-symmetric") if field != MtxField::Pattern => MtxSymmetry::SkewSymmetric,
+        Some("skew-symmetric") => return Err(invalid(5, "a pattern to be general or symmetric")),
+        Some("hermitian") => {
+            return Err(MatrixError::Unsupported(
+                "Hermitian Matrix Market symmetry".to_string(),
+            ))
+        }
+        _ => return Err(invalid(5, "`general`, `symmetric` or `skew-symmetric`")),
+    };
+
+    Ok((layout, field, symmetry))
+}
+
+/// The error for input that ends at `line` before `expected`.
+fn missing(line: usize, expected: &str) -> MatrixError {
+    MatrixError::Parse {
+        line,
+        column: 1,
+        text: String::new(),
+        source: format!("unexpected end of input, expected {}", expected).into(),
+    }
+}
+
+/// Parses the 1-based index in `fields[k]` and checks it against `len`.
+fn parse_index(fields: &[&str], k: usize, len: usize, line: usize) -> Result<usize, MatrixError> {
+    let text = fields[k];
+    let source: Box<dyn Error + Send + Sync> = match text.parse::<usize>() {
+        Ok(index @ 1..) if index <= len => return Ok(index - 1),
+        Ok(_) => format!("index out of range 1..={}", len).into(),
+        Err(err) => Box::new(err),
+    };
+    Err(MatrixError::Parse {
+        line,
+        column: k + 1,
+        text: text.to_string(),
+        source,
+    })
+}
+
+fn parse_value<T: for<'a> Deserialize<'a>>(
+    text: &str,
+    line: usize,
+    column: usize,
+) -> Result<T, MatrixError> {
+    T::deserialize(FieldDeserializer(text)).map_err(|err| MatrixError::Parse {
+        line,
+        column,
+        text: text.to_string(),
+        source: Box::new(err),
+    })
+}
+
+/// Returns `text` with its sign flipped, so that mirrored entries of skew-symmetric matrices
+/// are parsed as exact negations.
+fn negated(text: &str) -> String {
+    match text.strip_prefix('-') {
+        Some(rest) => rest.to_string(),
+        None => format!("-{}", text.strip_prefix('+').unwrap_or(text)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColMajor, RowMajor};
+
+    fn rows<O: Order>(m: &Matrix<f64, O>) -> Vec<Vec<f64>> {
+        (0..m.num_rows)
+            .map(|i| (0..m.num_cols).map(|j| m[(i, j)]).collect())
+            .collect()
+    }
+
+    fn read<O: Order>(text: &str) -> Result<Matrix<f64, O>, MatrixError> {
+        Matrix::from_mtx(text.as_bytes())
+    }
+
+    #[test]
+    fn read_array_and_coordinate() {
+        let array =
+            "%%MatrixMarket matrix array real general\n% a comment\n2 3\n1\n4\n2\n5\n3\n6\n";
+        let expected = vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]];
+        assert_eq!(rows(&read::<RowMajor>(array).unwrap()), expected);
+        assert_eq!(rows(&read::<ColMajor>(array).unwrap()), expected);
+
+        let coordinate =
+            "%%MatrixMarket Matrix Coordinate Real General\n%\n\n2 3 3\n1 1 1.5\n2 3 -2e3\n1 2 7\n";
+        assert_eq!(
+            rows(&read::<ColMajor>(coordinate).unwrap()),
+            vec![vec![1.5, 7.0, 0.0], vec![0.0, 0.0, -2000.0]]
+        );
+
+        let ints: Matrix<i32, RowMajor> = Matrix::from_mtx(
+            "%%MatrixMarket matrix coordinate integer general\n1 2 1\n1 2 -4\n".as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(ints.data, vec![0, -4]);
+    }
+
+    #[test]
+    fn expand_symmetric_storage() {
+        let symmetric =
+            "%%MatrixMarket matrix coordinate real symmetric\n3 3 3\n1 1 1\n3 1 2\n3 2 3\n";
+        assert_eq!(
+            rows(&read::<RowMajor>(symmetric).unwrap()),
+            vec![
+                vec![1.0, 0.0, 2.0],
+                vec![0.0, 0.0, 3.0],
+                vec![2.0, 3.0, 0.0]
+            ]
+        );
+
+        let skew = "%%MatrixMarket matrix array real skew-symmetric\n3 3\n1\n-2\n3\n";
+        assert_eq!(
+            rows(&read::<ColMajor>(skew).unwrap()),
+            vec![
+                vec![0.0, -1.0, 2.0],
+                vec![1.0, 0.0, -3.0],
+                vec![-2.0, 3.0, 0.0]
+            ]
+        );
+
+        let pattern = "%%MatrixMarket matrix coordinate pattern symmetric\n2 2 2\n1 1\n2 1\n";
+        let m: Matrix<u8, RowMajor> = Matrix::from_mtx(pattern.as_bytes()).unwrap();
+        assert_eq!(m.data, vec![1, 1, 1, 0]);
+
+        // Unsigned types cannot hold the mirrored entries of a skew-symmetric matrix.
+        let skew = "%%MatrixMarket matrix coordinate integer skew-symmetric\n2 2 1\n2 1 4\n";
+        assert!(matches!(
+            Matrix::<u8, RowMajor>::from_mtx(skew.as_bytes()),
+            Err(MatrixError::Parse { line: 3, column: 3, ref text, .. }) if text == "-4"
+        ));
+    }
+
+    #[test]
+    fn write_and_read_back() {
+        let m: Matrix<f64, ColMajor> =
+            Matrix::from_csv_str("1,0,-2.5\n0,3,0\n-2.5,0,0.1\n").unwrap();
+
+        for layout in [MtxLayout::Array, MtxLayout::Coordinate] {
+            for symmetry in [MtxSymmetry::General, MtxSymmetry::Symmetric] {
+                let opts = MtxWriteOptions::new().layout(layout).symmetry(symmetry);
+                let mut out = Vec::new();
+                m.to_mtx(&mut out, &opts).unwrap();
+                let back: Matrix<f64, RowMajor> = Matrix::from_mtx(&out[..]).unwrap();
+                assert_eq!(rows(&back), rows(&m));
+            }
+        }
+
+        let opts = MtxWriteOptions::new()
+            .layout(MtxLayout::Coordinate)
+            .symmetry(MtxSymmetry::Symmetric);
+        let mut out = Vec::new();
+        m.to_mtx(&mut out, &opts).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "%%MatrixMarket matrix coordinate real symmetric\n3 3 4\n1 1 1\n3 1 -2.5\n2 2 3\n3 3 0.1\n"
+        );
+
+        let opts = opts.field(MtxField::Pattern).symmetry(MtxSymmetry::General);
+        let mut out = Vec::new();
+        m.to_mtx(&mut out, &opts).unwrap();
+        let pattern: Matrix<f64, RowMajor> = Matrix::from_mtx(&out[..]).unwrap();
+        assert_eq!(
+            pattern.data,
+            vec![1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0]
+        );
+
+        let skew: Matrix<f64, RowMajor> = Matrix::from_csv_str("0,-1\n1,0\n").unwrap();
+        let file = std::env::temp_dir().join(format!("mtx_round_trip_{}.mtx", std::process::id()));
+        let opts = MtxWriteOptions::new()
+            .symmetry(MtxSymmetry::SkewSymmetric)
+            .format(NumberFormat::Fixed(1));
+        skew.to_mtx_path(&file, &opts).unwrap();
+        let text = std::fs::read_to_string(&file).unwrap();
+        let back: Matrix<f64, ColMajor> = Matrix::from_mtx_path(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(
+            text,
+            "%%MatrixMarket matrix array real skew-symmetric\n2 2\n1.0\n"
+        );
+        assert_eq!(rows(&back), rows(&skew));
+
+        assert!(matches!(
+            m.to_mtx(Vec::new(), &MtxWriteOptions::new().field(MtxField::Pattern)),
+            Err(MatrixError::Unsupported(_))
+        ));
+        let whole: Matrix<f64, RowMajor> = Matrix::from_csv_str("1,-3\n0,1e20\n").unwrap();
+        let opts = MtxWriteOptions::new()
+            .field(MtxField::Integer)
+            .format(NumberFormat::Fixed(2));
+        let mut out = Vec::new();
+        whole.to_mtx(&mut out, &opts).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "%%MatrixMarket matrix array integer general\n2 2\n1\n0\n-3\n100000000000000000000\n"
+        );
+        assert!(matches!(
+            m.to_mtx(Vec::new(), &opts),
+            Err(MatrixError::Unsupported(ref msg)) if msg.contains("non-integral value -2.5")
+        ));
+
+        let wide: Matrix<f64, RowMajor> = Matrix::new(2, 3).unwrap();
+        let opts = MtxWriteOptions::new().symmetry(MtxSymmetry::Symmetric);
+        assert!(matches!(
+            wide.to_mtx(Vec::new(), &opts),
+            Err(MatrixError::NotSquare { rows: 2, cols: 3 })
+        ));
+    }
+
+    #[test]
+    fn errors() {
+        let banner = "%%MatrixMarket matrix coordinate real general\n";
+        assert!(matches!(
+            read::<RowMajor>("2 2\n1\n2\n3\n4\n"),
+            Err(MatrixError::Parse {
+                line: 1,
+                column: 1,
+                ..
+            })
+        ));
+        assert!(matches!(
+            read::<RowMajor>("%%MatrixMarket matrix coordinate complex general\n"),
+            Err(MatrixError::Unsupported(_))
+        ));
+        assert!(matches!(
+            read::<RowMajor>("%%MatrixMarket matrix array pattern general\n"),
+            Err(MatrixError::Parse {
+                line: 1,
+                column: 4,
+                ..
+            })
+        ));
+        assert!(matches!(
+            read::<RowMajor>("%%MatrixMarket matrix array real symmetric\n2 3\n"),
+            Err(MatrixError::NotSquare { rows: 2, cols: 3 })
+        ));
+        assert!(matches!(
+            read::<RowMajor>(&format!("{}0 2 0\n", banner)),
+            Err(MatrixError::ZeroDimension { rows: 0, cols: 2 })
+        ));
+        assert!(matches!(
+            read::<RowMajor>(&format!("{}2 2 2\n1 1 1\n% note\n2 3 1\n", banner)),
+            Err(MatrixError::Parse { line: 5, column: 2, ref text, .. }) if text == "3"
+        ));
+        assert!(matches!(
+            read::<RowMajor>(&format!("{}2 2 2\n1 1 1\n2\n", banner)),
+            Err(MatrixError::RaggedRow {
+                line: 4,
+                expected: 3,
+                found: 1
+            })
+        ));
+        assert!(matches!(
+            read::<RowMajor>(&format!("{}2 2 3\n1 1 1\n2 2 x\n", banner)),
+            Err(MatrixError::Parse {
+                line: 4,
+                column: 3,
+                ..
+            })
+        ));
+
+        let skew = "%%MatrixMarket matrix coordinate real skew-symmetric\n2 2 2\n2 1 4\n2 2 1\n";
+        assert!(matches!(
+            read::<RowMajor>(skew),
+            Err(MatrixError::Parse {
+                line: 4,
+                column: 1,
+                ..
+            })
+        ));
+
+        let err = read::<RowMajor>(&format!("{}2 2 3\n1 1 1\n", banner))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("expected 3 entries, found 1"));
+        let err = read::<RowMajor>(&format!("{}2 2 1\n1 1 1\n2 2 1\n", banner))
+            .err()
+            .unwrap();
+        assert!(matches!(err, MatrixError::Parse { line: 4, .. }));
+    }
+}
@@ -0,0 +1,487 @@
+//! Reading and writing matrices in NumPy's `.npy` format, and bundles of them in `.npz`
+//! archives.
+//!
+//! A `.npy` file is a magic string, a version, and a header holding a Python dict literal such
+//! as `{'descr': '<f8', 'fortran_order': False, 'shape': (3, 4), }`, followed by the raw
+//! elements. `fortran_order: True` is column-major storage and `False` is row-major, so
+//! matrices are read and written without reordering when the orders agree.
+
+mod npz;
+
+pub use npz::{NpzReader, NpzWriter};
+
+use crate::transpose::from_storage;
+use crate::{checked_len, is_col_major, ColMajor, Matrix, MatrixError, Order, RowMajor};
+use std::io::{self, Read, Write};
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+/// Element types that can be stored in `.npy` files: `bool`, the fixed-size integers, `f32`
+/// and `f64`.
+pub trait NpyElement: Copy + Default {
+    /// The dtype kind: `b'b'` for booleans, `b'i'` and `b'u'` for signed and unsigned
+    /// integers, `b'f'` for floats.
+    const KIND: u8;
+    const SIZE: usize;
+
+    /// Decodes one element from `SIZE` bytes.
+    fn from_bytes(bytes: &[u8], little_endian: bool) -> Self;
+
+    /// Appends the little-endian encoding of the element to `out`.
+    fn extend_le(self, out: &mut Vec<u8>);
+}
+
+macro_rules! npy_element {
+    ($($ty:ty => $kind:literal),* $(,)?) => {
+        $(
+            impl NpyElement for $ty {
+                const KIND: u8 = $kind;
+                const SIZE: usize = std::mem::size_of::<$ty>();
+
+                fn from_bytes(bytes: &[u8], little_endian: bool) -> Self {
+                    let bytes = bytes.try_into().unwrap();
+                    if little_endian {
+                        <$ty>::from_le_bytes(bytes)
+                    } else {
+                        <$ty>::from_be_bytes(bytes)
+                    }
+                }
+
+                fn extend_le(self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+npy_element! {
+    i8 => b'i', i16 => b'i', i32 => b'i', i64 => b'i',
+    u8 => b'u', u16 => b'u', u32 => b'u', u64 => b'u',
+    f32 => b'f', f64 => b'f',
+}
+
+impl NpyElement for bool {
+    const KIND: u8 = b'b';
+    const SIZE: usize = 1;
+
+    fn from_bytes(bytes: &[u8], _little_endian: bool) -> Self {
+        bytes[0] != 0
+    }
+
+    fn extend_le(self, out: &mut Vec<u8>) {
+        out.push(u8::from(self));
+    }
+}
+
+/// The fields of a `.npy` header.
+struct Header {
+    descr: String,
+    fortran_order: bool,
+    shape: Vec<usize>,
+}
+
+impl<T: NpyElement + Send + Sync, O: Order> Matrix<T, O> {
+    /// Reads a two-dimensional array from a `.npy` file. The dtype must match `T` in kind and
+    /// size, in either byte order. Data stored in the other order is transposed on the way
+    /// in.
+    pub fn read_npy<R: Read>(mut reader: R) -> Result<Self, MatrixError> {
+        let header = read_header(&mut reader)?;
+
+        let (kind, size, little_endian) = parse_descr(&header.descr)?;
+        if kind != T::KIND || size != T::SIZE {
+            return Err(MatrixError::Unsupported(format!(
+                "reading dtype '{}' into a matrix of {}",
+                header.descr,
+                std::any::type_name::<T>()
+            )));
+        }
+        let &[num_rows, num_cols] = &header.shape[..] else {
+            return Err(MatrixError::Unsupported(format!(
+                ".npy array of rank {} with shape {:?}, only 2-dimensional arrays are read",
+                header.shape.len(),
+                header.shape
+            )));
+        };
+        let len = checked_len::<T>(num_rows, num_cols)?;
+
+        // `len` comes from the header, so the buffer grows as data arrives rather than being
+        // reserved up front.
+        let mut data = Vec::with_capacity(len.min(8192));
+        let mut buf = vec![0; T::SIZE * 8192];
+        while data.len() < len {
+            let count = (len - data.len()).min(8192);
+            let bytes = &mut buf[..count * T::SIZE];
+            reader.read_exact(bytes)?;
+            data.extend(
+                bytes
+                    .chunks_exact(T::SIZE)
+                    .map(|b| T::from_bytes(b, little_endian)),
+            );
+        }
+
+        let dims = (num_rows, num_cols);
+        Ok(if header.fortran_order {
+            from_storage::<T, O, ColMajor>(dims, data)
+        } else {
+            from_storage::<T, O, RowMajor>(dims, data)
+        })
+    }
+
+    /// Writes the matrix as a little-endian `.npy` file, with `fortran_order` set for
+    /// column-major storage.
+    pub fn write_npy<W: Write>(&self, mut writer: W) -> Result<(), MatrixError> {
+        let fortran_order = is_col_major::<O>();
+        let byte_order = if T::SIZE == 1 { '|' } else { '<' };
+        let dict = format!(
+            "{{'descr': '{}{}{}', 'fortran_order': {}, 'shape': ({}, {}), }}",
+            byte_order,
+            T::KIND as char,
+            T::SIZE,
+            if fortran_order { "True" } else { "False" },
+            self.num_rows,
+            self.num_cols
+        );
+
+        // The header is padded with spaces and a newline so that the data starts at a
+        // multiple of 64 bytes. Two dimensions always fit in a version 1.0 header.
+        let unpadded = MAGIC.len() + 2 + 2 + dict.len() + 1;
+        let padding = unpadded.next_multiple_of(64) - unpadded;
+        let header_len = dict.len() + padding + 1;
+        let mut out = Vec::with_capacity(unpadded + padding);
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&[1, 0]);
+        out.extend_from_slice(&(header_len as u16).to_le_bytes());
+        out.extend_from_slice(dict.as_bytes());
+        out.resize(out.len() + padding, b' ');
+        out.push(b'\n');
+        writer.write_all(&out)?;
+
+        for chunk in self.data.chunks(8192) {
+            out.clear();
+            chunk.iter().for_each(|x| x.extend_le(&mut out));
+            writer.write_all(&out)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+fn invalid(message: String) -> MatrixError {
+    MatrixError::Io(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+fn read_header<R: Read>(reader: &mut R) -> Result<Header, MatrixError> {
+    let mut preamble = [0; 8];
+    reader.read_exact(&mut preamble)?;
+    if &preamble[..6] != MAGIC {
+        return Err(invalid("not a .npy file".to_string()));
+    }
+
+    let header_len = match preamble[6] {
+        1 => {
+            let mut len = [0; 2];
+            reader.read_exact(&mut len)?;
+            u16::from_le_bytes(len) as usize
+        }
+        2 | 3 => {
+            let mut len = [0; 4];
+            reader.read_exact(&mut len)?;
+            u32::from_le_bytes(len) as usize
+        }
+        major => {
+            return Err(MatrixError::Unsupported(format!(
+                ".npy format version {}.{}",
+                major, preamble[7]
+            )))
+        }
+    };
+    let mut dict = Vec::new();
+    reader.take(header_len as u64).read_to_end(&mut dict)?;
+    if dict.len() < header_len {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    let dict = String::from_utf8(dict)
+        .map_err(|_| invalid("the .npy header is not valid text".to_string()))?;
+
+    parse_dict(&dict).ok_or_else(|| invalid(format!("malformed .npy header {:?}", dict.trim())))
+}
+
+/// Parses the header dict. Keys may come in any order, and other keys are ignored.
+fn parse_dict(dict: &str) -> Option<Header> {
+    let mut rest = dict.trim().strip_prefix('{')?.trim_start();
+    let (mut descr, mut fortran_order, mut shape) = (None, None, None);
+
+    while !rest.starts_with('}') {
+        let key;
+        (key, rest) = parse_str(rest)?;
+        rest = rest.trim_start().strip_prefix(':')?.trim_start();
+        match key {
+            "descr" => {
+                // Structured dtypes are lists, kept whole so that they are reported as
+                // unsupported.
+                let (value, tail) = if rest.starts_with('[') {
+                    split_literal(rest)?
+                } else {
+                    parse_str(rest)?
+                };
+                descr = Some(value.to_string());
+                rest = tail;
+            }
+            "fortran_order" => {
+                let value = ["True", "False"]
+                    .into_iter()
+                    .find(|word| rest.starts_with(word))?;
+                fortran_order = Some(value == "True");
+                rest = &rest[value.len()..];
+            }
+            "shape" => {
+                let end = rest.find(')')?;
+                let dims = rest.strip_prefix('(')?[..end - 1]
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|dim| !dim.is_empty())
+                    .map(|dim| dim.trim_end_matches('L').parse().ok())
+                    .collect::<Option<Vec<usize>>>()?;
+                shape = Some(dims);
+                rest = &rest[end + 1..];
+            }
+            _ => rest = split_literal(rest)?.1,
+        }
+        rest = rest.trim_start();
+        rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+    }
+
+    Some(Header {
+        descr: descr?,
+        fortran_order: fortran_order?,
+        shape: shape?,
+    })
+}
+
+/// Splits a quoted Python string literal off the front of `text`.
+fn parse_str(text: &str) -> Option<(&str, &str)> {
+    let quote = text.chars().next().filter(|&c| c == '\'' || c == '"')?;
+    let end = text[1..].find(quote)? + 1;
+    Some((&text[1..end], &text[end + 1..]))
+}
+
+/// Splits one Python literal off the front of `text`: a string, a bracketed tuple, list or
+/// dict, or a bare word or number, which ends at the next `,` or `}`.
+fn split_literal(text: &str) -> Option<(&str, &str)> {
+    let (mut depth, mut quote, mut escaped) = (0, None, false);
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(q) if c == q => {
+                quote = None;
+                if depth == 0 {
+                    return Some(text.split_at(i + 1));
+                }
+            }
+            Some(_) => {}
+            None => match c {
+                '\'' | '"' => quote = Some(c),
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' if depth > 0 => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(text.split_at(i + 1));
+                    }
+                }
+                ',' | '}' if depth == 0 => {
+                    return (i > 0).then(|| (text[..i].trim_end(), &text[i..]));
+                }
+                ')' | ']' => return None,
+                _ => {}
+            },
+        }
+    }
+    None
+}
+
+/// Splits a dtype such as `<f8` into its kind, its size in bytes and whether it is
+/// little-endian.
+fn parse_descr(descr: &str) -> Result<(u8, usize, bool), MatrixError> {
+    let unsupported = || MatrixError::Unsupported(format!("dtype '{}'", descr));
+    let bytes = descr.as_bytes();
+    let (&order, rest) = bytes.split_first().ok_or_else(unsupported)?;
+    let little_endian = match order {
+        b'<' | b'|' => true,
+        b'>' => false,
+        b'=' => cfg!(target_endian = "little"),
+        _ => return Err(unsupported()),
+    };
+    let (&kind, size) = rest.split_first().ok_or_else(unsupported)?;
+    let size: usize = std::str::from_utf8(size)
+        .ok()
+        .and_then(|size| size.parse().ok())
+        .ok_or_else(unsupported)?;
+    match (kind, size) {
+        (b'b', 1) | (b'i' | b'u', 1 | 2 | 4 | 8) | (b'f', 4 | 8) => Ok((kind, size, little_endian)),
+        _ => Err(unsupported()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a `.npy` file the way NumPy does, with a version 1.0 header.
+    fn npy(dict: &str, data: &[u8]) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.extend_from_slice(&[1, 0]);
+        let padded = format!("{:<width$}\n", dict, width = 117);
+        out.extend_from_slice(&(padded.len() as u16).to_le_bytes());
+        out.extend_from_slice(padded.as_bytes());
+        out.extend_from_slice(data);
+        out
+    }
+
+    #[test]
+    fn read_both_orders() {
+        let values: Vec<u8> = [1.0f64, 2.0, 3.0, 4.0, 5.0, 6.0]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+
+        let c = npy(
+            "{'descr': '<f8', 'fortran_order': False, 'shape': (2, 3), }",
+            &values,
+        );
+        let m: Matrix<f64, RowMajor> = Matrix::read_npy(&c[..]).unwrap();
+        assert_eq!(m.data, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let t: Matrix<f64, ColMajor> = Matrix::read_npy(&c[..]).unwrap();
+        assert_eq!(t[(0, 1)], 2.0);
+        assert_eq!(t.data, vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+
+        let f = npy(
+            "{'fortran_order': True, 'shape': (2, 3), 'descr': '<f8'}",
+            &values,
+        );
+        let m: Matrix<f64, ColMajor> = Matrix::read_npy(&f[..]).unwrap();
+        assert_eq!(m.data, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!((m[(1, 0)], m[(0, 1)]), (2.0, 3.0));
+
+        let big: Vec<u8> = [7i32, -8].iter().flat_map(|x| x.to_be_bytes()).collect();
+        let b = npy(
+            "{'descr': '>i4', 'fortran_order': False, 'shape': (1L, 2L), }",
+            &big,
+        );
+        let m: Matrix<i32, RowMajor> = Matrix::read_npy(&b[..]).unwrap();
+        assert_eq!(m.data, vec![7, -8]);
+    }
+
+    #[test]
+    fn write_and_read_back() {
+        let m: Matrix<f32, ColMajor> = Matrix::from_csv_str("1.5,2\n-3,4\n5,6\n").unwrap();
+        let mut out = Vec::new();
+        m.write_npy(&mut out).unwrap();
+        let text = String::from_utf8_lossy(&out[10..128]).into_owned();
+        assert!(text.starts_with("{'descr': '<f4', 'fortran_order': True, 'shape': (3, 2), }"));
+        assert!(text.ends_with(" \n"));
+        assert_eq!(out.len(), 128 + 6 * 4);
+
+        let back: Matrix<f32, ColMajor> = Matrix::read_npy(&out[..]).unwrap();
+        assert_eq!(back.data, m.data);
+        let row_major: Matrix<f32, RowMajor> = Matrix::read_npy(&out[..]).unwrap();
+        assert_eq!(row_major.data, vec![1.5, 2.0, -3.0, 4.0, 5.0, 6.0]);
+
+        let flags: Matrix<bool, RowMajor> = Matrix::read_npy(
+            &npy(
+                "{'descr': '|b1', 'fortran_order': False, 'shape': (1, 3), }",
+                &[1, 0, 1],
+            )[..],
+        )
+        .unwrap();
+        let mut out = Vec::new();
+        flags.write_npy(&mut out).unwrap();
+        assert!(String::from_utf8_lossy(&out).contains("'descr': '|b1', 'fortran_order': False"));
+        let back: Matrix<bool, RowMajor> = Matrix::read_npy(&out[..]).unwrap();
+        assert_eq!(back.data, vec![true, false, true]);
+    }
+
+    #[test]
+    fn unknown_keys_are_skipped() {
+        let values: Vec<u8> = [1i64, 2].iter().flat_map(|x| x.to_le_bytes()).collect();
+        let m: Matrix<i64, RowMajor> = Matrix::read_npy(
+            &npy(
+                "{'descr': '<i8', 'note': 'a, b}', 'strides': (16, 8), 'meta': {'k': [1, (2,)]}, \
+                 'version': 2, 'fortran_order': False, 'shape': (1, 2), 'flag': None}",
+                &values,
+            )[..],
+        )
+        .unwrap();
+        assert_eq!(m.data, vec![1, 2]);
+
+        let err = Matrix::<i64, RowMajor>::read_npy(
+            &npy(
+                "{'descr': '<i8', 'meta': (1, 2, 'fortran_order': False, 'shape': (1, 2)}",
+                &values,
+            )[..],
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(err, MatrixError::Io(ref e) if e.kind() == io::ErrorKind::InvalidData));
+    }
+
+    #[test]
+    fn errors() {
+        let data = [0u8; 48];
+        let read = |dict: &str| Matrix::<f64, RowMajor>::read_npy(&npy(dict, &data)[..]);
+        let unsupported = |result: Result<Matrix<f64, RowMajor>, MatrixError>, what: &str| matches!(result, Err(MatrixError::Unsupported(ref msg)) if msg.contains(what));
+
+        assert!(unsupported(
+            read("{'descr': '<f4', 'fortran_order': False, 'shape': (2, 3), }"),
+            "reading dtype '<f4' into a matrix of f64"
+        ));
+        assert!(unsupported(
+            read("{'descr': '<c16', 'fortran_order': False, 'shape': (2, 3), }"),
+            "dtype '<c16'"
+        ));
+        assert!(unsupported(
+            read("{'descr': '<f8', 'fortran_order': False, 'shape': (2, 1, 3), }"),
+            "rank 3"
+        ));
+        assert!(unsupported(
+            read("{'descr': '<f8', 'fortran_order': False, 'shape': (6,), }"),
+            "rank 1"
+        ));
+        assert!(unsupported(
+            read("{'descr': [('x', '<f8'), ('y', '<i4', (2,))], 'fortran_order': False, 'shape': (2, 3), }"),
+            "dtype '[('x', '<f8'), ('y', '<i4', (2,))]'"
+        ));
+        assert!(matches!(
+            read("{'descr': '<f8', 'fortran_order': False, 'shape': (0, 3), }"),
+            Err(MatrixError::ZeroDimension { rows: 0, cols: 3 })
+        ));
+
+        let truncated = npy(
+            "{'descr': '<f8', 'fortran_order': False, 'shape': (2, 4), }",
+            &data,
+        );
+        let err = Matrix::<f64, RowMajor>::read_npy(&truncated[..])
+            .err()
+            .unwrap();
+        assert!(matches!(err, MatrixError::Io(ref e) if e.kind() == io::ErrorKind::UnexpectedEof));
+
+        let huge = npy(
+            "{'descr': '<f8', 'fortran_order': False, 'shape': (1000000000000, 1000), }",
+            &data,
+        );
+        let err = Matrix::<f64, RowMajor>::read_npy(&huge[..]).err().unwrap();
+        assert!(matches!(err, MatrixError::Io(ref e) if e.kind() == io::ErrorKind::UnexpectedEof));
+        let mut long_header = MAGIC.to_vec();
+        long_header.extend_from_slice(&[2, 0, 0xff, 0xff, 0xff, 0xff, b'{']);
+        let err = Matrix::<f64, RowMajor>::read_npy(&long_header[..])
+            .err()
+            .unwrap();
+        assert!(matches!(err, MatrixError::Io(ref e) if e.kind() == io::ErrorKind::UnexpectedEof));
+
+        let err = Matrix::<f64, RowMajor>::read_npy(&b"PK\x03\x04 not npy"[..])
+            .err()
+            .unwrap();
+        assert!(matches!(err, MatrixError::Io(ref e) if e.kind() == io::ErrorKind::InvalidData));
+    }
+}
@@ -0,0 +1,402 @@
+//! Uncompressed zip archives of `.npy` files, as written by `numpy.savez`.
+//!
+//! Only what `.npz` files need is supported: stored (uncompressed) entries, with zip64 sizes
+//! and offsets when reading. Compressed archives from `numpy.savez_compressed` are rejected
+//! with [`MatrixError::Unsupported`].
+
+use super::NpyElement;
+use crate::crc32::{Crc32Reader, Crc32Writer};
+use crate::{Matrix, MatrixError, Order};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIR: u32 = 0x0605_4b50;
+const ZIP64_END_OF_CENTRAL_DIR: u32 = 0x0606_4b50;
+const ZIP64_LOCATOR: u32 = 0x0706_4b50;
+const ZIP64_EXTRA: u16 = 0x0001;
+
+/// Version 2.0 of the zip format, the first with stored entries in directories.
+const VERSION: u16 = 20;
+/// 1980-01-01, the earliest date the format can hold.
+const DOS_DATE: u16 = 0x21;
+
+/// A file in the archive, as described by the central directory.
+struct Entry {
+    name: String,
+    flags: u16,
+    method: u16,
+    crc: u32,
+    size: u64,
+    offset: u64,
+}
+
+/// Writes several named matrices into a single uncompressed `.npz` archive.
+///
+/// Each matrix is stored as `<name>.npy`, so `numpy.load` returns them under their names.
+/// Nothing is seeked, so any writer will do. The archive is only valid once
+/// [`NpzWriter::finish`] has written the central directory.
+pub struct NpzWriter<W: Write> {
+    writer: W,
+    offset: u64,
+    entries: Vec<Entry>,
+}
+
+impl<W: Write> NpzWriter<W> {
+    pub fn new(writer: W) -> Self {
+        NpzWriter {
+            writer,
+            offset: 0,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Adds `matrix` to the archive as `name`. The matrix is encoded twice, once to find the
+    /// checksum and size that go in front of the data.
+    pub fn add<T: NpyElement + Send + Sync, O: Order>(
+        &mut self,
+        name: &str,
+        matrix: &Matrix<T, O>,
+    ) -> Result<(), MatrixError> {
+        if self.entries.len() == u16::MAX as usize {
+            return Err(MatrixError::Unsupported(
+                ".npz archives of more than 65535 matrices".to_string(),
+            ));
+        }
+
+        let mut sink = Crc32Writer::new(io::sink());
+        matrix.write_npy(&mut sink)?;
+        let name = format!("{}.npy", name);
+        let entry = Entry {
+            crc: sink.crc.finish(),
+            size: sink.len,
+            flags: 0,
+            method: 0,
+            offset: self.offset,
+            name,
+        };
+        let end = self.offset + 30 + entry.name.len() as u64 + entry.size;
+        if entry.name.len() > u16::MAX as usize || end > u32::MAX as u64 {
+            return Err(MatrixError::Unsupported(
+                ".npz archives larger than 4 GiB".to_string(),
+            ));
+        }
+
+        let mut header = Vec::with_capacity(30 + entry.name.len());
+        put_u32(&mut header, LOCAL_HEADER);
+        put_u16(&mut header, VERSION);
+        put_entry_fields(&mut header, &entry);
+        put_u16(&mut header, 0);
+        header.extend_from_slice(entry.name.as_bytes());
+        self.writer.write_all(&header)?;
+        matrix.write_npy(&mut self.writer)?;
+
+        self.offset = end;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Writes the central directory and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W, MatrixError> {
+        let mut directory = Vec::new();
+        for entry in &self.entries {
+            put_u32(&mut directory, CENTRAL_HEADER);
+            put_u16(&mut directory, VERSION);
+            put_u16(&mut directory, VERSION);
+            put_entry_fields(&mut directory, entry);
+            // Extra field, comment, disk number and internal and external attributes.
+            directory.extend_from_slice(&[0; 12]);
+            put_u32(&mut directory, entry.offset as u32);
+            directory.extend_from_slice(entry.name.as_bytes());
+        }
+        if self.offset + directory.len() as u64 > u32::MAX as u64 {
+            return Err(MatrixError::Unsupported(
+                ".npz archives larger than 4 GiB".to_string(),
+            ));
+        }
+
+        let count = self.entries.len() as u16;
+        let mut end = Vec::with_capacity(22);
+        put_u32(&mut end, END_OF_CENTRAL_DIR);
+        end.extend_from_slice(&[0; 4]);
+        put_u16(&mut end, count);
+        put_u16(&mut end, count);
+        put_u32(&mut end, directory.len() as u32);
+        put_u32(&mut end, self.offset as u32);
+        put_u16(&mut end, 0);
+
+        self.writer.write_all(&directory)?;
+        self.writer.write_all(&end)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// The fields shared by local and central headers, from the flags to the extra field length
+/// exclusive.
+fn put_entry_fields(out: &mut Vec<u8>, entry: &Entry) {
+    put_u16(out, entry.flags);
+    put_u16(out, entry.method);
+    put_u16(out, 0);
+    put_u16(out, DOS_DATE);
+    put_u32(out, entry.crc);
+    put_u32(out, entry.size as u32);
+    put_u32(out, entry.size as u32);
+    put_u16(out, entry.name.len() as u16);
+}
+
+fn put_u16(out: &mut Vec<u8>, x: u16) {
+    out.extend_from_slice(&x.to_le_bytes());
+}
+
+fn put_u32(out: &mut Vec<u8>, x: u32) {
+    out.extend_from_slice(&x.to_le_bytes());
+}
+
+/// Reads named matrices from an `.npz` archive.
+pub struct NpzReader<R: Read + Seek> {
+    reader: R,
+    entries: Vec<Entry>,
+}
+
+impl<R: Read + Seek> NpzReader<R> {
+    /// Reads the archive's central directory.
+    pub fn new(mut reader: R) -> Result<Self, MatrixError> {
+        let len = reader.seek(SeekFrom::End(0))?;
+        let tail_len = len.min(22 + u16::MAX as u64);
+        let mut tail = vec![0; tail_len as usize];
+        reader.seek(SeekFrom::Start(len - tail_len))?;
+        reader.read_exact(&mut tail)?;
+
+        // The end record is the last 22 bytes unless the archive has a comment.
+        let end = (0..tail.len().saturating_sub(21))
+            .rev()
+            .find(|&i| u32_at(&tail, i) == END_OF_CENTRAL_DIR)
+            .ok_or_else(|| invalid("not a zip archive"))?;
+        let end_pos = len - tail_len + end as u64;
+        let record = &tail[end..];
+        let mut count = u16_at(record, 10) as u64;
+        let mut directory_len = u32_at(record, 12) as u64;
+        let mut directory_pos = u32_at(record, 16) as u64;
+
+        if count == u16::MAX as u64
+            || directory_len == u32::MAX as u64
+            || directory_pos == u32::MAX as u64
+        {
+            let locator = end_pos
+                .checked_sub(20)
+                .ok_or_else(|| invalid("truncated zip64 archive"))?;
+            let mut buf = [0; 56];
+            reader.seek(SeekFrom::Start(locator))?;
+            reader.read_exact(&mut buf[..20])?;
+            if u32_at(&buf, 0) != ZIP64_LOCATOR {
+                return Err(invalid("missing zip64 end of central directory locator"));
+            }
+            reader.seek(SeekFrom::Start(u64_at(&buf, 8)))?;
+            reader.read_exact(&mut buf)?;
+            if u32_at(&buf, 0) != ZIP64_END_OF_CENTRAL_DIR {
+                return Err(invalid("missing zip64 end of central directory"));
+            }
+            count = u64_at(&buf, 32);
+            directory_len = u64_at(&buf, 40);
+            directory_pos = u64_at(&buf, 48);
+        }
+
+        if directory_pos.saturating_add(directory_len) > len {
+            return Err(invalid("the zip central directory is out of range"));
+        }
+        let mut directory = vec![0; directory_len as usize];
+        reader.seek(SeekFrom::Start(directory_pos))?;
+        reader.read_exact(&mut directory)?;
+
+        let mut entries = Vec::new();
+        let mut rest = &directory[..];
+        for _ in 0..count {
+            if rest.len() < 46 || u32_at(rest, 0) != CENTRAL_HEADER {
+                return Err(invalid("corrupt zip central directory"));
+            }
+            let name_len = u16_at(rest, 28) as usize;
+            let extra_len = u16_at(rest, 30) as usize;
+            let comment_len = u16_at(rest, 32) as usize;
+            let record_len = 46 + name_len + extra_len + comment_len;
+            if rest.len() < record_len {
+                return Err(invalid("corrupt zip central directory"));
+            }
+
+            let name = String::from_utf8_lossy(&rest[46..46 + name_len]);
+            let mut entry = Entry {
+                name: name.strip_suffix(".npy").unwrap_or(&name).to_string(),
+                flags: u16_at(rest, 8),
+                method: u16_at(rest, 10),
+                crc: u32_at(rest, 16),
+                size: u32_at(rest, 24) as u64,
+                offset: u32_at(rest, 42) as u64,
+            };
+            let compressed_size = u32_at(rest, 20);
+            read_zip64_extra(
+                &rest[46 + name_len..46 + name_len + extra_len],
+                &mut entry,
+                compressed_size,
+            );
+            entries.push(entry);
+            rest = &rest[record_len..];
+        }
+
+        Ok(NpzReader { reader, entries })
+    }
+
+    /// Returns the names of the arrays in the archive, without the `.npy` extension.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|entry| entry.name.as_str())
+    }
+
+    /// Reads the array called `name`, as [`Matrix::read_npy`] would. Fails with a `NotFound`
+    /// I/O error if there is no such array, and with an `InvalidData` one if its checksum
+    /// does not match.
+    pub fn read<T: NpyElement + Send + Sync, O: Order>(
+        &mut self,
+        name: &str,
+    ) -> Result<Matrix<T, O>, MatrixError> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| {
+                MatrixError::Io(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("no array named '{}' in the archive", name),
+                ))
+            })?;
+        if entry.flags & 1 != 0 {
+            return Err(MatrixError::Unsupported(format!(
+                "encrypted .npz entry '{}'",
+                name
+            )));
+        }
+        if entry.method != 0 {
+            return Err(MatrixError::Unsupported(format!(
+                "compressed .npz entry '{}' (zip method {}), save with numpy.savez rather \
+                 than numpy.savez_compressed",
+                name, entry.method
+            )));
+        }
+
+        let mut header = [0; 30];
+        self.reader.seek(SeekFrom::Start(entry.offset))?;
+        self.reader.read_exact(&mut header)?;
+        if u32_at(&header, 0) != LOCAL_HEADER {
+            return Err(invalid("corrupt zip local header"));
+        }
+        let skip = u16_at(&header, 26) as i64 + u16_at(&header, 28) as i64;
+        self.reader.seek(SeekFrom::Current(skip))?;
+
+        let mut data = Crc32Reader::new((&mut self.reader).take(entry.size));
+        let matrix = Matrix::read_npy(&mut data)?;
+        io::copy(&mut data, &mut io::sink())?;
+        if data.crc.finish() != entry.crc {
+            return Err(invalid("checksum mismatch in .npz entry"));
+        }
+        Ok(matrix)
+    }
+}
+
+/// Replaces the sizes and offset that did not fit in 32 bits with the ones in the zip64 extra
+/// field, which holds them in the order of the central header, skipping any that did fit.
+fn read_zip64_extra(mut extra: &[u8], entry: &mut Entry, compressed_size: u32) {
+    while extra.len() >= 4 {
+        let id = u16_at(extra, 0);
+        let len = (u16_at(extra, 2) as usize).min(extra.len() - 4);
+        if id == ZIP64_EXTRA {
+            let mut fields = extra[4..4 + len]
+                .chunks_exact(8)
+                .map(|field| u64_at(field, 0));
+            if entry.size == u32::MAX as u64 {
+                entry.size = fields.next().unwrap_or(entry.size);
+            }
+            if compressed_size == u32::MAX {
+                fields.next();
+            }
+            if entry.offset == u32::MAX as u64 {
+                entry.offset = fields.next().unwrap_or(entry.offset);
+            }
+        }
+        extra = &extra[4 + len..];
+    }
+}
+
+fn invalid(message: &str) -> MatrixError {
+    MatrixError::Io(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+fn u16_at(bytes: &[u8], i: usize) -> u16 {
+    u16::from_le_bytes(bytes[i..i + 2].try_into().unwrap())
+}
+
+fn u32_at(bytes: &[u8], i: usize) -> u32 {
+    u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap())
+}
+
+fn u64_at(bytes: &[u8], i: usize) -> u64 {
+    u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColMajor, RowMajor};
+    use std::io::Cursor;
+
+    fn archive() -> Vec<u8> {
+        let a: Matrix<f64, RowMajor> = Matrix::from_csv_str("1,2,3\n4,5,6\n").unwrap();
+        let b: Matrix<i64, ColMajor> = Matrix::from_csv_str("7\n-8\n").unwrap();
+        let mut npz = NpzWriter::new(Vec::new());
+        npz.add("a", &a).unwrap();
+        npz.add("weights", &b).unwrap();
+        npz.finish().unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut npz = NpzReader::new(Cursor::new(archive())).unwrap();
+        assert_eq!(npz.names().collect::<Vec<_>>(), ["a", "weights"]);
+
+        let b: Matrix<i64, RowMajor> = npz.read("weights").unwrap();
+        assert_eq!((b.num_rows, b.num_cols), (2, 1));
+        assert_eq!(b.data, vec![7, -8]);
+        let a: Matrix<f64, ColMajor> = npz.read("a").unwrap();
+        assert_eq!(a.data, vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+
+        let err = npz.read::<f64, RowMajor>("b").err().unwrap();
+        assert!(matches!(err, MatrixError::Io(ref e) if e.kind() == io::ErrorKind::NotFound));
+        assert!(matches!(
+            npz.read::<f32, RowMajor>("a"),
+            Err(MatrixError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_compressed_and_corrupt_entries() {
+        let bytes = archive();
+        let directory = (0..bytes.len())
+            .find(|&i| u32_at(&bytes, i) == CENTRAL_HEADER)
+            .unwrap();
+
+        let mut deflated = bytes.clone();
+        deflated[directory + 10] = 8;
+        let mut npz = NpzReader::new(Cursor::new(deflated)).unwrap();
+        assert!(matches!(
+            npz.read::<f64, RowMajor>("a"),
+            Err(MatrixError::Unsupported(ref msg)) if msg.contains("savez_compressed")
+        ));
+
+        let mut corrupt = bytes.clone();
+        let last = directory - 1;
+        corrupt[last] ^= 0xff;
+        let mut npz = NpzReader::new(Cursor::new(corrupt)).unwrap();
+        assert!(npz.read::<f64, RowMajor>("a").is_ok());
+        let err = npz.read::<i64, RowMajor>("weights").err().unwrap();
+        assert!(matches!(err, MatrixError::Io(ref e) if e.kind() == io::ErrorKind::InvalidData));
+
+        assert!(NpzReader::new(Cursor::new(&bytes[..directory])).is_err());
+    }
+}
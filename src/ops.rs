@@ -0,0 +1,390 @@
+//! Arithmetic on matrices.
+//!
+//! Operands may use different storage orders; the result always has the order of the
+//! left-hand operand. The `checked_*` methods report mismatched dimensions as errors, while
+//! the operators panic on them.
+
+use crate::parallel;
+use crate::simd;
+use crate::transpose::storage_in;
+use crate::{Field, Matrix, MatrixError, Order, Scalar};
+use std::marker::PhantomData;
+use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+impl<T: Scalar + Send + Sync, O: Order> Matrix<T, O> {
+    fn check_same_dims<O2: Order>(&self, rhs: &Matrix<T, O2>) -> Result<(), MatrixError> {
+        if (self.num_rows, self.num_cols) != (rhs.num_rows, rhs.num_cols) {
+            return Err(MatrixError::DimensionMismatch {
+                expected: (self.num_rows, self.num_cols),
+                found: (rhs.num_rows, rhs.num_cols),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn zip_map<O2: Order>(
+        &self,
+        rhs: &Matrix<T, O2>,
+        f: impl Fn(&mut [T], &[T]) + Send + Sync,
+    ) -> Result<Self, MatrixError> {
+        let mut out = self.clone();
+        out.zip_apply(rhs, f)?;
+        Ok(out)
+    }
+
+    /// Runs the slice kernel `f` over matching pieces of `self` and `rhs`, splitting the work
+    /// into row or column panels when the `parallel` feature is enabled.
+    fn zip_apply<O2: Order>(
+        &mut self,
+        rhs: &Matrix<T, O2>,
+        f: impl Fn(&mut [T], &[T]) + Send + Sync,
+    ) -> Result<(), MatrixError> {
+        self.check_same_dims(rhs)?;
+
+        let rhs = storage_in::<T, O, O2>(rhs);
+        let rhs = &rhs[..];
+        let (_, run) = O::storage_dims((self.num_rows, self.num_cols));
+        parallel::for_each_chunk(&mut self.data, run, 1, |offset, chunk| {
+            f(chunk, &rhs[offset..offset + chunk.len()])
+        });
+
+        Ok(())
+    }
+
+    fn map(&self, f: impl Fn(&mut [T]) + Send + Sync) -> Self {
+        let mut out = self.clone();
+        out.apply(f);
+        out
+    }
+
+    fn apply(&mut self, f: impl Fn(&mut [T]) + Send + Sync) {
+        let (_, run) = O::storage_dims((self.num_rows, self.num_cols));
+        parallel::for_each_chunk(&mut self.data, run, 1, |_, chunk| f(chunk));
+    }
+
+    pub fn checked_add<O2: Order>(&self, rhs: &Matrix<T, O2>) -> Result<Self, MatrixError>
+    where
+        T: Add<Output = T>,
+    {
+        self.zip_map(rhs, simd::add_assign)
+    }
+
+    pub fn checked_sub<O2: Order>(&self, rhs: &Matrix<T, O2>) -> Result<Self, MatrixError>
+    where
+        T: Sub<Output = T>,
+    {
+        self.zip_map(rhs, simd::sub_assign)
+    }
+
+    /// Computes the matrix product `self * rhs`.
+    pub fn checked_mul<O2: Order>(&self, rhs: &Matrix<T, O2>) -> Result<Self, MatrixError>
+    where
+        T: Field,
+    {
+        if self.num_cols != rhs.num_rows {
+            return Err(MatrixError::DimensionMismatch {
+                expected: (self.num_cols, rhs.num_cols),
+                found: (rhs.num_rows, rhs.num_cols),
+            });
+        }
+
+        let mut out = Self {
+            num_rows: self.num_rows,
+            num_cols: rhs.num_cols,
+            data: vec![T::zero(); self.num_rows * rhs.num_cols],
+            _order: PhantomData,
+        };
+        Matrix::gemm(T::one(), self, rhs, T::zero(), &mut out)?;
+
+        Ok(out)
+    }
+}
+
+macro_rules! impl_elementwise_op {
+    ($Op:ident, $op:ident, $OpAssign:ident, $op_assign:ident, $checked:ident) => {
+        impl<T, O: Order, O2: Order> $Op<&Matrix<T, O2>> for &Matrix<T, O>
+        where
+            T: Scalar + Send + Sync + $Op<Output = T>,
+        {
+            type Output = Matrix<T, O>;
+
+            fn $op(self, rhs: &Matrix<T, O2>) -> Matrix<T, O> {
+                self.$checked(rhs).unwrap_or_else(|e| panic!("{}", e))
+            }
+        }
+
+        impl<T, O: Order, O2: Order> $Op<Matrix<T, O2>> for &Matrix<T, O>
+        where
+            T: Scalar + Send + Sync + $Op<Output = T>,
+        {
+            type Output = Matrix<T, O>;
+
+            fn $op(self, rhs: Matrix<T, O2>) -> Matrix<T, O> {
+                self.$op(&rhs)
+            }
+        }
+
+        impl<T, O: Order, O2: Order> $Op<&Matrix<T, O2>> for Matrix<T, O>
+        where
+            T: Scalar + Send + Sync + $Op<Output = T>,
+        {
+            type Output = Matrix<T, O>;
+
+            fn $op(mut self, rhs: &Matrix<T, O2>) -> Matrix<T, O> {
+                self.zip_apply(rhs, simd::$op_assign)
+                    .unwrap_or_else(|e| panic!("{}", e));
+                self
+            }
+        }
+
+        impl<T, O: Order, O2: Order> $Op<Matrix<T, O2>> for Matrix<T, O>
+        where
+            T: Scalar + Send + Sync + $Op<Output = T>,
+        {
+            type Output = Matrix<T, O>;
+
+            fn $op(self, rhs: Matrix<T, O2>) -> Matrix<T, O> {
+                self.$op(&rhs)
+            }
+        }
+
+        impl<T, O: Order, O2: Order> $OpAssign<&Matrix<T, O2>> for Matrix<T, O>
+        where
+            T: Scalar + Send + Sync + $Op<Output = T>,
+        {
+            fn $op_assign(&mut self, rhs: &Matrix<T, O2>) {
+                self.zip_apply(rhs, simd::$op_assign)
+                    .unwrap_or_else(|e| panic!("{}", e));
+            }
+        }
+
+        impl<T, O: Order, O2: Order> $OpAssign<Matrix<T, O2>> for Matrix<T, O>
+        where
+            T: Scalar + Send + Sync + $Op<Output = T>,
+        {
+            fn $op_assign(&mut self, rhs: Matrix<T, O2>) {
+                self.$op_assign(&rhs);
+            }
+        }
+    };
+}
+
+impl_elementwise_op!(Add, add, AddAssign, add_assign, checked_add);
+impl_elementwise_op!(Sub, sub, SubAssign, sub_assign, checked_sub);
+
+impl<T: Scalar + Send + Sync + Neg<Output = T>, O: Order> Neg for &Matrix<T, O> {
+    type Output = Matrix<T, O>;
+
+    fn neg(self) -> Matrix<T, O> {
+        self.map(|chunk| chunk.iter_mut().for_each(|a| *a = -*a))
+    }
+}
+
+impl<T: Scalar + Send + Sync + Neg<Output = T>, O: Order> Neg for Matrix<T, O> {
+    type Output = Matrix<T, O>;
+
+    fn neg(mut self) -> Matrix<T, O> {
+        self.apply(|chunk| chunk.iter_mut().for_each(|a| *a = -*a));
+        self
+    }
+}
+
+impl<T, O: Order, O2: Order> Mul<&Matrix<T, O2>> for &Matrix<T, O>
+where
+    T: Field,
+{
+    type Output = Matrix<T, O>;
+
+    fn mul(self, rhs: &Matrix<T, O2>) -> Matrix<T, O> {
+        self.checked_mul(rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+impl<T, O: Order, O2: Order> Mul<Matrix<T, O2>> for &Matrix<T, O>
+where
+    T: Field,
+{
+    type Output = Matrix<T, O>;
+
+    fn mul(self, rhs: Matrix<T, O2>) -> Matrix<T, O> {
+        self * &rhs
+    }
+}
+
+impl<T, O: Order, O2: Order> Mul<&Matrix<T, O2>> for Matrix<T, O>
+where
+    T: Field,
+{
+    type Output = Matrix<T, O>;
+
+    fn mul(self, rhs: &Matrix<T, O2>) -> Matrix<T, O> {
+        &self * rhs
+    }
+}
+
+impl<T, O: Order, O2: Order> Mul<Matrix<T, O2>> for Matrix<T, O>
+where
+    T: Field,
+{
+    type Output = Matrix<T, O>;
+
+    fn mul(self, rhs: Matrix<T, O2>) -> Matrix<T, O> {
+        &self * &rhs
+    }
+}
+
+impl<T, O: Order, O2: Order> MulAssign<&Matrix<T, O2>> for Matrix<T, O>
+where
+    T: Field,
+{
+    fn mul_assign(&mut self, rhs: &Matrix<T, O2>) {
+        *self = &*self * rhs;
+    }
+}
+
+impl<T, O: Order, O2: Order> MulAssign<Matrix<T, O2>> for Matrix<T, O>
+where
+    T: Field,
+{
+    fn mul_assign(&mut self, rhs: Matrix<T, O2>) {
+        *self *= &rhs;
+    }
+}
+
+impl<T: Scalar + Send + Sync + Mul<Output = T>, O: Order> Mul<T> for &Matrix<T, O> {
+    type Output = Matrix<T, O>;
+
+    fn mul(self, rhs: T) -> Matrix<T, O> {
+        self.map(|chunk| simd::scale(chunk, rhs))
+    }
+}
+
+impl<T: Scalar + Send + Sync + Mul<Output = T>, O: Order> Mul<T> for Matrix<T, O> {
+    type Output = Matrix<T, O>;
+
+    fn mul(mut self, rhs: T) -> Matrix<T, O> {
+        self *= rhs;
+        self
+    }
+}
+
+impl<T: Scalar + Send + Sync + Mul<Output = T>, O: Order> MulAssign<T> for Matrix<T, O> {
+    fn mul_assign(&mut self, rhs: T) {
+        self.apply(|chunk| simd::scale(chunk, rhs));
+    }
+}
+
+macro_rules! impl_scalar_lhs_mul {
+    ($($t:ty),*) => {
+        $(
+            impl<O: Order> Mul<&Matrix<$t, O>> for $t {
+                type Output = Matrix<$t, O>;
+
+                fn mul(self, rhs: &Matrix<$t, O>) -> Matrix<$t, O> {
+                    rhs * self
+                }
+            }
+
+            impl<O: Order> Mul<Matrix<$t, O>> for $t {
+                type Output = Matrix<$t, O>;
+
+                fn mul(self, rhs: Matrix<$t, O>) -> Matrix<$t, O> {
+                    rhs * self
+                }
+            }
+        )*
+    };
+}
+
+impl_scalar_lhs_mul!(f32, f64, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+#[cfg(test)]
+mod tests {
+    use crate::{ColMajor, Matrix, MatrixError, RowMajor};
+
+    fn row_major(rows: usize, cols: usize, data: Vec<i64>) -> Matrix<i64, RowMajor> {
+        let mut m = Matrix::new(rows, cols).unwrap();
+        m.data = data;
+        m
+    }
+
+    #[test]
+    fn add_sub_neg() {
+        let a = row_major(2, 2, vec![1, 2, 3, 4]);
+        let b = row_major(2, 2, vec![10, 20, 30, 40]);
+
+        assert_eq!((&a + &b).data, vec![11, 22, 33, 44]);
+        assert_eq!((&b - &a).data, vec![9, 18, 27, 36]);
+        assert_eq!((-&a).data, vec![-1, -2, -3, -4]);
+
+        let mut c = a + b;
+        c -= row_major(2, 2, vec![1, 1, 1, 1]);
+        assert_eq!(c.data, vec![10, 21, 32, 43]);
+    }
+
+    #[test]
+    fn mixed_orders() {
+        let a = row_major(2, 3, vec![1, 2, 3, 4, 5, 6]);
+        let b: Matrix<i64, ColMajor> = a.to_order();
+
+        let sum = &a + &b;
+        assert_eq!(sum.data, vec![2, 4, 6, 8, 10, 12]);
+
+        let sum: Matrix<i64, ColMajor> = &b + &a;
+        assert_eq!(sum.data, vec![2, 8, 4, 10, 6, 12]);
+
+        let product = &a * &b.transpose().unwrap();
+        assert_eq!(product.data, vec![14, 32, 32, 77]);
+
+        let c = row_major(2, 2, vec![1, 2, 3, 4]);
+        let d: Matrix<i64, ColMajor> = c.to_order();
+        assert_eq!((&c - &d).data, vec![0; 4]);
+    }
+
+    #[test]
+    fn products() {
+        let a = row_major(2, 3, vec![1, 2, 3, 4, 5, 6]);
+        let b = row_major(3, 2, vec![7, 8, 9, 10, 11, 12]);
+
+        let mut c = &a * &b;
+        assert_eq!(c.dims().rows, 2);
+        assert_eq!(c.dims().cols, 2);
+        assert_eq!(c.data, vec![58, 64, 139, 154]);
+
+        c *= row_major(2, 2, vec![1, 0, 0, 1]);
+        assert_eq!(c.data, vec![58, 64, 139, 154]);
+
+        assert_eq!((2 * &a).data, vec![2, 4, 6, 8, 10, 12]);
+        assert_eq!((a * 3).data, vec![3, 6, 9, 12, 15, 18]);
+    }
+
+    #[test]
+    fn dimension_mismatch() {
+        let a = row_major(2, 3, vec![1, 2, 3, 4, 5, 6]);
+        let b = row_major(2, 2, vec![1, 2, 3, 4]);
+
+        assert!(matches!(
+            a.checked_add(&b),
+            Err(MatrixError::DimensionMismatch {
+                expected: (2, 3),
+                found: (2, 2)
+            })
+        ));
+        assert!(a.checked_sub(&b).is_err());
+        assert!(matches!(
+            a.checked_mul(&b),
+            Err(MatrixError::DimensionMismatch {
+                expected: (3, 2),
+                found: (2, 2)
+            })
+        ));
+        assert!(b.checked_mul(&a).is_ok());
+    }
+
+    #[test]
+    #[should_panic]
+    fn add_panics_on_mismatch() {
+        let _ = row_major(2, 3, vec![0; 6]) + row_major(3, 2, vec![0; 6]);
+    }
+}
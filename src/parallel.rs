@@ -0,0 +1,170 @@
+//! Work splitting for the `parallel` feature.
+//!
+//! Kernels hand a mutable output buffer to [`for_each_chunk`], which cuts it at panel
+//! boundaries (rows or columns, depending on the storage order) and processes the pieces on
+//! scoped threads. Every output element is written by exactly one thread using the same
+//! arithmetic as the serial path, so results do not depend on the thread count. Without the
+//! feature, the whole buffer is processed on the calling thread.
+//!
+//! The entry points require `Send + Sync` with or without the feature, so that enabling it
+//! never changes which element types the public API accepts.
+
+#[cfg(feature = "parallel")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Minimum amount of work, in element operations, worth handing to a thread.
+#[cfg(feature = "parallel")]
+const MIN_WORK_PER_THREAD: usize = 1 << 16;
+
+#[cfg(feature = "parallel")]
+static NUM_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets the number of threads used by parallel kernels. `0` restores the default, which is
+/// the available parallelism reported by the operating system.
+#[cfg(feature = "parallel")]
+pub fn set_num_threads(num_threads: usize) {
+    NUM_THREADS.store(num_threads, Ordering::Relaxed);
+}
+
+/// Returns the number of threads used by parallel kernels.
+#[cfg(feature = "parallel")]
+pub fn num_threads() -> usize {
+    match NUM_THREADS.load(Ordering::Relaxed) {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+}
+
+/// Splits `data` into chunks whose lengths are multiples of `unit` (except possibly the last)
+/// and calls `f(offset, chunk)` on each, where `offset` is the index of the chunk's first
+/// element in `data`. `work_per_unit` estimates the cost of one unit and decides how many
+/// threads are worth starting.
+#[cfg(feature = "parallel")]
+pub(crate) fn for_each_chunk<T, F>(data: &mut [T], unit: usize, work_per_unit: usize, f: F)
+where
+    T: Send + Sync,
+    F: Fn(usize, &mut [T]) + Send + Sync,
+{
+    let unit = unit.max(1);
+    let units = data.len().div_ceil(unit);
+    let by_work = units.saturating_mul(work_per_unit) / MIN_WORK_PER_THREAD;
+    let threads = num_threads().min(units).min(by_work.max(1));
+
+    if threads <= 1 {
+        f(0, data);
+        return;
+    }
+
+    let chunk_len = units.div_ceil(threads) * unit;
+    std::thread::scope(|scope| {
+        let f = &f;
+        for (i, chunk) in data.chunks_mut(chunk_len).enumerate() {
+            scope.spawn(move || f(i * chunk_len, chunk));
+        }
+    });
+}
+
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn for_each_chunk<T, F>(data: &mut [T], _unit: usize, _work_per_unit: usize, f: F)
+where
+    T: Send + Sync,
+    F: Fn(usize, &mut [T]) + Send + Sync,
+{
+    f(0, data);
+}
+
+/// Returns how many pieces `work` element operations are worth splitting into.
+#[cfg(feature = "parallel")]
+pub(crate) fn task_count(work: usize) -> usize {
+    num_threads().min(work / MIN_WORK_PER_THREAD).max(1)
+}
+
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn task_count(_work: usize) -> usize {
+    1
+}
+
+/// Calls `f` on every task, each on its own scoped thread, and returns the results in the
+/// order of `tasks`.
+#[cfg(feature = "parallel")]
+pub(crate) fn map_tasks<I, R, F>(tasks: Vec<I>, f: F) -> Vec<R>
+where
+    I: Send + Sync,
+    R: Send + Sync,
+    F: Fn(I) -> R + Send + Sync,
+{
+    if tasks.len() <= 1 {
+        return tasks.into_iter().map(f).collect();
+    }
+
+    std::thread::scope(|scope| {
+        let f = &f;
+        let handles: Vec<_> = tasks
+            .into_iter()
+            .map(|task| scope.spawn(move || f(task)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+            .collect()
+    })
+}
+
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn map_tasks<I, R, F>(tasks: Vec<I>, f: F) -> Vec<R>
+where
+    I: Send + Sync,
+    R: Send + Sync,
+    F: Fn(I) -> R + Send + Sync,
+{
+    tasks.into_iter().map(f).collect()
+}
+
+#[cfg(all(test, feature = "parallel"))]
+mod tests {
+    use super::*;
+    use crate::{ColMajor, Matrix, RowMajor};
+
+    fn filled<O: crate::Order>(rows: usize, cols: usize, seed: usize) -> Matrix<f64, O> {
+        let mut m: Matrix<f64, O> = Matrix::new(rows, cols).unwrap();
+        for i in 0..rows {
+            for j in 0..cols {
+                m[(i, j)] = ((i * 31 + j * 17 + seed) % 101) as f64 / 7.0 - 5.0;
+            }
+        }
+        m
+    }
+
+    #[test]
+    fn chunks_cover_data_once() {
+        let mut data = vec![0usize; 1 << 20];
+        for_each_chunk(&mut data, 1000, 1 << 10, |offset, chunk| {
+            for (i, x) in chunk.iter_mut().enumerate() {
+                *x += offset + i;
+            }
+        });
+        assert!(data.iter().enumerate().all(|(i, &x)| x == i));
+    }
+
+    #[test]
+    fn results_do_not_depend_on_thread_count() {
+        let a = filled::<RowMajor>(300, 257, 1);
+        let b = filled::<ColMajor>(257, 190, 2);
+
+        let mut results = Vec::new();
+        for threads in [1, 3, 8] {
+            set_num_threads(threads);
+            let product = &a * &b;
+            let sum = &a + &a.to_order::<ColMajor>();
+            let transposed = b.transpose().unwrap();
+            results.push((product.data, sum.data, transposed.data));
+        }
+        set_num_threads(0);
+
+        assert!(results.windows(2).all(|w| w[0] == w[1]));
+    }
+}
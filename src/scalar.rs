@@ -0,0 +1,41 @@
+//! The layers of element types, from plain storage up to real numbers.
+//!
+//! Each trait is implemented for every type with the listed bounds, so the built-in numbers and
+//! any wrapper type implementing the `num_traits` traits qualify without further impls.
+
+use num_traits::{Float, Num};
+
+/// A matrix element, which only has to be copyable. Operations that may run on several threads
+/// additionally ask for `Send + Sync`.
+pub trait Scalar: Copy + 'static {}
+
+impl<T: Copy + 'static> Scalar for T {}
+
+/// An element with `+`, `-`, `*`, `/`, zero and one, as needed by matrix products. Integers
+/// qualify, with truncating division. Products may run on several threads, so the element must
+/// also be `Send + Sync`.
+pub trait Field: Scalar + Num + Send + Sync {}
+
+impl<T: Scalar + Num + Send + Sync> Field for T {}
+
+/// A real floating-point element, as needed by the decompositions in [`crate::Lu`],
+/// [`crate::Qr`], [`crate::Svd`] and the others.
+pub trait RealField: Field + Float {}
+
+impl<T: Field + Float> RealField for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_field<T: Field>() {}
+    fn is_real_field<T: RealField>() {}
+
+    #[test]
+    fn built_in_numbers() {
+        is_field::<i32>();
+        is_field::<u8>();
+        is_real_field::<f32>();
+        is_real_field::<f64>();
+    }
+}
@@ -0,0 +1,306 @@
+//! `Serialize` and `Deserialize` for [`Matrix`] and [`Dimensions`], behind the `serde` feature.
+//!
+//! A matrix is a struct of `rows`, `cols`, `order` (`"row_major"` or `"col_major"`) and `data`,
+//! the elements in that storage order. Deserializing checks that `data` has `rows * cols`
+//! elements, and transposes it if `order` is not the matrix's own.
+
+use crate::transpose::from_storage;
+use crate::{checked_len, is_col_major, ColMajor, Dimensions, Matrix, Order, RowMajor, Scalar};
+use serde::de::{self, Deserialize, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::fmt;
+use std::marker::PhantomData;
+
+const MATRIX_FIELDS: &[&str] = &["rows", "cols", "order", "data"];
+const DIMENSIONS_FIELDS: &[&str] = &["rows", "cols"];
+const ORDERS: &[&str] = &["row_major", "col_major"];
+
+impl<T: Serialize, O: Order> Serialize for Matrix<T, O> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Matrix", MATRIX_FIELDS.len())?;
+        s.serialize_field("rows", &self.num_rows)?;
+        s.serialize_field("cols", &self.num_cols)?;
+        s.serialize_field("order", ORDERS[usize::from(is_col_major::<O>())])?;
+        s.serialize_field("data", &self.data)?;
+        s.end()
+    }
+}
+
+impl<'de, T, O> Deserialize<'de> for Matrix<T, O>
+where
+    T: Deserialize<'de> + Scalar + Send + Sync,
+    O: Order,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("Matrix", MATRIX_FIELDS, MatrixVisitor(PhantomData))
+    }
+}
+
+struct MatrixVisitor<T, O>(PhantomData<(T, O)>);
+
+impl<'de, T, O> Visitor<'de> for MatrixVisitor<T, O>
+where
+    T: Deserialize<'de> + Scalar + Send + Sync,
+    O: Order,
+{
+    type Value = Matrix<T, O>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a matrix with rows, cols, order and data")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let missing = |i| de::Error::invalid_length(i, &"a matrix of 4 fields");
+        let rows = seq.next_element()?.ok_or_else(|| missing(0))?;
+        let cols = seq.next_element()?.ok_or_else(|| missing(1))?;
+        let order: OrderName = seq.next_element()?.ok_or_else(|| missing(2))?;
+        let data = seq.next_element()?.ok_or_else(|| missing(3))?;
+        build(rows, cols, order.0, data)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let (mut rows, mut cols, mut order, mut data) = (None, None, None, None);
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "rows" => set(&mut rows, "rows", map.next_value()?)?,
+                "cols" => set(&mut cols, "cols", map.next_value()?)?,
+                "order" => set(&mut order, "order", map.next_value::<OrderName>()?.0)?,
+                "data" => set(&mut data, "data", map.next_value()?)?,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        build(
+            rows.ok_or_else(|| de::Error::missing_field("rows"))?,
+            cols.ok_or_else(|| de::Error::missing_field("cols"))?,
+            order.ok_or_else(|| de::Error::missing_field("order"))?,
+            data.ok_or_else(|| de::Error::missing_field("data"))?,
+        )
+    }
+}
+
+fn set<V, E: de::Error>(slot: &mut Option<V>, name: &'static str, value: V) -> Result<(), E> {
+    if slot.replace(value).is_some() {
+        return Err(E::duplicate_field(name));
+    }
+    Ok(())
+}
+
+fn build<T, O, E>(
+    rows: usize,
+    cols: usize,
+    col_major: bool,
+    data: Vec<T>,
+) -> Result<Matrix<T, O>, E>
+where
+    T: Scalar + Send + Sync,
+    O: Order,
+    E: de::Error,
+{
+    let len = checked_len::<T>(rows, cols).map_err(E::custom)?;
+    if data.len() != len {
+        return Err(E::invalid_length(
+            data.len(),
+            &format!("{} elements for a {}x{} matrix", len, rows, cols).as_str(),
+        ));
+    }
+
+    Ok(if col_major {
+        from_storage::<T, O, ColMajor>((rows, cols), data)
+    } else {
+        from_storage::<T, O, RowMajor>((rows, cols), data)
+    })
+}
+
+/// The `order` field, `true` for column-major.
+struct OrderName(bool);
+
+impl<'de> Deserialize<'de> for OrderName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        match ORDERS.iter().position(|&order| order == name) {
+            Some(i) => Ok(OrderName(i == 1)),
+            None => Err(de::Error::unknown_variant(&name, ORDERS)),
+        }
+    }
+}
+
+impl Serialize for Dimensions {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Dimensions", DIMENSIONS_FIELDS.len())?;
+        s.serialize_field("rows", &self.rows)?;
+        s.serialize_field("cols", &self.cols)?;
+        s.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Dimensions {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("Dimensions", DIMENSIONS_FIELDS, DimensionsVisitor)
+    }
+}
+
+struct DimensionsVisitor;
+
+impl<'de> Visitor<'de> for DimensionsVisitor {
+    type Value = Dimensions;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("dimensions with rows and cols")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let missing = |i| de::Error::invalid_length(i, &"dimensions of 2 fields");
+        Ok(Dimensions {
+            rows: seq.next_element()?.ok_or_else(|| missing(0))?,
+            cols: seq.next_element()?.ok_or_else(|| missing(1))?,
+        })
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let (mut rows, mut cols) = (None, None);
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "rows" => set(&mut rows, "rows", map.next_value()?)?,
+                "cols" => set(&mut cols, "cols", map.next_value()?)?,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(Dimensions {
+            rows: rows.ok_or_else(|| de::Error::missing_field("rows"))?,
+            cols: cols.ok_or_else(|| de::Error::missing_field("cols"))?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::value::{Error, MapDeserializer, SeqDeserializer};
+    use serde::de::IntoDeserializer;
+
+    /// A self-describing value, standing in for a format such as JSON.
+    enum Value {
+        Int(u64),
+        Float(f64),
+        Str(&'static str),
+        Seq(Vec<Value>),
+    }
+
+    impl<'de> Deserializer<'de> for Value {
+        type Error = Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self {
+                Value::Int(x) => visitor.visit_u64(x),
+                Value::Float(x) => visitor.visit_f64(x),
+                Value::Str(s) => visitor.visit_str(s),
+                Value::Seq(items) => visitor.visit_seq(SeqDeserializer::new(items.into_iter())),
+            }
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
+            byte_buf option unit unit_struct newtype_struct seq tuple tuple_struct map struct
+            enum identifier ignored_any
+        }
+    }
+
+    impl IntoDeserializer<'_, Error> for Value {
+        type Deserializer = Self;
+
+        fn into_deserializer(self) -> Self {
+            self
+        }
+    }
+
+    fn from_map<T: for<'de> Deserialize<'de>>(
+        fields: Vec<(&'static str, Value)>,
+    ) -> Result<T, Error> {
+        T::deserialize(MapDeserializer::new(fields.into_iter()))
+    }
+
+    fn floats(xs: &[f64]) -> Value {
+        Value::Seq(xs.iter().map(|&x| Value::Float(x)).collect())
+    }
+
+    #[test]
+    fn from_either_order() {
+        let fields = || {
+            vec![
+                ("order", Value::Str("col_major")),
+                ("rows", Value::Int(2)),
+                ("cols", Value::Int(3)),
+                ("comment", Value::Str("ignored")),
+                ("data", floats(&[1.0, 4.0, 2.0, 5.0, 3.0, 6.0])),
+            ]
+        };
+        let m: Matrix<f64, ColMajor> = from_map(fields()).unwrap();
+        assert_eq!(m.data, vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+        let m: Matrix<f64, RowMajor> = from_map(fields()).unwrap();
+        assert_eq!((m.num_rows, m.num_cols), (2, 3));
+        assert_eq!(m.data, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+
+        let dims: Dimensions =
+            from_map(vec![("cols", Value::Int(3)), ("rows", Value::Int(2))]).unwrap();
+        assert_eq!((dims.rows, dims.cols), (2, 3));
+    }
+
+    #[test]
+    fn invalid_input() {
+        let error = |fields| {
+            from_map::<Matrix<f64, RowMajor>>(fields)
+                .err()
+                .unwrap()
+                .to_string()
+        };
+        let base = |data: &[f64], order| {
+            vec![
+                ("rows", Value::Int(2)),
+                ("cols", Value::Int(2)),
+                ("order", Value::Str(order)),
+                ("data", floats(data)),
+            ]
+        };
+
+        assert!(error(base(&[1.0, 2.0, 3.0], "row_major"))
+            .contains("invalid length 3, expected 4 elements for a 2x2 matrix"));
+        assert!(error(base(&[1.0; 4], "diagonal")).contains("unknown variant `diagonal`"));
+
+        let mut fields = base(&[1.0; 4], "row_major");
+        fields.remove(0);
+        assert!(error(fields).contains("missing field `rows`"));
+        let mut fields = base(&[1.0; 4], "row_major");
+        fields.push(("cols", Value::Int(2)));
+        assert!(error(fields).contains("duplicate field `cols`"));
+        let mut fields = base(&[], "row_major");
+        fields[0].1 = Value::Int(0);
+        assert!(error(fields).contains("0x2"));
+    }
+
+    #[test]
+    fn through_csv() {
+        let m: Matrix<i64, ColMajor> = Matrix::from_csv_str("1,2\n3,4\n5,6\n").unwrap();
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_writer(Vec::new());
+        writer.serialize(&m).unwrap();
+        writer.serialize(m.dims()).unwrap();
+        let text = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        assert_eq!(text, "3,2,col_major,1,3,5,2,4,6\n3,2\n");
+
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(text.as_bytes());
+        let mut records = reader.records().map(Result::unwrap);
+        let back: Matrix<i64, RowMajor> = records.next().unwrap().deserialize(None).unwrap();
+        assert_eq!(back.data, vec![1, 2, 3, 4, 5, 6]);
+        let dims: Dimensions = records.next().unwrap().deserialize(None).unwrap();
+        assert_eq!((dims.rows, dims.cols), (3, 2));
+    }
+}
@@ -0,0 +1,393 @@
+//! Vectorized kernels for `f32` and `f64`.
+//!
+//! The generic entry points below check at runtime whether `T` is `f32` or `f64` and, if the
+//! CPU supports it, forward to an AVX2/FMA or AVX-512 implementation. Every other type, and
+//! every CPU without those features, takes the portable scalar loop. The instruction set is
+//! detected once with `is_x86_feature_detected!` and cached.
+//!
+//! The SIMD paths sum in a different order from the scalar ones, so results agree within
+//! rounding error rather than bit for bit. For a given machine they are still deterministic.
+
+#[cfg(target_arch = "x86_64")]
+mod x86_64;
+
+use num_traits::Num;
+use std::any::TypeId;
+use std::ops::{Add, Mul, Sub};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Instruction set used by the kernels, from least to most capable. Each level includes every
+/// feature of the ones before it, so any `isa <= detected()` is safe to run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Isa {
+    Scalar,
+    Avx2Fma,
+    Avx512,
+}
+
+static DETECTED: AtomicU8 = AtomicU8::new(u8::MAX);
+
+/// Returns the most capable instruction set supported by the running CPU.
+pub(crate) fn detected() -> Isa {
+    match DETECTED.load(Ordering::Relaxed) {
+        0 => Isa::Scalar,
+        1 => Isa::Avx2Fma,
+        2 => Isa::Avx512,
+        _ => {
+            let isa = detect();
+            DETECTED.store(isa as u8, Ordering::Relaxed);
+            isa
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn detect() -> Isa {
+    // The AVX-512 level also runs the AVX2 kernels (the `f32` micro-kernel has no AVX-512
+    // version), and CPUID bits can be masked independently, so AVX2 is checked explicitly.
+    let avx2_fma = is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma");
+    if avx2_fma && is_x86_feature_detected!("avx512f") {
+        Isa::Avx512
+    } else if avx2_fma {
+        Isa::Avx2Fma
+    } else {
+        Isa::Scalar
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn detect() -> Isa {
+    Isa::Scalar
+}
+
+/// Reinterprets `x` as a slice of `U` if `T` and `U` are the same type.
+#[cfg(target_arch = "x86_64")]
+fn cast<T: 'static, U: 'static>(x: &[T]) -> Option<&[U]> {
+    if TypeId::of::<T>() == TypeId::of::<U>() {
+        // SAFETY: `T` and `U` are the same type.
+        Some(unsafe { std::slice::from_raw_parts(x.as_ptr() as *const U, x.len()) })
+    } else {
+        None
+    }
+}
+
+/// Reinterprets `x` as a mutable slice of `U` if `T` and `U` are the same type.
+#[cfg(target_arch = "x86_64")]
+fn cast_mut<T: 'static, U: 'static>(x: &mut [T]) -> Option<&mut [U]> {
+    if TypeId::of::<T>() == TypeId::of::<U>() {
+        // SAFETY: `T` and `U` are the same type.
+        Some(unsafe { std::slice::from_raw_parts_mut(x.as_mut_ptr() as *mut U, x.len()) })
+    } else {
+        None
+    }
+}
+
+/// Converts `x` to `U`, which must be the same type as `T`.
+#[cfg(target_arch = "x86_64")]
+fn value<T: Copy + 'static, U: Copy + 'static>(x: T) -> U {
+    cast::<T, U>(std::slice::from_ref(&x)).expect("mismatched types")[0]
+}
+
+/// Calls `$kernel` from the AVX-512 or AVX2 module, or evaluates to `None` for `Isa::Scalar`.
+#[cfg(target_arch = "x86_64")]
+macro_rules! simd_call {
+    ($isa:expr, $avx512:ident, $avx2:ident, $kernel:ident($($arg:expr),*)) => {
+        match $isa {
+            // SAFETY: `isa` never exceeds what `detected` reported for this CPU.
+            Isa::Avx512 => Some(unsafe { x86_64::$avx512::$kernel($($arg),*) }),
+            Isa::Avx2Fma => Some(unsafe { x86_64::$avx2::$kernel($($arg),*) }),
+            Isa::Scalar => None,
+        }
+    };
+}
+
+pub(crate) fn dot<T: Num + Copy + 'static>(x: &[T], y: &[T]) -> T {
+    dot_with(detected(), x, y)
+}
+
+#[cfg_attr(not(target_arch = "x86_64"), allow(unused_variables))]
+pub(crate) fn dot_with<T: Num + Copy + 'static>(isa: Isa, x: &[T], y: &[T]) -> T {
+    assert_eq!(x.len(), y.len());
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        let isa = isa.min(detected());
+        if let (Some(x), Some(y)) = (cast::<T, f64>(x), cast::<T, f64>(y)) {
+            if let Some(r) = simd_call!(isa, avx512_f64, avx2_f64, dot(x, y)) {
+                return value(r);
+            }
+        } else if let (Some(x), Some(y)) = (cast::<T, f32>(x), cast::<T, f32>(y)) {
+            if let Some(r) = simd_call!(isa, avx512_f32, avx2_f32, dot(x, y)) {
+                return value(r);
+            }
+        }
+    }
+
+    x.iter().zip(y).fold(T::zero(), |acc, (&a, &b)| acc + a * b)
+}
+
+/// `y += alpha * x`
+pub(crate) fn axpy<T: Num + Copy + 'static>(alpha: T, x: &[T], y: &mut [T]) {
+    axpy_with(detected(), alpha, x, y)
+}
+
+#[cfg_attr(not(target_arch = "x86_64"), allow(unused_variables))]
+pub(crate) fn axpy_with<T: Num + Copy + 'static>(isa: Isa, alpha: T, x: &[T], y: &mut [T]) {
+    assert_eq!(x.len(), y.len());
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        let isa = isa.min(detected());
+        if let Some(x) = cast::<T, f64>(x) {
+            let y = cast_mut::<T, f64>(y).unwrap();
+            if simd_call!(isa, avx512_f64, avx2_f64, axpy(value(alpha), x, y)).is_some() {
+                return;
+            }
+        } else if let Some(x) = cast::<T, f32>(x) {
+            let y = cast_mut::<T, f32>(y).unwrap();
+            if simd_call!(isa, avx512_f32, avx2_f32, axpy(value(alpha), x, y)).is_some() {
+                return;
+            }
+        }
+    }
+
+    y.iter_mut().zip(x).for_each(|(y, &x)| *y = *y + alpha * x);
+}
+
+/// `y += x`
+pub(crate) fn add_assign<T: Copy + Add<Output = T> + 'static>(y: &mut [T], x: &[T]) {
+    add_assign_with(detected(), y, x)
+}
+
+#[cfg_attr(not(target_arch = "x86_64"), allow(unused_variables))]
+pub(crate) fn add_assign_with<T: Copy + Add<Output = T> + 'static>(isa: Isa, y: &mut [T], x: &[T]) {
+    assert_eq!(x.len(), y.len());
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        let isa = isa.min(detected());
+        if let Some(x) = cast::<T, f64>(x) {
+            let y = cast_mut::<T, f64>(y).unwrap();
+            if simd_call!(isa, avx512_f64, avx2_f64, add_assign(y, x)).is_some() {
+                return;
+            }
+        } else if let Some(x) = cast::<T, f32>(x) {
+            let y = cast_mut::<T, f32>(y).unwrap();
+            if simd_call!(isa, avx512_f32, avx2_f32, add_assign(y, x)).is_some() {
+                return;
+            }
+        }
+    }
+
+    y.iter_mut().zip(x).for_each(|(y, &x)| *y = *y + x);
+}
+
+/// `y -= x`
+pub(crate) fn sub_assign<T: Copy + Sub<Output = T> + 'static>(y: &mut [T], x: &[T]) {
+    sub_assign_with(detected(), y, x)
+}
+
+#[cfg_attr(not(target_arch = "x86_64"), allow(unused_variables))]
+pub(crate) fn sub_assign_with<T: Copy + Sub<Output = T> + 'static>(isa: Isa, y: &mut [T], x: &[T]) {
+    assert_eq!(x.len(), y.len());
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        let isa = isa.min(detected());
+        if let Some(x) = cast::<T, f64>(x) {
+            let y = cast_mut::<T, f64>(y).unwrap();
+            if simd_call!(isa, avx512_f64, avx2_f64, sub_assign(y, x)).is_some() {
+                return;
+            }
+        } else if let Some(x) = cast::<T, f32>(x) {
+            let y = cast_mut::<T, f32>(y).unwrap();
+            if simd_call!(isa, avx512_f32, avx2_f32, sub_assign(y, x)).is_some() {
+                return;
+            }
+        }
+    }
+
+    y.iter_mut().zip(x).for_each(|(y, &x)| *y = *y - x);
+}
+
+/// `y *= alpha`
+pub(crate) fn scale<T: Copy + Mul<Output = T> + 'static>(y: &mut [T], alpha: T) {
+    scale_with(detected(), y, alpha)
+}
+
+#[cfg_attr(not(target_arch = "x86_64"), allow(unused_variables))]
+pub(crate) fn scale_with<T: Copy + Mul<Output = T> + 'static>(isa: Isa, y: &mut [T], alpha: T) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        let isa = isa.min(detected());
+        if let Some(y) = cast_mut::<T, f64>(y) {
+            if simd_call!(isa, avx512_f64, avx2_f64, scale(y, value(alpha))).is_some() {
+                return;
+            }
+        } else if let Some(y) = cast_mut::<T, f32>(y) {
+            if simd_call!(isa, avx512_f32, avx2_f32, scale(y, value(alpha))).is_some() {
+                return;
+            }
+        }
+    }
+
+    y.iter_mut().for_each(|y| *y = *y * alpha);
+}
+
+/// Runs a vectorized GEMM micro-kernel if one exists for `T` and `isa`. Returns `false` if the
+/// caller has to fall back to the generic kernel.
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(not(target_arch = "x86_64"), allow(unused_variables))]
+pub(crate) fn micro_kernel<T: Copy + 'static>(
+    isa: Isa,
+    kc: usize,
+    alpha: T,
+    a: &[T],
+    b: &[T],
+    c: &mut [T],
+    strides: (usize, usize),
+    valid: (usize, usize),
+) -> bool {
+    #[cfg(target_arch = "x86_64")]
+    {
+        let isa = isa.min(detected());
+        if let (Some(a), Some(b)) = (cast::<T, f64>(a), cast::<T, f64>(b)) {
+            let c = cast_mut::<T, f64>(c).unwrap();
+            let alpha = value(alpha);
+            // SAFETY: `isa` never exceeds what `detected` reported for this CPU.
+            match isa {
+                Isa::Avx512 => unsafe {
+                    x86_64::avx512_f64_micro_kernel(kc, alpha, a, b, c, strides, valid)
+                },
+                Isa::Avx2Fma => unsafe {
+                    x86_64::avx2_f64_micro_kernel(kc, alpha, a, b, c, strides, valid)
+                },
+                Isa::Scalar => return false,
+            }
+            return true;
+        } else if let (Some(a), Some(b)) = (cast::<T, f32>(a), cast::<T, f32>(b)) {
+            let c = cast_mut::<T, f32>(c).unwrap();
+            if isa == Isa::Scalar {
+                return false;
+            }
+            // SAFETY: `detect` only reports `Isa::Avx512` if AVX2 and FMA were detected as well.
+            unsafe { x86_64::avx2_f32_micro_kernel(kc, value(alpha), a, b, c, strides, valid) };
+            return true;
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColMajor, Matrix, Order, RowMajor};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn isas() -> Vec<Isa> {
+        [Isa::Scalar, Isa::Avx2Fma, Isa::Avx512]
+            .into_iter()
+            .filter(|&isa| isa <= detected())
+            .collect()
+    }
+
+    fn random<O: Order>(rows: usize, cols: usize, seed: u64) -> Matrix<f64, O> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut m: Matrix<f64, O> = Matrix::new(rows, cols).unwrap();
+        m.data
+            .iter_mut()
+            .for_each(|x| *x = rng.gen_range(-1.0..1.0));
+        m
+    }
+
+    fn single<O: Order>(m: &Matrix<f64, O>) -> Matrix<f32, O> {
+        let mut out: Matrix<f32, O> = Matrix::new(m.num_rows, m.num_cols).unwrap();
+        out.data = m.data.iter().map(|&x| x as f32).collect();
+        out
+    }
+
+    fn assert_close<T: Into<f64> + Copy>(a: &[T], b: &[T], tol: f64) {
+        assert_eq!(a.len(), b.len());
+        for (&a, &b) in a.iter().zip(b) {
+            let (a, b) = (a.into(), b.into());
+            assert!((a - b).abs() <= tol * (1.0 + a.abs()), "{} != {}", a, b);
+        }
+    }
+
+    #[test]
+    fn vector_kernels_agree_with_scalar() {
+        let x = random::<RowMajor>(7, 37, 1);
+        let y = random::<ColMajor>(7, 37, 2);
+        let (xs, ys) = (single(&x), single(&y));
+
+        for isa in isas() {
+            let dot = dot_with(isa, &x.data, &y.data);
+            assert_close(&[dot], &[dot_with(Isa::Scalar, &x.data, &y.data)], 1e-12);
+            let dot = dot_with(isa, &xs.data, &ys.data);
+            assert_close(&[dot], &[dot_with(Isa::Scalar, &xs.data, &ys.data)], 1e-4);
+
+            let (mut simd, mut scalar) = (y.data.clone(), y.data.clone());
+            axpy_with(isa, 0.5, &x.data, &mut simd);
+            axpy_with(Isa::Scalar, 0.5, &x.data, &mut scalar);
+            assert_close(&simd, &scalar, 1e-12);
+
+            let (mut simd, mut scalar) = (ys.data.clone(), ys.data.clone());
+            add_assign_with(isa, &mut simd, &xs.data);
+            add_assign_with(Isa::Scalar, &mut scalar, &xs.data);
+            assert_close(&simd, &scalar, 0.0);
+
+            sub_assign_with(isa, &mut simd, &xs.data);
+            sub_assign_with(Isa::Scalar, &mut scalar, &xs.data);
+            assert_close(&simd, &scalar, 0.0);
+
+            scale_with(isa, &mut simd, -3.0);
+            scale_with(Isa::Scalar, &mut scalar, -3.0);
+            assert_close(&simd, &scalar, 0.0);
+        }
+    }
+
+    fn gemm_with_isa<T, OA: Order, OB: Order, OC: Order>(
+        isa: Isa,
+        a: &Matrix<T, OA>,
+        b: &Matrix<T, OB>,
+        c: &mut Matrix<T, OC>,
+    ) where
+        T: Num + Copy + Send + Sync + 'static,
+    {
+        let (c_rs, c_cs) = OC::strides((c.num_rows, c.num_cols));
+        crate::gemm::gemm_with(
+            isa,
+            (a.num_rows, b.num_cols, a.num_cols),
+            T::one(),
+            a.operand(),
+            b.operand(),
+            T::zero(),
+            &mut c.data,
+            c_rs,
+            c_cs,
+        );
+    }
+
+    #[test]
+    fn micro_kernels_agree_with_scalar() {
+        let a = random::<RowMajor>(45, 300, 3);
+        let b = random::<ColMajor>(300, 23, 4);
+        let (a32, b32) = (single(&a), single(&b));
+
+        for isa in isas() {
+            let mut simd: Matrix<f64, ColMajor> = Matrix::new(45, 23).unwrap();
+            let mut scalar: Matrix<f64, ColMajor> = Matrix::new(45, 23).unwrap();
+            gemm_with_isa(isa, &a, &b, &mut simd);
+            gemm_with_isa(Isa::Scalar, &a, &b, &mut scalar);
+            assert_close(&simd.data, &scalar.data, 1e-12);
+
+            let mut simd: Matrix<f32, RowMajor> = Matrix::new(45, 23).unwrap();
+            let mut scalar: Matrix<f32, RowMajor> = Matrix::new(45, 23).unwrap();
+            gemm_with_isa(isa, &a32, &b32, &mut simd);
+            gemm_with_isa(Isa::Scalar, &a32, &b32, &mut scalar);
+            assert_close(&simd.data, &scalar.data, 1e-4);
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! AVX2/FMA and AVX-512 kernels for `f32` and `f64`.
+//!
+//! Every function here is `unsafe` because it must only be called once the matching CPU
+//! features have been detected; see `super::detected`.
+
+use crate::gemm::{MR, NR};
+use std::arch::x86_64::*;
+
+macro_rules! vector_kernels {
+    (
+        $name:ident, $feature:literal, $t:ty, $lanes:expr,
+        $load:ident, $store:ident, $set1:ident, $zero:ident,
+        $add:ident, $sub:ident, $mul:ident, $fmadd:ident
+    ) => {
+        pub(crate) mod $name {
+            use super::*;
+
+            const LANES: usize = $lanes;
+
+            #[target_feature(enable = $feature)]
+            pub(crate) unsafe fn dot(x: &[$t], y: &[$t]) -> $t {
+                let n = x.len().min(y.len());
+                let (xp, yp) = (x.as_ptr(), y.as_ptr());
+
+                // Four independent accumulators hide the latency of the fused multiply-adds.
+                let mut acc = [$zero(); 4];
+                let mut i = 0;
+                while i + 4 * LANES <= n {
+                    for (k, acc) in acc.iter_mut().enumerate() {
+                        let offset = i + k * LANES;
+                        *acc = $fmadd($load(xp.add(offset)), $load(yp.add(offset)), *acc);
+                    }
+                    i += 4 * LANES;
+                }
+                while i + LANES <= n {
+                    acc[0] = $fmadd($load(xp.add(i)), $load(yp.add(i)), acc[0]);
+                    i += LANES;
+                }
+
+                let mut lanes = [0.0; LANES];
+                $store(
+                    lanes.as_mut_ptr(),
+                    $add($add(acc[0], acc[1]), $add(acc[2], acc[3])),
+                );
+
+                let tail: $t = x[i..n].iter().zip(&y[i..n]).map(|(a, b)| a * b).sum();
+                lanes.iter().sum::<$t>() + tail
+            }
+
+            /// `y += alpha * x`
+            #[target_feature(enable = $feature)]
+            pub(crate) unsafe fn axpy(alpha: $t, x: &[$t], y: &mut [$t]) {
+                let n = x.len().min(y.len());
+                let (xp, yp) = (x.as_ptr(), y.as_mut_ptr());
+                let alpha_v = $set1(alpha);
+
+                let mut i = 0;
+                while i + LANES <= n {
+                    $store(
+                        yp.add(i),
+                        $fmadd(alpha_v, $load(xp.add(i)), $load(yp.add(i))),
+                    );
+                    i += LANES;
+                }
+
+                for (y, x) in y[i..n].iter_mut().zip(&x[i..n]) {
+                    *y += alpha * x;
+                }
+            }
+
+            #[target_feature(enable = $feature)]
+            pub(crate) unsafe fn add_assign(y: &mut [$t], x: &[$t]) {
+                let n = x.len().min(y.len());
+                let (xp, yp) = (x.as_ptr(), y.as_mut_ptr());
+
+                let mut i = 0;
+                while i + LANES <= n {
+                    $store(yp.add(i), $add($load(yp.add(i)), $load(xp.add(i))));
+                    i += LANES;
+                }
+
+                for (y, x) in y[i..n].iter_mut().zip(&x[i..n]) {
+                    *y += x;
+                }
+            }
+
+            #[target_feature(enable = $feature)]
+            pub(crate) unsafe fn sub_assign(y: &mut [$t], x: &[$t]) {
+                let n = x.len().min(y.len());
+                let (xp, yp) = (x.as_ptr(), y.as_mut_ptr());
+
+                let mut i = 0;
+                while i + LANES <= n {
+                    $store(yp.add(i), $sub($load(yp.add(i)), $load(xp.add(i))));
+                    i += LANES;
+                }
+
+                for (y, x) in y[i..n].iter_mut().zip(&x[i..n]) {
+                    *y -= x;
+                }
+            }
+
+            #[target_feature(enable = $feature)]
+            pub(crate) unsafe fn scale(y: &mut [$t], alpha: $t) {
+                let n = y.len();
+                let yp = y.as_mut_ptr();
+                let alpha_v = $set1(alpha);
+
+                let mut i = 0;
+                while i + LANES <= n {
+                    $store(yp.add(i), $mul($load(yp.add(i)), alpha_v));
+                    i += LANES;
+                }
+
+                for y in &mut y[i..] {
+                    *y *= alpha;
+                }
+            }
+        }
+    };
+}
+
+/// Generates an `MR x NR` GEMM micro-kernel with the same contract as
+/// `crate::gemm::micro_kernel`. Each column of the tile is held in `MR / LANES` registers.
+macro_rules! micro_kernel {
+    (
+        $name:ident, $feature:literal, $t:ty, $lanes:expr,
+        $load:ident, $store:ident, $set1:ident, $zero:ident,
+        $add:ident, $mul:ident, $fmadd:ident
+    ) => {
+        #[target_feature(enable = $feature)]
+        #[allow(clippy::too_many_arguments)]
+        pub(crate) unsafe fn $name(
+            kc: usize,
+            alpha: $t,
+            a: &[$t],
+            b: &[$t],
+            c: &mut [$t],
+            (c_rs, c_cs): (usize, usize),
+            (rows, cols): (usize, usize),
+        ) {
+            const LANES: usize = $lanes;
+            const V: usize = MR / LANES;
+
+            assert!(a.len() >= kc * MR && b.len() >= kc * NR);
+            let (ap, bp) = (a.as_ptr(), b.as_ptr());
+
+            let mut acc = [[$zero(); V]; NR];
+            for p in 0..kc {
+                let mut av = [$zero(); V];
+                for (v, av) in av.iter_mut().enumerate() {
+                    *av = $load(ap.add(p * MR + v * LANES));
+                }
+
+                for (jj, acc) in acc.iter_mut().enumerate() {
+                    let bv = $set1(*bp.add(p * NR + jj));
+                    for (acc, &av) in acc.iter_mut().zip(&av) {
+                        *acc = $fmadd(av, bv, *acc);
+                    }
+                }
+            }
+
+            if rows == MR && cols == NR && c_rs == 1 {
+                assert!(c.len() >= (NR - 1) * c_cs + MR);
+                let alpha_v = $set1(alpha);
+
+                for (jj, acc) in acc.iter().enumerate() {
+                    for (v, &acc) in acc.iter().enumerate() {
+                        let ptr = c.as_mut_ptr().add(jj * c_cs + v * LANES);
+                        $store(ptr, $add($load(ptr), $mul(alpha_v, acc)));
+                    }
+                }
+            } else {
+                let mut tile = [[0.0; MR]; NR];
+                for (tile, acc) in tile.iter_mut().zip(&acc) {
+                    for (v, &acc) in acc.iter().enumerate() {
+                        $store(tile.as_mut_ptr().add(v * LANES), acc);
+                    }
+                }
+
+                for (jj, tile) in tile.iter().enumerate().take(cols) {
+                    for (ii, &value) in tile.iter().enumerate().take(rows) {
+                        let x = &mut c[ii * c_rs + jj * c_cs];
+                        *x += alpha * value;
+                    }
+                }
+            }
+        }
+    };
+}
+
+vector_kernels! {
+    avx2_f64, "avx2,fma", f64, 4,
+    _mm256_loadu_pd, _mm256_storeu_pd, _mm256_set1_pd, _mm256_setzero_pd,
+    _mm256_add_pd, _mm256_sub_pd, _mm256_mul_pd, _mm256_fmadd_pd
+}
+
+vector_kernels! {
+    avx2_f32, "avx2,fma", f32, 8,
+    _mm256_loadu_ps, _mm256_storeu_ps, _mm256_set1_ps, _mm256_setzero_ps,
+    _mm256_add_ps, _mm256_sub_ps, _mm256_mul_ps, _mm256_fmadd_ps
+}
+
+vector_kernels! {
+    avx512_f64, "avx512f", f64, 8,
+    _mm512_loadu_pd, _mm512_storeu_pd, _mm512_set1_pd, _mm512_setzero_pd,
+    _mm512_add_pd, _mm512_sub_pd, _mm512_mul_pd, _mm512_fmadd_pd
+}
+
+vector_kernels! {
+    avx512_f32, "avx512f", f32, 16,
+    _mm512_loadu_ps, _mm512_storeu_ps, _mm512_set1_ps, _mm512_setzero_ps,
+    _mm512_add_ps, _mm512_sub_ps, _mm512_mul_ps, _mm512_fmadd_ps
+}
+
+micro_kernel! {
+    avx2_f64_micro_kernel, "avx2,fma", f64, 4,
+    _mm256_loadu_pd, _mm256_storeu_pd, _mm256_set1_pd, _mm256_setzero_pd,
+    _mm256_add_pd, _mm256_mul_pd, _mm256_fmadd_pd
+}
+
+micro_kernel! {
+    avx512_f64_micro_kernel, "avx512f", f64, 8,
+    _mm512_loadu_pd, _mm512_storeu_pd, _mm512_set1_pd, _mm512_setzero_pd,
+    _mm512_add_pd, _mm512_mul_pd, _mm512_fmadd_pd
+}
+
+// An `f32` column of the tile fits in a single 256-bit register, so the AVX2 kernel is also
+// used on AVX-512 hardware.
+micro_kernel! {
+    avx2_f32_micro_kernel, "avx2,fma", f32, 8,
+    _mm256_loadu_ps, _mm256_storeu_ps, _mm256_set1_ps, _mm256_setzero_ps,
+    _mm256_add_ps, _mm256_mul_ps, _mm256_fmadd_ps
+}
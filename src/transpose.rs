@@ -0,0 +1,195 @@
+//! Transpose kernels.
+//!
+//! Every kernel works on a flat buffer made of `outer` contiguous runs of `inner` elements.
+//! For `RowMajor` the runs are rows, for `ColMajor` they are columns, so the same kernel
+//! transposes either order.
+
+use crate::parallel;
+use crate::{Matrix, Order};
+use std::borrow::Cow;
+use std::marker::PhantomData;
+
+/// Edge length of the square tiles used by the blocked kernels.
+const BLOCK: usize = 32;
+
+/// Writes the transpose of `src` into `dst` one `BLOCK x BLOCK` tile at a time, so that
+/// both the reads and the strided writes stay in cache. With the `parallel` feature, the
+/// runs of `dst` are split between threads.
+pub(crate) fn transpose_into<T: Copy + Send + Sync>(
+    src: &[T],
+    outer: usize,
+    inner: usize,
+    dst: &mut [T],
+) {
+    assert_eq!(src.len(), outer * inner);
+    assert_eq!(dst.len(), outer * inner);
+
+    if dst.is_empty() {
+        return;
+    }
+
+    parallel::for_each_chunk(dst, outer, outer, |offset, dst| {
+        transpose_runs(src, outer, inner, offset / outer, dst)
+    });
+}
+
+/// Fills `dst` with the runs of the transpose starting at run `first`.
+fn transpose_runs<T: Copy>(src: &[T], outer: usize, inner: usize, first: usize, dst: &mut [T]) {
+    let count = dst.len() / outer;
+
+    for ob in (0..outer).step_by(BLOCK) {
+        let o_end = (ob + BLOCK).min(outer);
+        for ib in (0..count).step_by(BLOCK) {
+            let i_end = (ib + BLOCK).min(count);
+            for o in ob..o_end {
+                let run = &src[o * inner + first + ib..o * inner + first + i_end];
+                for (i, &value) in (ib..i_end).zip(run) {
+                    dst[i * outer + o] = value;
+                }
+            }
+        }
+    }
+}
+
+/// Returns the data of `m` laid out in storage order `O`, copying only if the orders differ.
+pub(crate) fn storage_in<T: Copy + Send + Sync, O: Order, O2: Order>(
+    m: &Matrix<T, O2>,
+) -> Cow<'_, [T]> {
+    let dims = (m.num_rows, m.num_cols);
+    let (outer, inner) = O2::storage_dims(dims);
+
+    if O::strides(dims) == O2::strides(dims) {
+        Cow::Borrowed(&m.data)
+    } else {
+        let mut data = m.data.clone();
+        transpose_into(&m.data, outer, inner, &mut data);
+        Cow::Owned(data)
+    }
+}
+
+/// Builds a matrix with storage order `O` from `data` laid out in order `O2`.
+pub(crate) fn from_storage<T: Copy + Send + Sync, O: Order, O2: Order>(
+    (num_rows, num_cols): (usize, usize),
+    data: Vec<T>,
+) -> Matrix<T, O> {
+    let m = Matrix::<T, O2> {
+        num_rows,
+        num_cols,
+        data,
+        _order: PhantomData,
+    };
+
+    let data = match storage_in::<T, O, O2>(&m) {
+        Cow::Borrowed(_) => m.data,
+        Cow::Owned(data) => data,
+    };
+
+    Matrix {
+        num_rows,
+        num_cols,
+        data,
+        _order: PhantomData,
+    }
+}
+
+/// Transposes `data` without allocating a second buffer. Afterwards `data` holds `inner`
+/// runs of `outer` elements.
+pub(crate) fn transpose_in_place<T: Copy>(data: &mut [T], outer: usize, inner: usize) {
+    assert_eq!(data.len(), outer * inner);
+
+    if outer <= 1 || inner <= 1 {
+        // A single row or column has the same layout as its transpose.
+        return;
+    }
+
+    if outer == inner {
+        square_in_place(data, outer);
+    } else {
+        cycles_in_place(data, outer, inner);
+    }
+}
+
+fn square_in_place<T: Copy>(data: &mut [T], n: usize) {
+    for ib in (0..n).step_by(BLOCK) {
+        let i_end = (ib + BLOCK).min(n);
+        for jb in (ib..n).step_by(BLOCK) {
+            let j_end = (jb + BLOCK).min(n);
+            for i in ib..i_end {
+                let j_start = if ib == jb { i + 1 } else { jb };
+                for j in j_start..j_end {
+                    data.swap(i * n + j, j * n + i);
+                }
+            }
+        }
+    }
+}
+
+/// Follows the cycles of the transpose permutation. The element at `o * inner + i` moves to
+/// `i * outer + o`; the first and last elements never move. Visited positions are tracked in
+/// a bitset, which costs one bit per element instead of a full copy of the matrix.
+fn cycles_in_place<T: Copy>(data: &mut [T], outer: usize, inner: usize) {
+    let last = data.len() - 1;
+    let mut visited = vec![0u64; data.len().div_ceil(64)];
+
+    for start in 1..last {
+        if visited[start / 64] & (1 << (start % 64)) != 0 {
+            continue;
+        }
+
+        let mut current = start;
+        let mut carried = data[start];
+        loop {
+            let next = (current % inner) * outer + current / inner;
+            visited[next / 64] |= 1 << (next % 64);
+            std::mem::swap(&mut data[next], &mut carried);
+            current = next;
+
+            if current == start {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive(src: &[usize], outer: usize, inner: usize) -> Vec<usize> {
+        let mut dst = vec![0; src.len()];
+        for o in 0..outer {
+            for i in 0..inner {
+                dst[i * outer + o] = src[o * inner + i];
+            }
+        }
+        dst
+    }
+
+    #[test]
+    fn blocked_matches_naive() {
+        for &(outer, inner) in &[(1, 1), (1, 7), (7, 1), (33, 65), (64, 64), (100, 3)] {
+            let src: Vec<usize> = (0..outer * inner).collect();
+            let mut dst = vec![0; src.len()];
+            transpose_into(&src, outer, inner, &mut dst);
+            assert_eq!(dst, naive(&src, outer, inner));
+        }
+    }
+
+    #[test]
+    fn in_place_matches_naive() {
+        for &(outer, inner) in &[
+            (2, 3),
+            (3, 2),
+            (33, 65),
+            (64, 64),
+            (70, 70),
+            (1, 9),
+            (100, 3),
+        ] {
+            let src: Vec<usize> = (0..outer * inner).collect();
+            let mut data = src.clone();
+            transpose_in_place(&mut data, outer, inner);
+            assert_eq!(data, naive(&src, outer, inner));
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use crate::{Dimensions, Matrix, Order};
+use std::marker::PhantomData;
+
+/// A borrowed, read-only matrix over existing storage.
+pub struct MatrixView<'a, T, O> {
+    pub(crate) num_rows: usize,
+    pub(crate) num_cols: usize,
+    pub(crate) data: &'a [T],
+    pub(crate) _order: PhantomData<O>,
+}
+
+impl<'a, T, O: Order> MatrixView<'a, T, O> {
+    pub fn dims(&self) -> Dimensions {
+        Dimensions {
+            rows: self.num_rows,
+            cols: self.num_cols,
+        }
+    }
+
+    pub fn to_owned(&self) -> Matrix<T, O>
+    where
+        T: Clone,
+    {
+        Matrix {
+            num_rows: self.num_rows,
+            num_cols: self.num_cols,
+            data: self.data.to_vec(),
+            _order: PhantomData,
+        }
+    }
+}
+
+impl<'a, T, O: Order> std::ops::Index<(usize, usize)> for MatrixView<'a, T, O> {
+    type Output = T;
+
+    fn index(&self, index: (usize, usize)) -> &Self::Output {
+        let idx = O::calc_index(index, (self.num_rows, self.num_cols));
+        &self.data[idx]
+    }
+}